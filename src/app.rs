@@ -2,31 +2,96 @@ use super::*;
 
 #[derive(Debug)]
 pub(crate) struct App {
-  code: String,
+  breadcrumb_area: Rect,
+  editing: Option<Draft>,
+  errors: VecDeque<(String, Instant)>,
+  file_deleted: bool,
+  history: History,
   language: TreeSitterLanguage,
-  last_reload: Option<Instant>,
+  log_scroll: usize,
+  max_text_width: usize,
   message: Option<(String, Instant)>,
   mode: Mode,
+  parse_log: ParseLog,
+  path: Option<PathBuf>,
+  pattern: Pattern,
+  previous_query: String,
+  profile: Option<QueryProfile>,
+  session: Option<(SessionStore, PathBuf)>,
+  show_errors: bool,
   show_help: bool,
+  source: Source,
   state: State,
   terminal_height: u16,
   tree: Tree,
-  watch_path: Option<PathBuf>,
+  tree_area: Rect,
+  watch: Option<WatchPaths>,
 }
 
 impl App {
-  fn draw(&self, frame: &mut Frame) {
+  const BREADCRUMB_HEIGHT: u16 = 1;
+
+  const LOG_SCROLL_STEP: usize = 10;
+
+  const MAX_ERRORS: usize = 100;
+
+  fn apply(&mut self, edit: &Edit) -> Result {
+    self.replace(edit.start_byte, &edit.old_text, &edit.new_text)
+  }
+
+  fn confirm_input(&mut self) -> Result<ControlFlow<()>> {
+    let mode = mem::take(&mut self.mode);
+
+    match mode {
+      Mode::Edit => {
+        if let Some(Draft { edit, .. }) = self
+          .editing
+          .take()
+          .filter(|draft| draft.edit.new_text != draft.edit.old_text)
+        {
+          self.history.push(edit);
+        }
+      }
+      Mode::Query if ExCommand::is_command(&self.state.ts_query) => {
+        let command = mem::replace(
+          &mut self.state.ts_query,
+          mem::take(&mut self.previous_query),
+        );
+
+        self.rerun_queries();
+
+        return self.execute_command(command.parse()?);
+      }
+      Mode::Query => self.refresh_profile(),
+      Mode::Fold | Mode::Normal | Mode::Search | Mode::Yank => {}
+    }
+
+    Ok(ControlFlow::Continue(()))
+  }
+
+  fn copy(&mut self, text: String, message: &str) -> Result {
+    if cli_clipboard::set_contents(text.clone()).is_err() {
+      Terminal::set_clipboard(&text).context("failed to copy to clipboard")?;
+    }
+
+    self.message = Some((message.to_string(), Instant::now()));
+
+    Ok(())
+  }
+
+  fn draw(&mut self, frame: &mut Frame) {
     let area = frame.area();
 
-    let tree_panel = TreePanel::new(&self.tree, &self.code, &self.state);
+    let tree_panel = TreePanel::new(&self.tree, &self.source, &self.state)
+      .max_text_width(self.max_text_width);
 
-    let info_node = self
-      .state
-      .selected
-      .and_then(|_| self.state.node(&self.tree).ok());
+    let selected = self.state.selected_nodes(&self.tree);
 
     let status_line =
-      StatusLine::new(&self.mode, &self.state, self.message.as_ref());
+      StatusLine::new(&self.mode, &self.state, self.message.as_ref())
+        .draft(self.editing.as_ref())
+        .error(self.errors.back())
+        .file_deleted(self.file_deleted);
 
     let main_area = if status_line.visible() {
       let chunks = Layout::default()
@@ -41,16 +106,62 @@ impl App {
       area
     };
 
-    if let Some(node) = info_node {
-      let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
-        .split(main_area);
+    let chunks = Layout::default()
+      .direction(Direction::Vertical)
+      .constraints([
+        Constraint::Length(Self::BREADCRUMB_HEIGHT),
+        Constraint::Min(0),
+      ])
+      .split(main_area);
 
-      frame.render_widget(tree_panel, chunks[0]);
-      frame.render_widget(InfoPanel::new(node, &self.code), chunks[1]);
-    } else {
-      frame.render_widget(tree_panel, main_area);
+    if let Ok(node) = self.state.node(&self.tree) {
+      frame.render_widget(
+        Breadcrumb::new(&self.tree, node, self.state.zoom()),
+        chunks[0],
+      );
+    }
+
+    self.breadcrumb_area = chunks[0];
+
+    let main_area = chunks[1];
+
+    let log = self.parse_log.lines();
+
+    let panels = usize::from(!selected.is_empty())
+      + usize::from(self.profile.is_some())
+      + usize::from(log.is_some())
+      + usize::from(self.show_errors);
+
+    let chunks = Layout::default()
+      .direction(Direction::Vertical)
+      .constraints(
+        iter::once(Constraint::Min(0))
+          .chain(iter::repeat_n(Constraint::Percentage(30), panels)),
+      )
+      .split(main_area);
+
+    frame.render_widget(tree_panel, chunks[0]);
+
+    self.tree_area = chunks[0];
+
+    let mut panels = chunks.iter().skip(1);
+
+    if !selected.is_empty() {
+      if let Some(&area) = panels.next() {
+        frame.render_widget(InfoPanel::new(selected, &self.source), area);
+      }
+    }
+
+    if let Some((profile, &area)) = self.profile.as_ref().zip(panels.next()) {
+      frame.render_widget(ProfilePanel::new(profile), area);
+    }
+
+    if let Some((log, &area)) = log.as_ref().zip(panels.next()) {
+      frame.render_widget(LogPanel::new(log, self.log_scroll), area);
+    }
+
+    if let Some(&area) = panels.next().filter(|_| self.show_errors) {
+      frame.render_widget(ErrorPanel::new(&self.errors), area);
     }
 
     if self.show_help {
@@ -58,66 +169,203 @@ impl App {
     }
   }
 
+  fn execute_command(&mut self, command: ExCommand) -> Result<ControlFlow<()>> {
+    match command {
+      ExCommand::Quit { force } => return Ok(self.quit(force)),
+      ExCommand::Reload { force } => {
+        if let Some(path) = self.path.clone() {
+          self.reload(path, force)?;
+        }
+      }
+      ExCommand::Set(setting) => {
+        self.message = Some((format!("Set {setting}"), Instant::now()));
+
+        setting.apply(&mut self.state.query_options);
+
+        self.rerun_queries();
+      }
+      ExCommand::Write => self.write()?,
+    }
+
+    Ok(ControlFlow::Continue(()))
+  }
+
+  /// Runs the prompt's input as the user types it, except for commands,
+  /// which only run once confirmed.
   fn execute_input(&mut self) {
     match self.mode {
-      Mode::Search => self.state.search(&self.tree, &self.code),
+      Mode::Search => self.state.search(&self.tree, &self.source),
+      Mode::Query if ExCommand::is_command(&self.state.ts_query) => {
+        self.state.clear_query_results();
+      }
       Mode::Query => {
         self
           .state
-          .execute_query(&self.language, &self.tree, &self.code);
+          .execute_query(&self.language, &self.tree, &self.source);
       }
-      Mode::Normal => unreachable!(),
+      Mode::Edit | Mode::Fold | Mode::Normal | Mode::Yank => unreachable!(),
     }
   }
 
+  pub(crate) fn fold_to_depth(&mut self, depth: usize) {
+    self.state.collapse_to_depth(&self.tree, depth);
+  }
+
   fn handle_event(&mut self, event: &Event) -> Result<ControlFlow<()>> {
+    if matches!(self.mode, Mode::Fold | Mode::Yank) {
+      self.mode = Mode::Normal;
+    }
+
     match event {
-      Event::Quit => return Ok(ControlFlow::Break(())),
+      Event::Quit => return Ok(self.quit(false)),
       Event::MoveUp => self.state.move_up(&self.tree)?,
       Event::MoveDown => self.state.move_down(&self.tree)?,
       Event::MoveLeft => self.state.move_left(&self.tree)?,
       Event::MoveRight => self.state.move_right(&self.tree)?,
       Event::ToggleSelect => self.state.toggle_select(),
       Event::ToggleCollapse => self.state.toggle_collapse(&self.tree)?,
+      Event::ScrollLeft => self.state.scroll_left(),
+      Event::ScrollRight => {
+        let max_offset = TreePanel::new(&self.tree, &self.source, &self.state)
+          .max_text_width(self.max_text_width)
+          .max_horizontal_offset(self.tree_area);
+
+        self.state.scroll_right(max_offset);
+      }
       Event::ScrollUp => self.state.scroll_up(&self.tree, self.terminal_height),
       Event::ScrollDown => {
         self.state.scroll_down(&self.tree, self.terminal_height);
       }
+      Event::CollapseAll => self.state.collapse_all(&self.tree),
+      Event::CollapseKind => self.state.collapse_kind(&self.tree)?,
+      Event::CollapseSiblings => self.state.collapse_siblings(&self.tree)?,
+      Event::CollapseToDepth(depth) => {
+        self.state.collapse_to_depth(&self.tree, *depth);
+      }
+      Event::EnterEdit => {
+        let node = self.state.node(&self.tree)?;
+
+        let text =
+          self.source.exact_text(node.byte_range()).ok_or_else(|| {
+            anyhow!(
+              "`{}` isn't valid {} and can't be edited",
+              node.kind(),
+              self.source.encoding()
+            )
+          })?;
+
+        self.editing = Some(Draft::new(text, node.start_byte()));
+
+        self.mode = Mode::Edit;
+      }
+      Event::EnterFold => self.mode = Mode::Fold,
+      Event::EnterYank => self.mode = Mode::Yank,
+      Event::ExpandAll => self.state.expand_all(),
+      Event::ExtendSelection { forward } => {
+        self.state.extend_selection(&self.tree, *forward);
+      }
+      Event::ExtendSiblings { forward } => {
+        self.state.extend_siblings(&self.tree, *forward)?;
+      }
+      Event::Focus => self.state.focus(&self.tree)?,
       Event::EnterSearch => {
         self.state.clear_search();
         self.mode = Mode::Search;
       }
       Event::EnterQuery => {
+        self.previous_query = mem::take(&mut self.state.ts_query);
         self.state.clear_query();
         self.mode = Mode::Query;
       }
       Event::FileChanged => self.handle_file_changed()?,
+      Event::FileDeleted => self.file_deleted = true,
+      Event::GrammarBuildFailed(error) => {
+        return Err(anyhow!("failed to rebuild grammar: {error}"));
+      }
+      Event::GrammarChanged => self.reload_grammar()?,
+      Event::QueryChanged => self.reload_queries(),
+      Event::GeneratePattern => {
+        let node = self.state.node(&self.tree)?;
+
+        self.previous_query = mem::replace(
+          &mut self.state.ts_query,
+          self.pattern.common(&[node]).unwrap_or_default(),
+        );
+        self.mode = Mode::Query;
+
+        self.execute_input();
+      }
       Event::JumpToMatch { forward } => self.state.jump_to_match(*forward),
       Event::MoveToTop => self.state.move_to_top(&self.tree),
       Event::MoveToBottom => self.state.move_to_bottom(&self.tree),
-      Event::Yank => {
-        let node = self.state.node(&self.tree)?;
+      // Needs the terminal, so it's handled in `run`
+      Event::OpenEditor => {}
+      Event::Yank(format) => self.yank(*format)?,
+      Event::ClearSearch => self.state.clear_search(),
+      Event::CycleProfileSort => {
+        if let Some(profile) = &mut self.profile {
+          profile.sort = profile.sort.next();
+        }
+      }
+      Event::CycleQueryScope => {
+        self.state.query_scope = self.state.query_scope.next(self.state.cursor);
 
-        let text = &self.code[node.start_byte()..node.end_byte()];
+        self.rerun_queries();
 
-        cli_clipboard::set_contents(text.to_string())
-          .map_err(|e| anyhow!("failed to copy to clipboard: {e}"))?;
+        self.message = Some((
+          format!("Queries run over the {}", self.state.query_scope),
+          Instant::now(),
+        ));
+      }
+      Event::ScrollLog { down } => {
+        self.scroll_log(if *down {
+          self.log_scroll.saturating_add(Self::LOG_SCROLL_STEP)
+        } else {
+          self.log_scroll.saturating_sub(Self::LOG_SCROLL_STEP)
+        });
+      }
+      Event::ToggleLog => {
+        self.parse_log.capture(!self.parse_log.is_capturing());
 
-        self.message =
-          Some(("Copied text to clipboard".to_string(), Instant::now()));
+        self.log_scroll = 0;
+
+        if self.parse_log.is_capturing() {
+          self.reparse(None)?;
+        }
       }
-      Event::ClearSearch => self.state.clear_search(),
+      Event::ToggleProfile => {
+        if self.profile.take().is_none() {
+          self.profile_query();
+        }
+      }
+      Event::ToggleErrors => self.show_errors = !self.show_errors,
       Event::ToggleHelp => self.show_help = !self.show_help,
-      Event::InputConfirm => self.mode = Mode::Normal,
+      Event::InputConfirm => return self.confirm_input(),
       Event::InputCancel => {
         match self.mode {
+          Mode::Edit => {
+            if let Some(draft) = self.editing.take() {
+              self.apply(&draft.edit.inverse())?;
+            }
+          }
           Mode::Search => self.state.clear_search(),
           Mode::Query => self.state.clear_query(),
-          Mode::Normal => unreachable!(),
+          Mode::Fold | Mode::Normal | Mode::Yank => {}
         }
 
         self.mode = Mode::Normal;
       }
+      Event::InputBackspace if self.mode == Mode::Edit => {
+        self.update_edit(Draft::backspace)?;
+      }
+      Event::InputChar(c) if self.mode == Mode::Edit => {
+        self.update_edit(|draft| draft.insert(*c))?;
+      }
+      Event::InputDelete => self.update_edit(Draft::delete)?,
+      Event::InputEnd => self.update_edit(Draft::end)?,
+      Event::InputHome => self.update_edit(Draft::home)?,
+      Event::InputLeft => self.update_edit(Draft::left)?,
+      Event::InputRight => self.update_edit(Draft::right)?,
       Event::InputBackspace => {
         self.input_buffer_mut().pop();
         self.execute_input();
@@ -126,8 +374,37 @@ impl App {
         self.input_buffer_mut().push(*c);
         self.execute_input();
       }
-      Event::Click { row } => {
-        if let Some(id) = self.state.node_at_row(&self.tree, *row) {
+      Event::Redo => match self.history.redo() {
+        Some(edit) => self.apply(&edit)?,
+        None => {
+          self.message =
+            Some(("Already at newest change".into(), Instant::now()));
+        }
+      },
+      Event::Undo => match self.history.undo() {
+        Some(edit) => self.apply(&edit)?,
+        None => {
+          self.message =
+            Some(("Already at oldest change".into(), Instant::now()));
+        }
+      },
+      Event::ZoomIn => self.state.zoom_in(&self.tree)?,
+      Event::ZoomOut => self.state.zoom_out(&self.tree),
+      Event::Click { column, row } => {
+        let area = self.breadcrumb_area;
+
+        let id = if area.contains(Position::new(*column, *row)) {
+          self.state.node(&self.tree).ok().and_then(|node| {
+            Breadcrumb::new(&self.tree, node, self.state.zoom())
+              .node_at_column(column - area.x, area.width)
+          })
+        } else if let Some(row) = row.checked_sub(Self::BREADCRUMB_HEIGHT) {
+          self.state.node_at_row(&self.tree, row)
+        } else {
+          None
+        };
+
+        if let Some(id) = id {
           self.state.cursor = id;
         }
       }
@@ -137,81 +414,415 @@ impl App {
   }
 
   fn handle_file_changed(&mut self) -> Result {
-    if self
-      .last_reload
-      .is_some_and(|t| t.elapsed() < Duration::from_millis(100))
-    {
-      return Ok(());
-    }
-
-    let Some(path) = &self.watch_path else {
+    let Some(path) = self.watch.as_ref().and_then(|watch| watch.source.clone())
+    else {
       return Ok(());
     };
 
-    let cursor_byte = self.state.node(&self.tree).ok().map(|n| n.start_byte());
-
-    self.reload_source(path.clone())?;
-
-    let new_cursor = cursor_byte
-      .and_then(|offset| self.tree.root_node().find_at_byte(offset))
-      .unwrap_or_else(|| self.tree.root_node().id());
-
-    self.state.reconcile(new_cursor);
-
-    if !self.state.ts_query.is_empty() {
-      self
-        .state
-        .execute_query(&self.language, &self.tree, &self.code);
-    }
-
-    if !self.state.search_query.is_empty() {
-      self.state.search(&self.tree, &self.code);
-    }
-
-    self.last_reload = Some(Instant::now());
-    self.message = Some(("File reloaded".to_string(), Instant::now()));
-
-    Ok(())
+    self.reload(path, false)
   }
 
   fn input_buffer_mut(&mut self) -> &mut String {
     match self.mode {
       Mode::Search => &mut self.state.search_query,
       Mode::Query => &mut self.state.ts_query,
-      Mode::Normal => unreachable!(),
+      Mode::Edit | Mode::Fold | Mode::Normal | Mode::Yank => unreachable!(),
     }
   }
 
+  /// Whether the source has edits that haven't been written, counting the
+  /// one being typed.
+  fn modified(&self) -> bool {
+    self
+      .editing
+      .as_ref()
+      .is_some_and(|draft| draft.edit.new_text != draft.edit.old_text)
+      || self.history.is_modified()
+  }
+
   pub(crate) fn new(
-    code: String,
+    source: Source,
     tree: Tree,
     language: TreeSitterLanguage,
-    watch_path: Option<PathBuf>,
+    path: Option<PathBuf>,
+    watch: Option<WatchPaths>,
   ) -> Self {
     Self {
-      last_reload: None,
+      breadcrumb_area: Rect::default(),
+      editing: None,
+      errors: VecDeque::new(),
+      file_deleted: false,
+      history: History::default(),
+      log_scroll: 0,
+      max_text_width: TreePanel::DEFAULT_MAX_TEXT_WIDTH,
       message: None,
       mode: Mode::default(),
+      parse_log: ParseLog::default(),
+      path,
+      pattern: Pattern::default(),
+      previous_query: String::new(),
+      profile: None,
+      session: None,
+      show_errors: false,
       show_help: false,
       state: State::new(tree.root_node().id()),
       terminal_height: 0,
-      code,
+      source,
       language,
       tree,
-      watch_path,
+      tree_area: Rect::default(),
+      watch,
+    }
+  }
+
+  fn open_editor(&mut self, terminal: &mut Terminal, input: &Input) -> Result {
+    let Some(path) = self.path.clone() else {
+      self.message = Some(("No file to open".into(), Instant::now()));
+      return Ok(());
+    };
+
+    let position = match self.state.node(&self.tree) {
+      Ok(node) => Point {
+        row: node.start_position().row,
+        column: self.source.char_column(node.start_byte()),
+      },
+      Err(error) => {
+        self.report(&error);
+        return Ok(());
+      }
+    };
+
+    let mut command = match Editor::from_env() {
+      Ok(editor) => editor.command(&path, position),
+      Err(error) => {
+        self.report(&error);
+        return Ok(());
+      }
+    };
+
+    // Failing to suspend or restore the terminal ends the session, but the
+    // editor itself failing doesn't.
+    let status = input.pause(|| terminal.suspend(|| command.status()))?;
+
+    match status {
+      Ok(status) if !status.success() => {
+        self.message =
+          Some((format!("Editor exited with {status}"), Instant::now()));
+      }
+      Ok(_) => {}
+      Err(error) => {
+        self.report(&Error::new(error).context("failed to launch editor"));
+        return Ok(());
+      }
+    }
+
+    if let Err(error) = self.reload(path, false) {
+      self.report(&error);
+    }
+
+    Ok(())
+  }
+
+  /// Profiles the current query, showing the profile panel, or explains why
+  /// it can't be profiled.
+  fn profile_query(&mut self) {
+    if self.state.ts_query.is_empty() {
+      self.message = Some((
+        "Enter a query with `:` to profile it".into(),
+        Instant::now(),
+      ));
+      return;
+    }
+
+    let sort = self
+      .profile
+      .as_ref()
+      .map_or_else(ProfileSort::default, |profile| profile.sort);
+
+    match QueryProfile::new(
+      &self.language,
+      &self.state.ts_query,
+      &self.tree,
+      &self.source,
+      &self.state.query_options,
+    ) {
+      Ok(profile) => self.profile = Some(QueryProfile { sort, ..profile }),
+      Err(error) => {
+        self.profile = None;
+        self.message =
+          Some((format!("Failed to profile query: {error}"), Instant::now()));
+      }
     }
   }
 
-  fn reload_source(&mut self, path: PathBuf) -> Result {
-    self.code = fs::read_to_string(&path)?;
+  /// Stops the session unless there are unsaved edits and `force` isn't set.
+  fn quit(&mut self, force: bool) -> ControlFlow<()> {
+    if self.modified() && !force {
+      self.message = Some((
+        "Unsaved edits, `:w` to write them or `:q!` to discard them".into(),
+        Instant::now(),
+      ));
+
+      return ControlFlow::Continue(());
+    }
+
+    ControlFlow::Break(())
+  }
+
+  /// Rebuilds the grammar library from its source directory in the
+  /// background. The rebuilt library is picked up by its own watch, so only
+  /// a failed build is sent back.
+  fn rebuild_grammar(&mut self, tx: &Sender<ChannelEvent>) {
+    let Some((directory, library)) = self
+      .watch
+      .as_ref()
+      .and_then(|watch| watch.grammar_dir.clone().zip(watch.grammar.clone()))
+    else {
+      return;
+    };
+
+    self.message = Some(("Rebuilding grammar".into(), Instant::now()));
+
+    let tx = tx.clone();
+
+    thread::spawn(move || {
+      if let Err(error) = Grammar::build(&directory, &library) {
+        tx.send(ChannelEvent::GrammarBuildFailed(format!("{error:#}")))
+          .ok();
+      }
+    });
+  }
+
+  /// Re-profiles the query if the profile panel is open.
+  fn refresh_profile(&mut self) {
+    if self.profile.is_some() {
+      self.profile_query();
+    }
+  }
+
+  /// Re-reads the file from disk. Unsaved edits are kept unless `force` is
+  /// set, so a change on disk never silently throws them away.
+  fn reload(&mut self, path: PathBuf, force: bool) -> Result {
+    let source = match fs::read(&path) {
+      Ok(bytes) => self.source.reload(bytes),
+      Err(error) if error.kind() == io::ErrorKind::NotFound => {
+        self.file_deleted = true;
+        return Ok(());
+      }
+      Err(error) => {
+        return Err(
+          Error::new(error)
+            .context(format!("failed to reload `{}`", path.display())),
+        );
+      }
+    };
+
+    let restored = mem::take(&mut self.file_deleted);
+
+    if source == self.source {
+      if restored {
+        self.message = Some(("File restored".into(), Instant::now()));
+      }
+
+      return Ok(());
+    }
+
+    if self.modified() && !force {
+      return Err(anyhow!(
+        "`{}` changed on disk, keeping unsaved edits (`:e!` to reload it)",
+        path.display()
+      ));
+    }
+
+    self.source = source;
+
+    self.editing = None;
+    self.history.clear();
+
+    if self.mode == Mode::Edit {
+      self.mode = Mode::Normal;
+    }
+
+    self.reparse(None)?;
+
+    self.message = Some(("File reloaded".to_string(), Instant::now()));
+
+    Ok(())
+  }
+
+  /// Swaps in a rebuilt grammar library and reparses with it. A library that
+  /// fails to load is usually still being written, so the error is only shown.
+  fn reload_grammar(&mut self) -> Result {
+    let Some(path) =
+      self.watch.as_ref().and_then(|watch| watch.grammar.clone())
+    else {
+      return Ok(());
+    };
+
+    match Grammar::Library(path).reload() {
+      Ok(language) => {
+        self.language = language;
+
+        self.reparse(None)?;
+
+        self.message = Some(("Grammar reloaded".into(), Instant::now()));
+      }
+      Err(error) => self.report(&error),
+    }
+
+    Ok(())
+  }
+
+  fn reload_queries(&mut self) {
+    let Some(watch) = &self.watch else {
+      return;
+    };
+
+    let source = watch
+      .queries
+      .iter()
+      .map(fs::read_to_string)
+      .collect::<io::Result<Vec<_>>>();
+
+    match source {
+      Ok(source) => {
+        let source = source.join("\n");
+
+        if source != self.state.ts_query {
+          self.set_query(&source);
+          self.refresh_profile();
+
+          self.message = Some(("Query reloaded".into(), Instant::now()));
+        }
+      }
+      Err(error) => {
+        self.report(&Error::new(error).context("failed to reload query"));
+      }
+    }
+  }
+
+  /// Reparses the source, reusing the old tree when given the edit that
+  /// produced it, and carries the cursor, folds, selection, zoom and query
+  /// scope over.
+  fn reparse(&mut self, edit: Option<&InputEdit>) -> Result {
+    let mut session = Session::capture(&self.state, &self.tree);
+
+    let cursor_byte = self.state.node(&self.tree).ok().map(|n| n.start_byte());
+
+    let old_tree = edit.map(|edit| {
+      session.edit(edit);
+
+      let mut tree = self.tree.clone();
+      tree.edit(edit);
+      tree
+    });
 
     let mut parser = Parser::new();
     parser.set_language(&self.language)?;
 
-    self.tree = parser
-      .parse(&self.code, None)
+    self.parse_log.attach(&mut parser)?;
+
+    self.tree = self
+      .source
+      .parse(&mut parser, old_tree.as_ref())
       .ok_or_else(|| anyhow!("failed to parse code"))?;
 
+    self.scroll_log(0);
+
+    let new_cursor = cursor_byte
+      .and_then(|offset| self.tree.root_node().find_at_byte(offset))
+      .unwrap_or_else(|| self.tree.root_node().id());
+
+    self.state.reconcile(new_cursor);
+
+    session.restore_query_scope(&mut self.state, &self.tree);
+
+    self.rerun_queries();
+
+    session.restore(&mut self.state, &self.tree);
+
+    Ok(())
+  }
+
+  /// Replaces `old_text` at `start_byte` with `new_text`, encoding both to
+  /// find where they end.
+  fn replace(
+    &mut self,
+    start_byte: usize,
+    old_text: &str,
+    new_text: &str,
+  ) -> Result {
+    let old_end_byte =
+      start_byte + self.source.encoding().encode(old_text)?.len();
+
+    let start_position = self.source.point(start_byte);
+    let old_end_position = self.source.point(old_end_byte);
+
+    let new_end_byte =
+      self.source.replace(start_byte..old_end_byte, new_text)?;
+
+    self.reparse(Some(&InputEdit {
+      start_byte,
+      old_end_byte,
+      new_end_byte,
+      start_position,
+      old_end_position,
+      new_end_position: self.source.point(new_end_byte),
+    }))?;
+
+    if let Some(node) = self
+      .tree
+      .root_node()
+      .descendant_for_byte_range(start_byte, new_end_byte)
+    {
+      self.state.cursor = node.id();
+    }
+
+    Ok(())
+  }
+
+  /// Records an error that doesn't need to end the session, showing it in
+  /// the status line and keeping it in the error history.
+  fn report(&mut self, error: &Error) {
+    if self.errors.len() == Self::MAX_ERRORS {
+      self.errors.pop_front();
+    }
+
+    self
+      .errors
+      .push_back((format!("{error:#}"), Instant::now()));
+  }
+
+  fn rerun_queries(&mut self) {
+    if !self.state.ts_query.is_empty() {
+      self
+        .state
+        .execute_query(&self.language, &self.tree, &self.source);
+    }
+
+    if !self.state.search_query.is_empty() {
+      self.state.search(&self.tree, &self.source);
+    }
+
+    self.refresh_profile();
+  }
+
+  pub(crate) fn restore_session(
+    &mut self,
+    store: SessionStore,
+    path: PathBuf,
+  ) -> Result {
+    if let Some(session) = store.load(&path)? {
+      self.state.search_query.clone_from(&session.search_query);
+      self.state.ts_query.clone_from(&session.ts_query);
+
+      self.rerun_queries();
+
+      session.restore(&mut self.state, &self.tree);
+    }
+
+    self.session = Some((store, path));
+
     Ok(())
   }
 
@@ -220,22 +831,12 @@ impl App {
 
     let (tx, rx) = channel();
 
-    let crossterm_tx = tx.clone();
-
-    thread::spawn(move || loop {
-      if crossterm::event::poll(Duration::from_millis(100)).unwrap_or(false) {
-        if let Ok(event) = crossterm::event::read() {
-          if crossterm_tx.send(ChannelEvent::Crossterm(event)).is_err() {
-            break;
-          }
-        }
-      }
-    });
+    let input = Input::new(tx.clone());
 
     let _watcher = self
-      .watch_path
+      .watch
       .as_ref()
-      .map(|path| Watcher::new(path, &tx))
+      .map(|watch| Watcher::new(watch, &tx))
       .transpose()?;
 
     loop {
@@ -246,6 +847,12 @@ impl App {
           .state
           .ensure_cursor_in_view(&self.tree, self.terminal_height);
 
+        self.state.refresh_viewport_query(
+          &self.language,
+          &self.tree,
+          &self.source,
+        );
+
         self.draw(f);
       })?;
 
@@ -255,6 +862,11 @@ impl App {
         .and_then(|(_, t)| {
           StatusLine::MESSAGE_DURATION.checked_sub(t.elapsed())
         })
+        .into_iter()
+        .chain(self.errors.back().and_then(|(_, t)| {
+          StatusLine::ERROR_DURATION.checked_sub(t.elapsed())
+        }))
+        .min()
         .unwrap_or(Duration::from_secs(60));
 
       match rx.recv_timeout(timeout) {
@@ -264,10 +876,22 @@ impl App {
               Event::from_crossterm(&ct, &self.mode)
             }
             ChannelEvent::FileChanged => Some(Event::FileChanged),
+            ChannelEvent::FileDeleted => Some(Event::FileDeleted),
+            ChannelEvent::GrammarBuildFailed(error) => {
+              Some(Event::GrammarBuildFailed(error))
+            }
+            ChannelEvent::GrammarChanged => Some(Event::GrammarChanged),
+            ChannelEvent::GrammarSourceChanged => {
+              self.rebuild_grammar(&tx);
+              None
+            }
+            ChannelEvent::QueryChanged => Some(Event::QueryChanged),
           };
 
           if let Some(event) = event {
-            if self.handle_event(&event)?.is_break() {
+            if event == Event::OpenEditor {
+              self.open_editor(&mut terminal, &input)?;
+            } else if self.update(&event).is_break() {
               break;
             }
           }
@@ -277,14 +901,665 @@ impl App {
       }
     }
 
+    // Restore the terminal first so the warning isn't lost on the alternate
+    // screen.
+    drop(terminal);
+
+    if let Some((store, path)) = &self.session {
+      if let Err(error) =
+        store.save(path, Session::capture(&self.state, &self.tree))
+      {
+        eprintln!("warning: failed to save session: {error:#}");
+      }
+    }
+
     Ok(())
   }
 
+  /// Scrolls the parse log panel to `offset`, keeping its last line visible.
+  fn scroll_log(&mut self, offset: usize) {
+    self.log_scroll = self
+      .parse_log
+      .lines()
+      .map_or(0, |lines| offset.min(lines.len().saturating_sub(1)));
+  }
+
+  pub(crate) fn set_max_text_width(&mut self, max_text_width: usize) {
+    self.max_text_width = max_text_width;
+  }
+
+  pub(crate) fn set_parse_log(&mut self, parse_log: ParseLog) {
+    self.parse_log = parse_log;
+  }
+
+  pub(crate) fn set_pattern(&mut self, pattern: Pattern) {
+    self.pattern = pattern;
+  }
+
   pub(crate) fn set_query(&mut self, query_source: &str) {
     self.state.ts_query = query_source.to_string();
 
     self
       .state
-      .execute_query(&self.language, &self.tree, &self.code);
+      .execute_query(&self.language, &self.tree, &self.source);
+  }
+
+  pub(crate) fn set_query_options(&mut self, options: QueryOptions) {
+    self.state.query_options = options;
+  }
+
+  /// Handles `event`, reporting errors instead of returning them, since the
+  /// only errors that should end the session come from the terminal.
+  fn update(&mut self, event: &Event) -> ControlFlow<()> {
+    self.handle_event(event).unwrap_or_else(|error| {
+      self.report(&error);
+      ControlFlow::Continue(())
+    })
+  }
+
+  /// Applies `f` to the edit being typed, replacing its text in the source
+  /// as it changes.
+  fn update_edit(&mut self, f: impl FnOnce(&mut Draft)) -> Result {
+    let Some(mut draft) = self.editing.clone() else {
+      return Ok(());
+    };
+
+    let old_text = draft.edit.new_text.clone();
+
+    f(&mut draft);
+
+    if draft.edit.new_text != old_text {
+      self.replace(draft.edit.start_byte, &old_text, &draft.edit.new_text)?;
+    }
+
+    self.editing = Some(draft);
+
+    Ok(())
+  }
+
+  fn write(&mut self) -> Result {
+    let Some(path) = &self.path else {
+      self.message = Some(("No file to write".into(), Instant::now()));
+      return Ok(());
+    };
+
+    fs::write(path, self.source.bytes())
+      .with_context(|| format!("failed to write `{}`", path.display()))?;
+
+    self.history.mark_saved();
+
+    self.message = Some((format!("Wrote {}", path.display()), Instant::now()));
+
+    Ok(())
+  }
+
+  fn yank(&mut self, format: YankFormat) -> Result {
+    let mut nodes = self.state.selected_nodes(&self.tree);
+
+    if nodes.is_empty() {
+      nodes.push(self.state.node(&self.tree)?);
+    }
+
+    let lines = |f: &dyn Fn(Node) -> Result<String>| -> Result<String> {
+      Ok(
+        nodes
+          .iter()
+          .map(|&node| f(node))
+          .collect::<Result<Vec<_>>>()?
+          .join("\n"),
+      )
+    };
+
+    let text = match format {
+      YankFormat::Ancestors => lines(&|node| {
+        Ok(Breadcrumb::new(&self.tree, node, self.state.zoom()).query_pattern())
+      })?,
+      YankFormat::ByteRange => lines(&|node| {
+        Ok(format!("{}..{}", node.start_byte(), node.end_byte()))
+      })?,
+      YankFormat::Json => lines(&|node| YankFormat::json(node, &self.source))?,
+      YankFormat::Kind => lines(&|node| Ok(node.kind().to_string()))?,
+      YankFormat::Pattern => self.pattern.common(&nodes).unwrap_or_default(),
+      YankFormat::Position => {
+        lines(&|node| Ok(YankFormat::position(node, self.path.as_deref())))?
+      }
+      YankFormat::Sexp => lines(&|node| Ok(node.sexp(&self.source)))?,
+      YankFormat::Text => {
+        lines(&|node| Ok(self.source.text(node.byte_range()).into_owned()))?
+      }
+    };
+
+    let message = match nodes.len() {
+      1 => format!("Copied {format} to clipboard"),
+      count => format!("Copied {format} of {count} nodes to clipboard"),
+    };
+
+    self.copy(text, &message)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn arguments_set_fold_and_pattern_depth() {
+    let code = "fn foo() { bar; }";
+
+    let tree = parse(code);
+
+    let function = tree.root_node().child(0).unwrap().id();
+
+    let app = Arguments::try_parse_from([
+      "axil",
+      "--interactive",
+      "--collapse-depth",
+      "1",
+      "--pattern-depth",
+      "0",
+    ])
+    .unwrap()
+    .app(
+      code.into(),
+      tree,
+      tree_sitter_rust::LANGUAGE.into(),
+      None,
+      None,
+    );
+
+    assert_eq!(app.state.collapsed_nodes(), &HashSet::from([function]));
+
+    assert_eq!(
+      app
+        .pattern
+        .common(&[app.tree.root_node().child(0).unwrap()]),
+      Some("(function_item) @node".into())
+    );
+  }
+
+  #[test]
+  fn click_breadcrumb_offset_by_layout() {
+    let mut app = new("fn foo() {}");
+
+    let function = app.tree.root_node().child(0).unwrap();
+
+    let (function, name) = (function.id(), function.child(1).unwrap().id());
+
+    app.state.cursor = name;
+    app.breadcrumb_area = Rect::new(4, 0, 76, 1);
+
+    assert!(app
+      .handle_event(&Event::Click { column: 2, row: 0 })
+      .unwrap()
+      .is_continue());
+
+    assert_eq!(app.state.cursor, name);
+
+    assert!(app
+      .handle_event(&Event::Click { column: 18, row: 0 })
+      .unwrap()
+      .is_continue());
+
+    assert_eq!(app.state.cursor, function);
+  }
+
+  #[test]
+  fn deleted_file_is_kept_until_it_comes_back() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let path = tempdir.path().join("foo.rs");
+
+    let mut app = new("fn foo() {}");
+
+    app.watch = Some(WatchPaths {
+      source: Some(path.clone()),
+      ..WatchPaths::default()
+    });
+
+    send(&mut app, Event::FileDeleted);
+
+    assert!(app.file_deleted);
+
+    send(&mut app, Event::FileChanged);
+
+    assert!(app.file_deleted);
+    assert_eq!(app.source.bytes(), b"fn foo() {}");
+
+    fs::write(&path, "fn bar() {}").unwrap();
+
+    send(&mut app, Event::FileChanged);
+
+    assert!(!app.file_deleted);
+    assert_eq!(app.source.bytes(), b"fn bar() {}");
+  }
+
+  #[test]
+  fn edit_cancel_reverts() {
+    let mut app = new("fn foo() {}");
+
+    app.state.cursor = app
+      .tree
+      .root_node()
+      .child(0)
+      .unwrap()
+      .child(1)
+      .unwrap()
+      .id();
+
+    send(&mut app, Event::EnterEdit);
+    send(&mut app, Event::InputChar('o'));
+
+    assert_eq!(app.source.bytes(), b"fn fooo() {}");
+
+    send(&mut app, Event::InputCancel);
+
+    assert_eq!(app.source.bytes(), b"fn foo() {}");
+    assert_eq!(app.mode, Mode::Normal);
+    assert_eq!(app.state.node(&app.tree).unwrap().kind(), "identifier");
+  }
+
+  #[test]
+  fn edit_moves_cursor() {
+    let mut app = new("fn foo() {}");
+
+    app.state.cursor = app
+      .tree
+      .root_node()
+      .child(0)
+      .unwrap()
+      .child(1)
+      .unwrap()
+      .id();
+
+    send(&mut app, Event::EnterEdit);
+    send(&mut app, Event::InputHome);
+    send(&mut app, Event::InputDelete);
+    send(&mut app, Event::InputChar('b'));
+    send(&mut app, Event::InputRight);
+    send(&mut app, Event::InputDelete);
+    send(&mut app, Event::InputChar('x'));
+    send(&mut app, Event::InputEnd);
+    send(&mut app, Event::InputLeft);
+    send(&mut app, Event::InputChar('_'));
+    send(&mut app, Event::InputConfirm);
+
+    assert_eq!(app.source.bytes(), b"fn bo_x() {}");
+  }
+
+  #[test]
+  fn edit_reparses_and_undoes() {
+    let mut app = new("fn foo() {}");
+
+    app.state.cursor = app
+      .tree
+      .root_node()
+      .child(0)
+      .unwrap()
+      .child(1)
+      .unwrap()
+      .id();
+
+    send(&mut app, Event::EnterEdit);
+
+    for _ in 0..3 {
+      send(&mut app, Event::InputBackspace);
+    }
+
+    for c in "bar".chars() {
+      send(&mut app, Event::InputChar(c));
+    }
+
+    send(&mut app, Event::InputConfirm);
+
+    assert_eq!(app.source.bytes(), b"fn bar() {}");
+    assert_eq!(
+      app.tree.root_node().to_sexp(),
+      new("fn bar() {}").tree.root_node().to_sexp()
+    );
+    assert_eq!(app.state.node(&app.tree).unwrap().kind(), "identifier");
+
+    send(&mut app, Event::Undo);
+    assert_eq!(app.source.bytes(), b"fn foo() {}");
+
+    send(&mut app, Event::Redo);
+    assert_eq!(app.source.bytes(), b"fn bar() {}");
+
+    send(&mut app, Event::Redo);
+    assert_eq!(app.source.bytes(), b"fn bar() {}");
+  }
+
+  #[test]
+  fn edit_writes_source_encoding() {
+    let utf16le = |text| Encoding::Utf16Le.encode(text).unwrap();
+
+    let mut app = new(Source::new(utf16le("fn foo() {}"), None));
+
+    app.state.cursor = app
+      .tree
+      .root_node()
+      .child(0)
+      .unwrap()
+      .child(1)
+      .unwrap()
+      .id();
+
+    send(&mut app, Event::EnterEdit);
+    send(&mut app, Event::InputChar('ö'));
+
+    assert_eq!(app.source.bytes(), utf16le("fn fooö() {}"));
+
+    let node = app.state.node(&app.tree).unwrap();
+
+    assert_eq!(node.kind(), "identifier");
+    assert_eq!(node.byte_range(), 6..14);
+    assert_eq!(node.end_position(), Point { row: 0, column: 14 });
+
+    send(&mut app, Event::InputBackspace);
+    send(&mut app, Event::InputConfirm);
+
+    assert_eq!(app.source.bytes(), utf16le("fn foo() {}"));
+  }
+
+  #[test]
+  fn ex_command_keeps_query() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let path = tempdir.path().join("foo.rs");
+
+    fs::write(&path, "fn foo() {}").unwrap();
+
+    let mut app = new("fn foo() {}");
+
+    app.path = Some(path.clone());
+
+    app.set_query("(identifier) @id");
+
+    send(&mut app, Event::EnterQuery);
+    send(&mut app, Event::InputChar('w'));
+
+    assert_eq!(app.state.ts_query_error, None);
+    assert!(app.state.ts_query_matches.is_empty());
+
+    send(&mut app, Event::InputConfirm);
+
+    assert_eq!(app.mode, Mode::Normal);
+    assert_eq!(app.state.ts_query, "(identifier) @id");
+    assert_eq!(app.state.ts_query_matches.len(), 1);
+
+    send(&mut app, Event::EnterQuery);
+
+    for c in "bogus".chars() {
+      send(&mut app, Event::InputChar(c));
+    }
+
+    assert!(app.update(&Event::InputConfirm).is_continue());
+    assert_eq!(app.errors[0].0, "unknown command `bogus`");
+    assert_eq!(app.state.ts_query, "(identifier) @id");
+  }
+
+  #[test]
+  fn failed_grammar_build_is_reported() {
+    let mut app = new("fn foo() {}");
+
+    assert!(app
+      .update(&Event::GrammarBuildFailed(
+        "`tree-sitter generate` failed".into()
+      ))
+      .is_continue());
+
+    assert_eq!(
+      app.errors[0].0,
+      "failed to rebuild grammar: `tree-sitter generate` failed"
+    );
+  }
+
+  #[test]
+  fn failed_reload_is_reported() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let path = tempdir.path().join("foo.rs");
+
+    fs::create_dir(&path).unwrap();
+
+    let mut app = new("fn foo() {}");
+
+    app.watch = Some(WatchPaths {
+      source: Some(path.clone()),
+      ..WatchPaths::default()
+    });
+
+    assert!(app.update(&Event::FileChanged).is_continue());
+
+    assert_eq!(app.source.bytes(), b"fn foo() {}");
+    assert_eq!(app.errors.len(), 1);
+    assert!(app.errors[0]
+      .0
+      .starts_with(&format!("failed to reload `{}`: ", path.display())));
+
+    send(&mut app, Event::ToggleErrors);
+
+    assert!(app.show_errors);
+  }
+
+  #[test]
+  fn invalid_text_is_not_editable() {
+    let mut app = new(Source::new(
+      b"// caf\xe9\nfn foo() {}".to_vec(),
+      Some(Encoding::Utf8),
+    ));
+
+    app.state.cursor = app.tree.root_node().child(0).unwrap().id();
+
+    assert!(app.update(&Event::EnterEdit).is_continue());
+
+    assert_eq!(app.mode, Mode::Normal);
+    assert_eq!(
+      app.errors[0].0,
+      "`line_comment` isn't valid utf-8 and can't be edited"
+    );
+  }
+
+  fn new(source: impl Into<Source>) -> App {
+    let source = source.into();
+
+    let language: TreeSitterLanguage = tree_sitter_rust::LANGUAGE.into();
+
+    let mut parser = Parser::new();
+
+    parser.set_language(&language).unwrap();
+
+    let tree = source.parse(&mut parser, None).unwrap();
+
+    App::new(source, tree, language, None, None)
+  }
+
+  #[test]
+  fn quit_and_reload_keep_unsaved_edits() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let path = tempdir.path().join("foo.rs");
+
+    fs::write(&path, "fn foo() {}").unwrap();
+
+    let mut app = new("fn foo() {}");
+
+    app.path = Some(path.clone());
+
+    app.watch = Some(WatchPaths {
+      source: Some(path.clone()),
+      ..WatchPaths::default()
+    });
+
+    app.state.cursor = app.tree.root_node().child(0).unwrap().id();
+
+    send(&mut app, Event::EnterEdit);
+    send(&mut app, Event::InputChar(' '));
+    send(&mut app, Event::InputConfirm);
+
+    send(&mut app, Event::Quit);
+
+    fs::write(&path, "fn bar() {}").unwrap();
+
+    assert!(app.update(&Event::FileChanged).is_continue());
+
+    assert_eq!(app.source.bytes(), b"fn foo() {} ");
+    assert!(app.errors[0].0.contains("keeping unsaved edits"));
+
+    send(&mut app, Event::Undo);
+    send(&mut app, Event::FileChanged);
+
+    assert_eq!(app.source.bytes(), b"fn bar() {}");
+
+    send(&mut app, Event::EnterEdit);
+    send(&mut app, Event::InputChar(' '));
+    send(&mut app, Event::InputConfirm);
+    send(&mut app, Event::EnterQuery);
+    send(&mut app, Event::InputChar('w'));
+    send(&mut app, Event::InputConfirm);
+
+    assert_eq!(fs::read(&path).unwrap(), b"fn bar() {} ");
+    assert!(app.handle_event(&Event::Quit).unwrap().is_break());
+  }
+
+  #[test]
+  fn reload_keeps_query_scope() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let path = tempdir.path().join("foo.rs");
+
+    let mut app = new("fn foo() { bar(); }\nfn baz() {}");
+
+    app.watch = Some(WatchPaths {
+      source: Some(path.clone()),
+      ..WatchPaths::default()
+    });
+
+    let function = app.tree.root_node().child(0).unwrap().id();
+
+    app.state.query_scope = QueryScope::Subtree(function);
+
+    app.set_query("(identifier) @id");
+
+    fs::write(&path, "fn foo() { bar(); qux(); }\nfn baz() {}").unwrap();
+
+    send(&mut app, Event::FileChanged);
+
+    let function = app.tree.root_node().child(0).unwrap().id();
+
+    assert_eq!(app.state.query_scope, QueryScope::Subtree(function));
+    assert_eq!(app.state.ts_query_matches.len(), 3);
+
+    app.state.query_scope = QueryScope::Viewport;
+
+    fs::write(&path, "fn foo() {}\nfn baz() {}").unwrap();
+
+    send(&mut app, Event::FileChanged);
+
+    assert_eq!(app.state.query_scope, QueryScope::Viewport);
+
+    app.state.query_scope =
+      QueryScope::Subtree(app.tree.root_node().child(1).unwrap().id());
+
+    fs::write(&path, "fn foo() {}").unwrap();
+
+    send(&mut app, Event::FileChanged);
+
+    assert_eq!(app.state.query_scope, QueryScope::Tree);
+  }
+
+  #[test]
+  fn restored_folds_win_over_collapse_depth() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let path = tempdir.path().join("foo.rs");
+    let sessions = tempdir.path().join("sessions.json");
+
+    let code = "fn foo() { bar; }";
+
+    fs::write(&path, code).unwrap();
+
+    let app = |session| {
+      let tree = parse(code);
+
+      Arguments::try_parse_from([
+        "axil".as_ref(),
+        path.as_os_str(),
+        "--interactive".as_ref(),
+        "--collapse-depth".as_ref(),
+        "1".as_ref(),
+      ])
+      .unwrap()
+      .app(
+        code.into(),
+        tree,
+        tree_sitter_rust::LANGUAGE.into(),
+        None,
+        session,
+      )
+    };
+
+    let app_without_session = app(None);
+
+    assert_eq!(app_without_session.state.collapsed_nodes().len(), 1);
+
+    let tree = parse(code);
+
+    SessionStore::with_path(sessions.clone())
+      .save(
+        &path,
+        Session::capture(&State::new(tree.root_node().id()), &tree),
+      )
+      .unwrap();
+
+    let restored = app(Some(SessionStore::with_path(sessions)));
+
+    assert!(restored.state.collapsed_nodes().is_empty());
+  }
+
+  fn send(app: &mut App, event: Event) {
+    assert!(app.handle_event(&event).unwrap().is_continue());
+  }
+
+  #[test]
+  fn set_changes_query_options() {
+    let mut app = new("fn foo() {} fn bar() {}");
+
+    app.set_query("(identifier) @id");
+
+    send(&mut app, Event::EnterQuery);
+
+    for c in "set query-range 12..23".chars() {
+      send(&mut app, Event::InputChar(c));
+    }
+
+    send(&mut app, Event::InputConfirm);
+
+    assert_eq!(
+      app.state.query_options.range,
+      Some(QueryRange::Bytes(12..23))
+    );
+    assert_eq!(app.state.ts_query_matches.len(), 1);
+    assert_eq!(app.message.unwrap().0, "Set query-range 12..23");
+  }
+
+  #[test]
+  fn toggle_log_captures_latest_parse() {
+    let mut app = new("fn foo() {}");
+
+    send(&mut app, Event::ToggleLog);
+
+    let lines = app.parse_log.lines().unwrap().len();
+
+    assert!(lines > App::LOG_SCROLL_STEP);
+
+    send(&mut app, Event::ScrollLog { down: true });
+    assert_eq!(app.log_scroll, App::LOG_SCROLL_STEP);
+
+    send(&mut app, Event::ScrollLog { down: false });
+    send(&mut app, Event::ScrollLog { down: false });
+    assert_eq!(app.log_scroll, 0);
+
+    send(&mut app, Event::ToggleLog);
+    assert!(app.parse_log.lines().is_none());
   }
 }
@@ -1,75 +1,285 @@
 use super::*;
 
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Clap, Debug)]
-#[clap(author, version)]
+#[clap(author, version, args_conflicts_with_subcommands = true)]
 pub(crate) struct Arguments {
-  /// Source file to parse into a syntax tree (reads from stdin if omitted)
+  /// Collapse every node at this depth when opening the interactive TUI,
+  /// unless the file's saved session restores its own folds
+  #[clap(long, value_name = "DEPTH", requires = "interactive")]
+  collapse_depth: Option<usize>,
+  /// Write tree-sitter's parser and lexer log to this file
+  #[clap(long, value_name = "FILE")]
+  debug_log: Option<PathBuf>,
+  /// Write the parse stack as DOT graphs to this file, rendered to SVG with
+  /// `dot` if it ends in `.html`
+  #[clap(long, value_name = "FILE")]
+  dot: Option<PathBuf>,
+  /// Read the source as `utf-8`, `utf-16le`, `utf-16be` or `latin-1` instead
+  /// of detecting its encoding
+  #[clap(long, value_name = "ENCODING")]
+  encoding: Option<Encoding>,
+  /// Source file to parse into a syntax tree (reads from stdin if omitted).
+  /// A file named like a subcommand must be written as `./NAME` or after `--`
   file: Option<PathBuf>,
+  /// Rebuild the `--language` grammar library from this grammar source
+  /// directory with the tree-sitter CLI whenever its sources change
+  #[clap(long, value_name = "DIR", requires = "watch")]
+  grammar_dir: Option<PathBuf>,
   /// Browse the syntax tree in an interactive TUI instead of printing it
   #[clap(short, long)]
   interactive: bool,
-  /// Language grammar to use (required when reading from stdin)
+  /// Language to parse as, either a built-in language or a grammar library
+  /// (required when reading from stdin)
   #[clap(short, long)]
-  language: Option<Language>,
+  language: Option<Grammar>,
+  /// Stop a query after this many in-progress matches
+  #[clap(long, value_name = "N")]
+  match_limit: Option<u32>,
+  /// Only start query matches at most this deep below the queried node
+  #[clap(long, value_name = "DEPTH")]
+  max_start_depth: Option<u32>,
+  /// Truncate leaf text in the interactive TUI beyond this many characters
+  #[clap(long, value_name = "WIDTH", requires = "interactive")]
+  max_text_width: Option<usize>,
+  /// Don't restore or save the cursor, folds and queries for this file
+  #[clap(long, requires = "interactive")]
+  no_session: bool,
+  /// Include anonymous nodes as quoted strings in generated query patterns
+  #[clap(long, requires = "interactive")]
+  pattern_anonymous: bool,
+  /// Descend this many levels below the cursor in generated query patterns
+  #[clap(long, value_name = "DEPTH", requires = "interactive")]
+  pattern_depth: Option<usize>,
+  /// Time each pattern of the query separately instead of printing the tree
+  #[clap(long, conflicts_with_all = ["interactive", "stats"])]
+  profile_query: bool,
   /// Tree-sitter query pattern to match against the syntax tree
   #[clap(short, long)]
   query: Option<String>,
+  /// File containing a tree-sitter query to match against the syntax tree,
+  /// which may be repeated to combine several files
+  #[clap(long, value_name = "FILE", conflicts_with = "query")]
+  query_file: Vec<PathBuf>,
+  /// Only run the query over this range, as `START..END` in bytes or
+  /// `ROW:COLUMN..ROW:COLUMN` in zero-based positions
+  #[clap(long, value_name = "RANGE")]
+  query_range: Option<QueryRange>,
+  /// Give up on a query after this many milliseconds
+  #[clap(long, value_name = "MS")]
+  query_timeout: Option<u64>,
+  /// Print parse statistics instead of the syntax tree
+  #[clap(long, conflicts_with = "interactive")]
+  stats: bool,
+  #[clap(subcommand)]
+  subcommand: Option<Subcommand>,
   /// Watch the source file for changes and reload automatically
   #[clap(short, long, requires = "interactive", requires = "file")]
   watch: bool,
 }
 
 impl Arguments {
-  fn parse_source(&self) -> Result<(String, Tree, TreeSitterLanguage)> {
-    let (code, language) = if let Some(file) = &self.file {
-      let code = fs::read_to_string(file)?;
+  /// Builds the interactive session, folding to `--collapse-depth` before
+  /// restoring the file's session from `session`, so restored folds win.
+  /// Queries given as arguments replace the restored ones.
+  pub(crate) fn app(
+    &self,
+    code: Source,
+    tree: Tree,
+    language: TreeSitterLanguage,
+    query_source: Option<&str>,
+    session: Option<SessionStore>,
+  ) -> App {
+    let watch = self.watch.then(|| WatchPaths {
+      grammar: match &self.language {
+        Some(Grammar::Library(path)) => Some(path.clone()),
+        Some(Grammar::Builtin(_)) | None => None,
+      },
+      grammar_dir: self.grammar_dir.clone(),
+      queries: self.query_file.clone(),
+      source: self.file.clone(),
+    });
 
-      let language = self
-        .language
-        .map_or_else(|| Language::try_from(file.clone()), Ok)?;
+    let mut app = App::new(code, tree, language, self.file.clone(), watch);
 
-      (code, language)
+    if let Some(width) = self.max_text_width {
+      app.set_max_text_width(width);
+    }
+
+    app.set_pattern(
+      Pattern::default()
+        .anonymous(self.pattern_anonymous)
+        .depth(self.pattern_depth.unwrap_or(Pattern::DEFAULT_DEPTH)),
+    );
+
+    if let Some(depth) = self.collapse_depth {
+      app.fold_to_depth(depth);
+    }
+
+    if let Some((store, file)) = session.zip(self.file.clone()) {
+      if let Err(error) = app.restore_session(store, file) {
+        eprintln!("warning: failed to restore session: {error:#}");
+      }
+    }
+
+    app.set_parse_log(self.parse_log());
+
+    app.set_query_options(self.query_options());
+
+    if let Some(query_source) = query_source {
+      app.set_query(query_source);
+    }
+
+    app
+  }
+
+  fn parse_log(&self) -> ParseLog {
+    self
+      .debug_log
+      .clone()
+      .map(ParseLog::file)
+      .unwrap_or_default()
+  }
+
+  fn parse_source(
+    &self,
+  ) -> Result<(Source, Tree, TreeSitterLanguage, Duration)> {
+    let (bytes, grammar) = if let Some(file) = &self.file {
+      let bytes = fs::read(file)
+        .with_context(|| format!("failed to read `{}`", file.display()))?;
+
+      let grammar = self.language.clone().map_or_else(
+        || Language::try_from(file.clone()).map(Grammar::Builtin),
+        Ok,
+      )?;
+
+      (bytes, grammar)
     } else {
-      let language = self.language.ok_or_else(|| {
+      let grammar = self.language.clone().ok_or_else(|| {
         anyhow!("`--language` is required when reading from stdin")
       })?;
 
-      let mut code = String::new();
+      let mut bytes = Vec::new();
 
-      io::stdin().read_to_string(&mut code)?;
+      io::stdin().read_to_end(&mut bytes)?;
 
-      (code, language)
+      (bytes, grammar)
     };
 
-    let ts_language: TreeSitterLanguage = language.into();
+    let source = Source::new(bytes, self.encoding);
+
+    let ts_language = grammar.load()?;
 
     let mut parser = Parser::new();
 
     parser.set_language(&ts_language)?;
 
-    let tree = parser
-      .parse(&code, None)
+    self.parse_log().attach(&mut parser)?;
+
+    let dot_graphs = self
+      .dot
+      .as_ref()
+      .map(|path| DotGraphs::start(path, &mut parser))
+      .transpose()?;
+
+    let start = Instant::now();
+
+    let tree = source
+      .parse(&mut parser, None)
       .ok_or_else(|| anyhow!("failed to parse code"))?;
 
-    Ok((code, tree, ts_language))
+    let parse_time = start.elapsed();
+
+    if let Some(dot_graphs) = dot_graphs {
+      dot_graphs.finish(&mut parser)?;
+    }
+
+    Ok((source, tree, ts_language, parse_time))
   }
 
-  pub(crate) fn run(self) -> Result {
-    let (code, tree, language) = self.parse_source()?;
+  fn query_options(&self) -> QueryOptions {
+    QueryOptions {
+      match_limit: self.match_limit,
+      max_start_depth: self.max_start_depth,
+      range: self.query_range.clone(),
+      timeout: self.query_timeout.map(Duration::from_millis),
+    }
+  }
 
-    if self.interactive {
-      let watch_path = if self.watch { self.file.clone() } else { None };
+  fn query_source(&self) -> Result<Option<String>> {
+    if self.query_file.is_empty() {
+      return Ok(self.query.clone());
+    }
 
-      let mut app = App::new(code, tree, language, watch_path);
+    self
+      .query_file
+      .iter()
+      .map(|path| {
+        fs::read_to_string(path)
+          .with_context(|| format!("failed to read query `{}`", path.display()))
+      })
+      .collect::<Result<Vec<_>>>()
+      .map(|sources| Some(sources.join("\n")))
+  }
 
-      if let Some(query_source) = &self.query {
-        app.set_query(query_source);
-      }
+  pub(crate) fn run(mut self) -> Result {
+    if let Some(subcommand) = self.subcommand.take() {
+      return subcommand.run();
+    }
+
+    if self.grammar_dir.is_some()
+      && !matches!(self.language, Some(Grammar::Library(_)))
+    {
+      return Err(anyhow!(
+        "`--grammar-dir` requires `--language` to be a grammar library"
+      ));
+    }
+
+    let (code, tree, language, parse_time) = self.parse_source()?;
+
+    if self.stats {
+      print!("{}", Stats::new(&tree, code.len(), parse_time));
+      return Ok(());
+    }
+
+    let query_source = self.query_source()?;
 
-      app.run()
+    if self.profile_query {
+      let source = query_source.ok_or_else(|| {
+        anyhow!("`--profile-query` requires `--query` or `--query-file`")
+      })?;
+
+      print!(
+        "{}",
+        QueryProfile::new(
+          &language,
+          &source,
+          &tree,
+          &code,
+          &self.query_options()
+        )?
+      );
+
+      return Ok(());
+    }
+
+    if self.interactive {
+      let session = if self.file.is_some() && !self.no_session {
+        SessionStore::new()
+          .inspect_err(|error| {
+            eprintln!("warning: failed to restore session: {error:#}");
+          })
+          .ok()
+      } else {
+        None
+      };
+
+      self
+        .app(code, tree, language, query_source.as_deref(), session)
+        .run()
     } else {
-      let matches = if let Some(query_source) = &self.query {
-        Self::run_query(query_source, &language, &tree, &code)?
+      let matches = if let Some(query_source) = &query_source {
+        self.run_query(query_source, &language, &tree, &code)?
       } else {
         HashSet::new()
       };
@@ -80,22 +290,27 @@ impl Arguments {
   }
 
   fn run_query(
+    &self,
     query_source: &str,
     language: &TreeSitterLanguage,
     tree: &Tree,
-    code: &str,
+    code: &Source,
   ) -> Result<HashSet<usize>> {
     let query = Query::new(language, query_source)?;
 
-    let mut cursor = QueryCursor::new();
-    let mut matches = cursor.matches(&query, tree.root_node(), code.as_bytes());
-
     let mut matched = HashSet::new();
 
-    while let Some(m) = matches.next() {
-      for capture in m.captures {
-        matched.insert(capture.node.id());
-      }
+    let outcome =
+      self
+        .query_options()
+        .execute(&query, tree.root_node(), code, |m| {
+          for capture in m.captures {
+            matched.insert(capture.node.id());
+          }
+        });
+
+    if let Some(warning) = outcome.warning() {
+      eprintln!("warning: {warning}");
     }
 
     Ok(matched)
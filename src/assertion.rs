@@ -0,0 +1,165 @@
+use super::*;
+
+/// A comment-based assertion about the captures at a position, like
+/// `// <- keyword` or `// ^ !function`.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Assertion {
+  pub(crate) expected: String,
+  pub(crate) negative: bool,
+  pub(crate) position: Point,
+}
+
+impl Display for Assertion {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(
+      f,
+      "{}{}",
+      if self.negative { "!" } else { "" },
+      self.expected
+    )
+  }
+}
+
+impl Assertion {
+  /// Returns whether the assertion holds given the capture names that cover
+  /// its position.
+  pub(crate) fn check(&self, names: &[&str]) -> bool {
+    names.contains(&self.expected.as_str()) != self.negative
+  }
+
+  /// Parses the assertions in a tree's comments. A `^` points at its own
+  /// column and `<-` at the comment's first column, in both cases on the
+  /// closest line above that isn't itself an assertion.
+  pub(crate) fn parse(tree: &Tree, source: &Source) -> Vec<Self> {
+    let mut comments = Vec::new();
+
+    tree.root_node().preorder(|node, _| {
+      if node.kind().to_lowercase().contains("comment") {
+        comments.push(node);
+        return false;
+      }
+
+      true
+    });
+
+    let mut marked = Vec::new();
+
+    for node in comments {
+      if node.start_position().row != node.end_position().row {
+        continue;
+      }
+
+      let text = source.text(node.byte_range());
+
+      let body = text.trim_start_matches(|c: char| {
+        !c.is_whitespace() && c != '^' && c != '<'
+      });
+
+      let body = body.trim_start();
+
+      let offset = source
+        .encoding()
+        .encoded_len(&text[..text.len() - body.len()]);
+
+      let (column, rest) = if let Some(rest) = body.strip_prefix("<-") {
+        (node.start_position().column, rest)
+      } else if body.starts_with('^') {
+        (
+          node.start_position().column + offset,
+          body.trim_start_matches('^'),
+        )
+      } else {
+        continue;
+      };
+
+      let rest = rest.trim_start();
+
+      let (negative, rest) = match rest.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+      };
+
+      let Some(expected) = rest.split_whitespace().next() else {
+        continue;
+      };
+
+      let row = node.start_position().row;
+
+      let line_start = node.start_byte() - node.start_position().column;
+
+      let standalone =
+        source.text(line_start..node.start_byte()).trim().is_empty();
+
+      marked.push((row, standalone, column, negative, expected.to_string()));
+    }
+
+    let standalone = marked
+      .iter()
+      .filter(|(_, standalone, ..)| *standalone)
+      .map(|(row, ..)| *row)
+      .collect::<HashSet<_>>();
+
+    marked
+      .into_iter()
+      .filter_map(|(row, _, column, negative, expected)| {
+        let row = (0..row).rev().find(|row| !standalone.contains(row))?;
+
+        Some(Self {
+          expected,
+          negative,
+          position: Point { row, column },
+        })
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn check() {
+    let assertion = |negative| Assertion {
+      expected: "keyword".into(),
+      negative,
+      position: Point::default(),
+    };
+
+    assert!(assertion(false).check(&["variable", "keyword"]));
+    assert!(!assertion(false).check(&["variable"]));
+    assert!(assertion(true).check(&[]));
+    assert!(!assertion(true).check(&["keyword"]));
+  }
+
+  #[test]
+  fn parse() {
+    let code = "\
+fn foo() {}
+// <- keyword
+// ^ !function
+//  ^ function
+let x = 1; // not an assertion
+    // ^ variable
+";
+
+    let tree = crate::parse(code);
+
+    assert_eq!(
+      Assertion::parse(&tree, &code.into()),
+      [
+        ("keyword", false, 0, 0),
+        ("function", true, 0, 3),
+        ("function", false, 0, 4),
+        ("variable", false, 4, 7),
+      ]
+      .into_iter()
+      .map(|(expected, negative, row, column)| Assertion {
+        expected: expected.into(),
+        negative,
+        position: Point { row, column },
+      })
+      .collect::<Vec<_>>()
+    );
+  }
+}
@@ -0,0 +1,199 @@
+use super::*;
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct Bench {
+  /// Source files to parse
+  #[clap(required = true)]
+  files: Vec<PathBuf>,
+  /// Number of times to parse each file
+  #[clap(short = 'n', long, default_value_t = 100)]
+  iterations: usize,
+  /// Grammar to benchmark, as a language name or a path to a grammar library;
+  /// repeat to compare several (detected from each file's extension if
+  /// omitted)
+  #[clap(short, long)]
+  language: Vec<Grammar>,
+}
+
+/// The parse timings of one file with one grammar.
+#[derive(Debug)]
+struct Run {
+  bytes: usize,
+  file: String,
+  grammar: String,
+  timings: Vec<Duration>,
+}
+
+impl Bench {
+  /// Returns the timing below which `fraction` of the sorted `timings` fall.
+  fn percentile(timings: &[Duration], fraction: f64) -> Duration {
+    #[allow(
+      clippy::cast_possible_truncation,
+      clippy::cast_precision_loss,
+      clippy::cast_sign_loss
+    )]
+    let rank = (timings.len() as f64 * fraction).ceil() as usize;
+
+    timings[rank.clamp(1, timings.len()) - 1]
+  }
+
+  pub(crate) fn run(self) -> Result {
+    if self.iterations == 0 {
+      return Err(anyhow!("`--iterations` must be at least 1"));
+    }
+
+    let mut parser = Parser::new();
+
+    let mut runs = Vec::new();
+
+    for file in &self.files {
+      let source = Source::new(
+        fs::read(file)
+          .with_context(|| format!("failed to read `{}`", file.display()))?,
+        None,
+      );
+
+      let grammars = if self.language.is_empty() {
+        vec![Grammar::Builtin(
+          Language::try_from(file.clone()).with_context(|| {
+            format!("failed to detect language for `{}`", file.display())
+          })?,
+        )]
+      } else {
+        self.language.clone()
+      };
+
+      for grammar in grammars {
+        parser.set_language(&grammar.load()?)?;
+
+        let mut timings = Vec::with_capacity(self.iterations);
+
+        for _ in 0..self.iterations {
+          let start = Instant::now();
+
+          let tree = source
+            .parse(&mut parser, None)
+            .ok_or_else(|| anyhow!("failed to parse `{}`", file.display()))?;
+
+          timings.push(start.elapsed());
+
+          // Freeing the tree isn't part of parsing it.
+          drop(tree);
+        }
+
+        timings.sort();
+
+        runs.push(Run {
+          bytes: source.len(),
+          file: file.display().to_string(),
+          grammar: grammar.to_string(),
+          timings,
+        });
+      }
+    }
+
+    print!("{}", Self::table(&runs));
+
+    Ok(())
+  }
+
+  /// Formats runs as an aligned table with one row per file and grammar.
+  fn table(runs: &[Run]) -> String {
+    let mut rows = vec![[
+      "file".to_string(),
+      "grammar".into(),
+      "min".into(),
+      "median".into(),
+      "p99".into(),
+      "throughput".into(),
+    ]];
+
+    for run in runs {
+      let median = Self::percentile(&run.timings, 0.5);
+
+      rows.push([
+        run.file.clone(),
+        run.grammar.clone(),
+        format!("{:?}", run.timings[0]),
+        format!("{median:?}"),
+        format!("{:?}", Self::percentile(&run.timings, 0.99)),
+        Stats::throughput(run.bytes, median),
+      ]);
+    }
+
+    let widths = (0..6)
+      .map(|i| {
+        rows
+          .iter()
+          .map(|row| row[i].chars().count())
+          .max()
+          .unwrap_or_default()
+      })
+      .collect::<Vec<_>>();
+
+    let mut table = String::new();
+
+    for row in rows {
+      let line = row
+        .iter()
+        .zip(&widths)
+        .map(|(cell, width)| format!("{cell:<width$}"))
+        .collect::<Vec<_>>()
+        .join("  ");
+
+      table.push_str(line.trim_end());
+      table.push('\n');
+    }
+
+    table
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn millis(values: &[u64]) -> Vec<Duration> {
+    values.iter().copied().map(Duration::from_millis).collect()
+  }
+
+  #[test]
+  fn percentile() {
+    let timings = millis(&(1..=100).collect::<Vec<_>>());
+
+    assert_eq!(Bench::percentile(&timings, 0.5), Duration::from_millis(50));
+    assert_eq!(Bench::percentile(&timings, 0.99), Duration::from_millis(99));
+    assert_eq!(Bench::percentile(&timings, 0.0), Duration::from_millis(1));
+    assert_eq!(
+      Bench::percentile(&millis(&[7]), 0.99),
+      Duration::from_millis(7)
+    );
+  }
+
+  #[test]
+  fn table() {
+    assert_eq!(
+      Bench::table(&[
+        Run {
+          bytes: 2_000_000,
+          file: "foo.rs".into(),
+          grammar: "rust".into(),
+          timings: millis(&[1, 2, 3]),
+        },
+        Run {
+          bytes: 2_000_000,
+          file: "foo.rs".into(),
+          grammar: "fork/rust.so".into(),
+          timings: millis(&[10, 20, 30]),
+        },
+      ]),
+      [
+        "file    grammar       min   median  p99   throughput",
+        "foo.rs  rust          1ms   2ms     3ms   1000.00 MB/s",
+        "foo.rs  fork/rust.so  10ms  20ms    30ms  100.00 MB/s",
+        "",
+      ]
+      .join("\n")
+    );
+  }
+}
@@ -0,0 +1,386 @@
+use super::*;
+
+pub(crate) struct Breadcrumb<'a> {
+  ancestors: Vec<(Option<&'static str>, Node<'a>)>,
+  zoom: Option<usize>,
+}
+
+impl Display for Breadcrumb<'_> {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    for (i, (field, node)) in self.ancestors.iter().enumerate() {
+      if i > 0 {
+        write!(f, "{}", Self::SEPARATOR)?;
+      }
+
+      write!(f, "{}", Self::label(*field, node))?;
+    }
+
+    Ok(())
+  }
+}
+
+impl Widget for Breadcrumb<'_> {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let (skipped, segments) = self.visible_segments(area.width);
+
+    let separator_style = Style::default().fg(Color::DarkGray);
+
+    let mut spans = Vec::new();
+
+    if skipped > 0 {
+      spans.push(Span::styled(Self::ELLIPSIS, separator_style));
+      spans.push(Span::styled(Self::SEPARATOR, separator_style));
+    }
+
+    let last = segments.len().saturating_sub(1);
+
+    for (i, (label, node)) in segments.into_iter().enumerate() {
+      if i > 0 {
+        spans.push(Span::styled(Self::SEPARATOR, separator_style));
+      }
+
+      let style = if i == last {
+        Style::default()
+          .fg(node.color())
+          .add_modifier(Modifier::BOLD)
+      } else if self.zoom == Some(node.id()) {
+        Style::default()
+          .fg(Color::Cyan)
+          .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+      } else {
+        Style::default().fg(Color::Gray)
+      };
+
+      spans.push(Span::styled(label, style));
+    }
+
+    Paragraph::new(Line::from(spans)).render(area, buf);
+  }
+}
+
+impl<'a> Breadcrumb<'a> {
+  const ELLIPSIS: &'static str = "…";
+  const SEPARATOR: &'static str = " > ";
+
+  pub(crate) fn escape(kind: &str) -> String {
+    kind.replace('\\', "\\\\").replace('"', "\\\"")
+  }
+
+  fn label(field: Option<&str>, node: &Node) -> String {
+    match field {
+      Some(field) => format!("{field}: {}", node.kind()),
+      None => node.kind().to_string(),
+    }
+  }
+
+  pub(crate) fn new(
+    tree: &'a Tree,
+    node: Node<'a>,
+    zoom: Option<usize>,
+  ) -> Self {
+    let mut cursor = tree.walk();
+
+    let mut ancestors = vec![(None, cursor.node())];
+
+    // Walk down to `node`, reading each field name off the cursor on the way
+    // instead of searching each parent's children for it afterwards.
+    while let Some(child) = cursor.node().child_with_descendant(node) {
+      // Seeking by byte would skip zero-width children like missing tokens.
+      let found = if child.byte_range().is_empty() {
+        cursor.goto_first_child()
+      } else {
+        cursor
+          .goto_first_child_for_byte(child.start_byte())
+          .is_some()
+      };
+
+      if !found {
+        break;
+      }
+
+      while cursor.node().id() != child.id() && cursor.goto_next_sibling() {}
+
+      if cursor.node().id() != child.id() {
+        break;
+      }
+
+      ancestors.push((cursor.field_name(), child));
+    }
+
+    if cursor.node().id() != node.id() {
+      ancestors = vec![(None, node)];
+    }
+
+    Self { ancestors, zoom }
+  }
+
+  pub(crate) fn node_at_column(
+    &self,
+    column: u16,
+    width: u16,
+  ) -> Option<usize> {
+    let (skipped, segments) = self.visible_segments(width);
+
+    let column = column as usize;
+
+    let mut offset = if skipped > 0 {
+      Self::ELLIPSIS.chars().count() + Self::SEPARATOR.len()
+    } else {
+      0
+    };
+
+    for (label, node) in segments {
+      let end = offset + label.chars().count();
+
+      if (offset..end).contains(&column) {
+        return Some(node.id());
+      }
+
+      offset = end + Self::SEPARATOR.len();
+    }
+
+    None
+  }
+
+  pub(crate) fn query_pattern(&self) -> String {
+    let mut pattern = String::new();
+    let mut closing = String::new();
+
+    let last = self.ancestors.len().saturating_sub(1);
+
+    for (i, (field, node)) in self.ancestors.iter().enumerate() {
+      if i > 0 {
+        pattern.push(' ');
+      }
+
+      if let Some(field) = field {
+        pattern.push_str(field);
+        pattern.push_str(": ");
+      }
+
+      if !node.is_named() {
+        pattern.push('"');
+        pattern.push_str(&Self::escape(node.kind()));
+        pattern.push('"');
+      } else if i == last {
+        pattern.push('(');
+        pattern.push_str(node.kind());
+        pattern.push(')');
+      } else {
+        pattern.push('(');
+        pattern.push_str(node.kind());
+        closing.push(')');
+      }
+    }
+
+    pattern.push_str(" @node");
+    pattern.push_str(&closing);
+
+    pattern
+  }
+
+  fn visible_segments(&self, width: u16) -> (usize, Vec<(String, Node<'a>)>) {
+    let segments = self
+      .ancestors
+      .iter()
+      .map(|(field, node)| (Self::label(*field, node), *node))
+      .collect::<Vec<_>>();
+
+    let total = |segments: &[(String, Node)]| {
+      segments
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .sum::<usize>()
+        + segments.len().saturating_sub(1) * Self::SEPARATOR.len()
+    };
+
+    let prefix = Self::ELLIPSIS.chars().count() + Self::SEPARATOR.len();
+
+    let mut skipped = 0;
+
+    while skipped + 1 < segments.len()
+      && total(&segments[skipped..]) + if skipped > 0 { prefix } else { 0 }
+        > width as usize
+    {
+      skipped += 1;
+    }
+
+    (skipped, segments.into_iter().skip(skipped).collect())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn cursor(tree: &Tree, path: &[u32]) -> usize {
+    path
+      .iter()
+      .fold(tree.root_node(), |node, &i| node.child(i).unwrap())
+      .id()
+  }
+
+  fn labels(breadcrumb: &Breadcrumb, width: u16) -> Vec<String> {
+    breadcrumb
+      .visible_segments(width)
+      .1
+      .into_iter()
+      .map(|(label, _)| label)
+      .collect()
+  }
+
+  #[test]
+  fn labels_include_field_names() {
+    let tree = parse("fn foo() { let x = 1; }");
+
+    let node = tree.root_node().child(0).unwrap().child(3).unwrap();
+
+    let breadcrumb = Breadcrumb::new(&tree, node, None);
+
+    assert_eq!(
+      labels(&breadcrumb, u16::MAX),
+      vec!["source_file", "function_item", "body: block"],
+    );
+
+    assert_eq!(
+      breadcrumb.to_string(),
+      "source_file > function_item > body: block"
+    );
+  }
+
+  #[test]
+  fn labels_reach_missing_nodes() {
+    let tree = parse("fn foo() { let x = 1 }");
+
+    let mut missing = None;
+
+    tree.root_node().preorder(|node, _| {
+      if node.is_missing() {
+        missing = Some(node);
+      }
+      true
+    });
+
+    let breadcrumb = Breadcrumb::new(&tree, missing.unwrap(), None);
+
+    assert_eq!(
+      labels(&breadcrumb, u16::MAX),
+      vec![
+        "source_file",
+        "function_item",
+        "body: block",
+        "let_declaration",
+        ";",
+      ],
+    );
+  }
+
+  #[test]
+  fn long_paths_drop_leading_segments() {
+    let tree = parse("fn foo() { let x = 1; }");
+
+    let node = tree
+      .root_node()
+      .child(0)
+      .unwrap()
+      .child(3)
+      .unwrap()
+      .child(1)
+      .unwrap();
+
+    let breadcrumb = Breadcrumb::new(&tree, node, None);
+
+    assert_eq!(breadcrumb.visible_segments(40).0, 2);
+
+    assert_eq!(
+      labels(&breadcrumb, 40),
+      vec!["body: block", "let_declaration"]
+    );
+
+    assert_eq!(breadcrumb.node_at_column(0, 40), None);
+    assert_eq!(
+      breadcrumb.node_at_column(4, 40),
+      Some(cursor(&tree, &[0, 3]))
+    );
+  }
+
+  #[test]
+  fn node_at_column() {
+    let tree = parse("fn foo() {}");
+
+    let node = tree.root_node().child(0).unwrap().child(1).unwrap();
+
+    let breadcrumb = Breadcrumb::new(&tree, node, None);
+
+    assert_eq!(breadcrumb.node_at_column(0, 80), Some(cursor(&tree, &[])));
+    assert_eq!(breadcrumb.node_at_column(10, 80), Some(cursor(&tree, &[])));
+    assert_eq!(breadcrumb.node_at_column(12, 80), None);
+    assert_eq!(breadcrumb.node_at_column(14, 80), Some(cursor(&tree, &[0])));
+    assert_eq!(
+      breadcrumb.node_at_column(30, 80),
+      Some(cursor(&tree, &[0, 1]))
+    );
+    assert_eq!(breadcrumb.node_at_column(79, 80), None);
+  }
+
+  #[test]
+  fn query_pattern() {
+    let tree = parse("fn foo() {}");
+
+    let node = tree.root_node().child(0).unwrap().child(1).unwrap();
+
+    assert_eq!(
+      Breadcrumb::new(&tree, node, None).query_pattern(),
+      "(source_file (function_item name: (identifier) @node))",
+    );
+  }
+
+  #[test]
+  fn query_pattern_anonymous_leaf() {
+    let tree = parse("fn foo() {}");
+
+    let node = tree.root_node().child(0).unwrap().child(0).unwrap();
+
+    assert_eq!(
+      Breadcrumb::new(&tree, node, None).query_pattern(),
+      "(source_file (function_item \"fn\" @node))",
+    );
+  }
+
+  #[test]
+  fn query_pattern_matches_cursor() {
+    let code = "fn foo() {} fn bar() { let x = 1; }";
+
+    let tree = parse(code);
+
+    let node = tree.root_node().child(1).unwrap().child(3).unwrap();
+
+    let query = Query::new(
+      &tree_sitter_rust::LANGUAGE.into(),
+      &Breadcrumb::new(&tree, node, None).query_pattern(),
+    )
+    .unwrap();
+
+    let mut cursor = QueryCursor::new();
+
+    let mut matches = cursor.matches(&query, tree.root_node(), code.as_bytes());
+
+    let mut captured = Vec::new();
+
+    while let Some(m) = matches.next() {
+      captured.extend(m.captures.iter().map(|capture| capture.node.id()));
+    }
+
+    assert!(captured.contains(&node.id()));
+  }
+
+  #[test]
+  fn root_only() {
+    let tree = parse("fn foo() {}");
+
+    let breadcrumb = Breadcrumb::new(&tree, tree.root_node(), None);
+
+    assert_eq!(labels(&breadcrumb, 80), vec!["source_file"]);
+    assert_eq!(breadcrumb.query_pattern(), "(source_file) @node");
+  }
+}
@@ -1,4 +1,10 @@
+#[derive(Clone, Debug, PartialEq)]
 pub(crate) enum ChannelEvent {
   Crossterm(crossterm::event::Event),
   FileChanged,
+  FileDeleted,
+  GrammarBuildFailed(String),
+  GrammarChanged,
+  GrammarSourceChanged,
+  QueryChanged,
 }
@@ -0,0 +1,48 @@
+use super::*;
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct Compare {
+  /// Read the source as `utf-8`, `utf-16le`, `utf-16be` or `latin-1` instead
+  /// of detecting its encoding
+  #[clap(long, value_name = "ENCODING")]
+  encoding: Option<Encoding>,
+  /// Source file to parse with both grammars
+  file: PathBuf,
+  /// Browse both trees side by side in an interactive TUI
+  #[clap(short, long)]
+  interactive: bool,
+  /// Grammar shown on the left, as a language name or a path to a grammar
+  /// library
+  left: Grammar,
+  /// Grammar shown on the right, as a language name or a path to a grammar
+  /// library
+  right: Grammar,
+}
+
+impl Compare {
+  pub(crate) fn run(self) -> Result {
+    let source = Source::new(
+      fs::read(&self.file)
+        .with_context(|| format!("failed to read `{}`", self.file.display()))?,
+      self.encoding,
+    );
+
+    let left =
+      Pane::new(self.left.to_string(), source.clone(), &self.left.load()?)?;
+
+    let right = Pane::new(self.right.to_string(), source, &self.right.load()?)?;
+
+    let diff = TreeDiff::by_range(&left.tree, &right.tree);
+
+    if self.interactive {
+      DiffView::new(diff, left, right).run()
+    } else {
+      print!(
+        "{}",
+        diff.render((&left.tree, &left.source), (&right.tree, &right.source))
+      );
+
+      Ok(())
+    }
+  }
+}
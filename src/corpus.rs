@@ -0,0 +1,156 @@
+use super::*;
+
+/// An example in a tree-sitter corpus file, with byte ranges locating its
+/// input and expected tree in the file.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Example {
+  pub(crate) error: bool,
+  pub(crate) expected: Range<usize>,
+  pub(crate) input: Range<usize>,
+  pub(crate) name: String,
+  pub(crate) skip: bool,
+}
+
+impl Example {
+  fn is_rule(line: &str, c: char) -> bool {
+    let line = line.trim_end();
+    line.len() >= 3 && line.chars().all(|x| x == c)
+  }
+
+  pub(crate) fn parse(text: &str) -> Result<Vec<Self>> {
+    let mut lines = text
+      .split_inclusive('\n')
+      .scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some((start, line))
+      })
+      .peekable();
+
+    let mut examples = Vec::new();
+
+    while let Some((_, line)) = lines.next() {
+      if !Self::is_rule(line, '=') {
+        continue;
+      }
+
+      let mut example = Self::default();
+
+      let mut names = Vec::new();
+
+      let body_start = loop {
+        let Some((offset, line)) = lines.next() else {
+          return Err(anyhow!("unterminated example header"));
+        };
+
+        if Self::is_rule(line, '=') {
+          break offset + line.len();
+        }
+
+        match line.trim() {
+          ":error" => example.error = true,
+          ":skip" => example.skip = true,
+          attribute if attribute.starts_with(':') => {}
+          name => names.push(name),
+        }
+      };
+
+      example.name = names.join(" ");
+
+      let mut body_end = text.len();
+
+      let mut divider = None;
+
+      while let Some(&(offset, line)) = lines.peek() {
+        if Self::is_rule(line, '=') {
+          body_end = offset;
+          break;
+        }
+
+        if Self::is_rule(line, '-') {
+          divider = Some(offset..offset + line.len());
+        }
+
+        lines.next();
+      }
+
+      let divider = divider.ok_or_else(|| {
+        anyhow!("example `{}` is missing a `---` divider", example.name)
+      })?;
+
+      let input = &text[body_start..divider.start];
+
+      let input = input
+        .strip_suffix('\n')
+        .map_or(input, |input| input.strip_suffix('\r').unwrap_or(input));
+
+      example.input = body_start..body_start + input.len();
+
+      let expected = &text[divider.end..body_end];
+
+      let start = divider.end + expected.len() - expected.trim_start().len();
+
+      example.expected = start..start + expected.trim().len();
+
+      examples.push(example);
+    }
+
+    Ok(examples)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn missing_divider() {
+    assert_eq!(
+      Example::parse("===\nfoo\n===\nfn foo() {}\n")
+        .unwrap_err()
+        .to_string(),
+      "example `foo` is missing a `---` divider"
+    );
+  }
+
+  #[test]
+  fn parse() {
+    let text = "\
+==================
+Functions
+==================
+
+fn foo() {}
+
+---
+
+(source_file
+  (function_item))
+
+==================
+Broken
+:error
+:skip
+==================
+fn (
+-----
+";
+
+    let examples = Example::parse(text).unwrap();
+
+    assert_eq!(examples.len(), 2);
+
+    assert_eq!(examples[0].name, "Functions");
+    assert_eq!(&text[examples[0].input.clone()], "\nfn foo() {}\n");
+    assert_eq!(
+      &text[examples[0].expected.clone()],
+      "(source_file\n  (function_item))"
+    );
+    assert!(!examples[0].error && !examples[0].skip);
+
+    assert_eq!(examples[1].name, "Broken");
+    assert_eq!(&text[examples[1].input.clone()], "fn (");
+    assert_eq!(&text[examples[1].expected.clone()], "");
+    assert!(examples[1].error && examples[1].skip);
+  }
+}
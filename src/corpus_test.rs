@@ -0,0 +1,222 @@
+use super::*;
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct CorpusTest {
+  /// Corpus file, or directory of `.txt` corpus files
+  #[clap(default_value = "test/corpus")]
+  corpus: PathBuf,
+  /// Open the first failing example in the interactive TUI
+  #[clap(short, long)]
+  interactive: bool,
+  /// Grammar to test, as a language name or a path to a grammar library
+  #[clap(short, long)]
+  language: Grammar,
+  /// Rewrite the expected trees of failing examples with the actual trees
+  #[clap(short, long)]
+  update: bool,
+}
+
+impl CorpusTest {
+  /// Returns a line diff between the expected and actual trees, with removed
+  /// lines in red and added lines in green if `color` is set.
+  fn diff(expected: &str, actual: &str, color: bool) -> String {
+    fn hashes(lines: &[&str]) -> Vec<u64> {
+      lines
+        .iter()
+        .map(|line| {
+          let mut hasher = DefaultHasher::new();
+          line.hash(&mut hasher);
+          hasher.finish()
+        })
+        .collect()
+    }
+
+    let paint = |sign: char, line: &str, foreground: Option<_>| {
+      let style = ContentStyle {
+        foreground_color: foreground.filter(|_| color),
+        ..ContentStyle::default()
+      };
+
+      format!("    {}\n", style.apply(format!("{sign} {line}")))
+    };
+
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+
+    let mut output = String::new();
+
+    let (mut i, mut j) = (0, 0);
+
+    for (a, b) in TreeDiff::align(&hashes(&expected), &hashes(&actual))
+      .into_iter()
+      .chain(iter::once((expected.len(), actual.len())))
+    {
+      for line in &expected[i..a] {
+        output.push_str(&paint('-', line, Some(crossterm::style::Color::Red)));
+      }
+
+      for line in &actual[j..b] {
+        output.push_str(&paint(
+          '+',
+          line,
+          Some(crossterm::style::Color::Green),
+        ));
+      }
+
+      if let Some(line) = expected.get(a) {
+        output.push_str(&paint(' ', line, None));
+      }
+
+      (i, j) = (a + 1, b + 1);
+    }
+
+    output
+  }
+
+  /// Returns the corpus files to run, sorted by path.
+  fn files(&self) -> Result<Vec<PathBuf>> {
+    if !self.corpus.is_dir() {
+      return Ok(vec![self.corpus.clone()]);
+    }
+
+    let mut files = Vec::new();
+
+    let mut directories = vec![self.corpus.clone()];
+
+    while let Some(directory) = directories.pop() {
+      let entries = fs::read_dir(&directory).with_context(|| {
+        format!("failed to read directory `{}`", directory.display())
+      })?;
+
+      for entry in entries {
+        let path = entry?.path();
+
+        if path.is_dir() {
+          directories.push(path);
+        } else if path.extension().is_some_and(|extension| extension == "txt") {
+          files.push(path);
+        }
+      }
+    }
+
+    files.sort();
+
+    Ok(files)
+  }
+
+  pub(crate) fn run(self) -> Result {
+    let language = self.language.load()?;
+
+    let mut parser = Parser::new();
+
+    parser.set_language(&language)?;
+
+    let color = env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal();
+
+    let (mut passed, mut failed, mut skipped, mut updated) = (0, 0, 0, 0);
+
+    let mut failure = None;
+
+    for path in self.files()? {
+      let text = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read `{}`", path.display()))?;
+
+      let examples = Example::parse(&text).with_context(|| {
+        format!("failed to parse corpus file `{}`", path.display())
+      })?;
+
+      println!("{}:", path.display());
+
+      let mut replacements = Vec::new();
+
+      for example in examples {
+        if example.skip {
+          println!("  {} ... skipped", example.name);
+          skipped += 1;
+          continue;
+        }
+
+        let input = &text[example.input.clone()];
+
+        let tree = parser.parse(input, None).ok_or_else(|| {
+          anyhow!("failed to parse example `{}`", example.name)
+        })?;
+
+        let sexp = tree.root_node().sexp(&input.into());
+
+        let (actual, expected) =
+          (Sexp::new(&sexp), Sexp::new(&text[example.expected.clone()]));
+
+        let ok = if example.error {
+          tree.root_node().has_error()
+        } else {
+          let fields = expected.fields();
+          actual.normalize(fields) == expected.normalize(fields)
+        };
+
+        if ok {
+          println!("  {} ... ok", example.name);
+          passed += 1;
+        } else if self.update && !example.error {
+          println!("  {} ... updated", example.name);
+          replacements.push((example.expected, actual.pretty(true)));
+          updated += 1;
+        } else {
+          println!("  {} ... FAILED", example.name);
+
+          if example.error {
+            println!("    expected the tree to contain errors");
+          } else {
+            let fields = expected.fields();
+
+            print!(
+              "{}",
+              Self::diff(
+                &expected.pretty(fields),
+                &actual.pretty(fields),
+                color
+              )
+            );
+          }
+
+          failure.get_or_insert_with(|| (input.to_owned(), tree));
+          failed += 1;
+        }
+      }
+
+      if !replacements.is_empty() {
+        let mut text = text.clone();
+
+        for (range, sexp) in replacements.into_iter().rev() {
+          text.replace_range(range, &sexp);
+        }
+
+        fs::write(&path, text)
+          .with_context(|| format!("failed to write `{}`", path.display()))?;
+      }
+    }
+
+    println!();
+
+    print!("{passed} passed, {failed} failed, {skipped} skipped");
+
+    if updated > 0 {
+      print!(", {updated} updated");
+    }
+
+    println!();
+
+    if let Some((code, tree)) = failure.filter(|_| self.interactive) {
+      App::new(code.as_str().into(), tree, language, None, None).run()?;
+    }
+
+    if failed > 0 {
+      return Err(anyhow!(
+        "{failed} of {} corpus examples failed",
+        passed + failed + updated
+      ));
+    }
+
+    Ok(())
+  }
+}
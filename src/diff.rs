@@ -0,0 +1,196 @@
+use super::*;
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct Diff {
+  /// Read both versions as `utf-8`, `utf-16le`, `utf-16be` or `latin-1`
+  /// instead of detecting their encodings
+  #[clap(long, value_name = "ENCODING")]
+  encoding: Option<Encoding>,
+  /// Old version of the file, or the working copy when diffing against `--rev`
+  file: PathBuf,
+  /// Browse the diff side by side in an interactive TUI
+  #[clap(short, long)]
+  interactive: bool,
+  /// Language grammar to use (detected from the file extension if omitted)
+  #[clap(short, long)]
+  language: Option<Language>,
+  /// New version of the file
+  #[clap(required_unless_present = "rev", conflicts_with = "rev")]
+  other: Option<PathBuf>,
+  /// Git revision to compare the working copy of `FILE` against
+  #[clap(long, value_name = "REV")]
+  rev: Option<String>,
+  /// Watch both files for changes and diff them again automatically
+  #[clap(short, long, requires = "interactive")]
+  watch: bool,
+}
+
+impl Diff {
+  fn language(&self) -> Result<TreeSitterLanguage> {
+    Ok(
+      self
+        .language
+        .map_or_else(
+          || {
+            Language::try_from(self.other.clone().unwrap_or(self.file.clone()))
+          },
+          Ok,
+        )?
+        .into(),
+    )
+  }
+
+  /// Reads and parses the old and new versions of the file.
+  fn panes(&self, language: &TreeSitterLanguage) -> Result<(Pane, Pane)> {
+    let (old, new) = match (&self.other, &self.rev) {
+      (Some(other), _) => (
+        (self.file.display().to_string(), self.read(&self.file)?),
+        (other.display().to_string(), self.read(other)?),
+      ),
+      (None, Some(rev)) => (
+        (
+          format!("{}@{rev}", self.file.display()),
+          self.read_revision(&self.file, rev)?,
+        ),
+        (self.file.display().to_string(), self.read(&self.file)?),
+      ),
+      (None, None) => unreachable!(),
+    };
+
+    Ok((
+      Pane::new(old.0, old.1, language)?,
+      Pane::new(new.0, new.1, language)?,
+    ))
+  }
+
+  fn read(&self, path: &Path) -> Result<Source> {
+    let bytes = fs::read(path)
+      .with_context(|| format!("failed to read `{}`", path.display()))?;
+
+    Ok(Source::new(bytes, self.encoding))
+  }
+
+  /// Exports `path` at `rev` to a temporary file with the same checkout
+  /// filters as the working copy, such as line ending conversion, and reads
+  /// it like any other file.
+  fn read_revision(&self, path: &Path, rev: &str) -> Result<Source> {
+    let directory = path
+      .parent()
+      .filter(|parent| !parent.as_os_str().is_empty())
+      .unwrap_or(Path::new("."));
+
+    let name = path
+      .file_name()
+      .ok_or_else(|| anyhow!("`{}` is not a file", path.display()))?
+      .to_string_lossy();
+
+    let object = format!("{rev}:./{name}");
+
+    let blob =
+      NamedTempFile::new().context("failed to create temporary file")?;
+
+    let output = Command::new("git")
+      .arg("-C")
+      .arg(directory)
+      .arg("cat-file")
+      .arg("--filters")
+      .arg(&object)
+      .stdout(blob.reopen()?)
+      .output()
+      .context("failed to run `git`")?;
+
+    if !output.status.success() {
+      return Err(anyhow!(
+        "failed to read `{object}` from git: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+      ));
+    }
+
+    self.read(blob.path())
+  }
+
+  pub(crate) fn run(self) -> Result {
+    let language = self.language()?;
+
+    let (old, new) = self.panes(&language)?;
+
+    let diff =
+      TreeDiff::new((&old.tree, &old.source), (&new.tree, &new.source));
+
+    if self.interactive {
+      let mut view = DiffView::new(diff, old, new);
+
+      if self.watch {
+        // With `--rev` only the working copy can change.
+        let paths = iter::once(self.file.clone())
+          .chain(self.other.clone())
+          .collect();
+
+        view = view.watch(paths, move || self.panes(&language));
+      }
+
+      view.run()
+    } else {
+      print!(
+        "{}",
+        diff.render((&old.tree, &old.source), (&new.tree, &new.source))
+      );
+
+      Ok(())
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn read_revision_exports_blob() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let git = |arguments: &[&str]| {
+      let status = Command::new("git")
+        .arg("-C")
+        .arg(tempdir.path())
+        .args(["-c", "user.name=axil", "-c", "user.email=axil@example.com"])
+        .args(arguments)
+        .output()
+        .unwrap()
+        .status;
+
+      assert!(status.success(), "git {arguments:?} failed");
+    };
+
+    let path = tempdir.path().join("foo.rs");
+
+    git(&["init", "--quiet"]);
+
+    fs::write(&path, "fn foo() { 1 }\n").unwrap();
+
+    git(&["add", "foo.rs"]);
+    git(&["commit", "--quiet", "--message", "foo"]);
+
+    fs::write(&path, "fn foo() { 2 }\n").unwrap();
+
+    let diff = Diff {
+      encoding: None,
+      file: path.clone(),
+      interactive: false,
+      language: None,
+      other: None,
+      rev: Some("HEAD".into()),
+      watch: false,
+    };
+
+    assert_eq!(
+      diff.read_revision(&path, "HEAD").unwrap().bytes(),
+      b"fn foo() { 1 }\n",
+    );
+
+    let (old, new) = diff.panes(&diff.language().unwrap()).unwrap();
+
+    assert_eq!(old.source.bytes(), b"fn foo() { 1 }\n");
+    assert_eq!(new.source.bytes(), b"fn foo() { 2 }\n");
+  }
+}
@@ -0,0 +1,363 @@
+use super::*;
+
+pub(crate) struct Pane {
+  area: Rect,
+  label: String,
+  pub(crate) source: Source,
+  state: State,
+  pub(crate) tree: Tree,
+}
+
+impl Pane {
+  fn chunks(&self) -> Rc<[Rect]> {
+    Layout::default()
+      .direction(Direction::Vertical)
+      .constraints([Constraint::Length(1), Constraint::Min(0)])
+      .split(self.area)
+  }
+
+  fn draw(&self, frame: &mut Frame, changes: &HashMap<usize, Change>) {
+    let chunks = self.chunks();
+
+    frame.render_widget(
+      Paragraph::new(Span::styled(
+        self.label.as_str(),
+        Style::default()
+          .fg(Color::Cyan)
+          .add_modifier(Modifier::BOLD),
+      )),
+      chunks[0],
+    );
+
+    frame.render_widget(
+      TreePanel::new(&self.tree, &self.source, &self.state).changes(changes),
+      chunks[1],
+    );
+  }
+
+  fn max_horizontal_offset(&self) -> u16 {
+    TreePanel::new(&self.tree, &self.source, &self.state)
+      .max_horizontal_offset(self.chunks()[1])
+  }
+
+  pub(crate) fn new(
+    label: String,
+    source: Source,
+    language: &TreeSitterLanguage,
+  ) -> Result<Self> {
+    let mut parser = Parser::new();
+
+    parser.set_language(language)?;
+
+    let tree = source
+      .parse(&mut parser, None)
+      .ok_or_else(|| anyhow!("failed to parse `{label}`"))?;
+
+    Ok(Self {
+      area: Rect::default(),
+      label,
+      source,
+      state: State::new(tree.root_node().id()),
+      tree,
+    })
+  }
+}
+
+/// Rebuilds both panes of a diff from their files.
+type Reload = Box<dyn Fn() -> Result<(Pane, Pane)>>;
+
+/// Shows the old and new trees of a diff side by side, moving the cursor in
+/// the new tree and keeping the old tree's cursor on the paired node.
+pub(crate) struct DiffView {
+  diff: TreeDiff,
+  new: Pane,
+  old: Pane,
+  reload: Option<Reload>,
+  terminal_height: u16,
+  watch: Vec<PathBuf>,
+}
+
+impl DiffView {
+  /// Lays out both panes and clamps their shared horizontal offset to the
+  /// new widths before drawing them, so widening the terminal can't leave
+  /// the trees scrolled past their widest rows.
+  fn draw(&mut self, frame: &mut Frame) {
+    let chunks = Layout::default()
+      .direction(Direction::Horizontal)
+      .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+      .split(frame.area());
+
+    self.old.area = chunks[0];
+    self.new.area = chunks[1];
+
+    let max_offset = self.max_horizontal_offset();
+
+    for pane in [&mut self.old, &mut self.new] {
+      pane.state.horizontal_offset =
+        pane.state.horizontal_offset.min(max_offset);
+    }
+
+    self.old.draw(frame, &self.diff.old);
+    self.new.draw(frame, &self.diff.new);
+  }
+
+  fn handle_event(&mut self, event: &Event) -> Result<ControlFlow<()>> {
+    let (state, tree) = (&mut self.new.state, &self.new.tree);
+
+    match event {
+      Event::FileChanged => self.reload()?,
+      Event::Quit => return Ok(ControlFlow::Break(())),
+      Event::MoveDown => state.move_down(tree)?,
+      Event::MoveLeft => state.move_left(tree)?,
+      Event::MoveRight => state.move_right(tree)?,
+      Event::MoveToBottom => state.move_to_bottom(tree),
+      Event::MoveToTop => state.move_to_top(tree),
+      Event::MoveUp => state.move_up(tree)?,
+      Event::ScrollDown => state.scroll_down(tree, self.terminal_height),
+      Event::ScrollUp => state.scroll_up(tree, self.terminal_height),
+      Event::ScrollLeft => {
+        state.scroll_left();
+        self.old.state.scroll_left();
+      }
+      Event::ScrollRight => {
+        let max_offset = self.max_horizontal_offset();
+
+        self.new.state.scroll_right(max_offset);
+        self.old.state.scroll_right(max_offset);
+      }
+      Event::ToggleCollapse => state.toggle_collapse(tree)?,
+      _ => {}
+    }
+
+    self.sync();
+
+    Ok(ControlFlow::Continue(()))
+  }
+
+  fn max_horizontal_offset(&self) -> u16 {
+    self
+      .old
+      .max_horizontal_offset()
+      .max(self.new.max_horizontal_offset())
+  }
+
+  pub(crate) fn new(diff: TreeDiff, old: Pane, new: Pane) -> Self {
+    Self {
+      diff,
+      new,
+      old,
+      reload: None,
+      terminal_height: 0,
+      watch: Vec::new(),
+    }
+  }
+
+  /// Rereads both panes with `reload` and diffs them again, carrying the new
+  /// tree's cursor and folds over. A file that's gone is left as it was until
+  /// it's back.
+  fn reload(&mut self) -> Result {
+    let Some(reload) = &self.reload else {
+      return Ok(());
+    };
+
+    let (mut old, mut new) = match reload() {
+      Ok(panes) => panes,
+      Err(error)
+        if error.chain().any(|cause| {
+          cause
+            .downcast_ref::<io::Error>()
+            .is_some_and(|error| error.kind() == io::ErrorKind::NotFound)
+        }) =>
+      {
+        return Ok(());
+      }
+      Err(error) => return Err(error),
+    };
+
+    Session::capture(&self.new.state, &self.new.tree)
+      .restore(&mut new.state, &new.tree);
+
+    for (pane, previous) in [(&mut old, &self.old), (&mut new, &self.new)] {
+      pane.area = previous.area;
+      pane.state.horizontal_offset = previous.state.horizontal_offset;
+    }
+
+    self.diff =
+      TreeDiff::new((&old.tree, &old.source), (&new.tree, &new.source));
+
+    self.old = old;
+    self.new = new;
+
+    Ok(())
+  }
+
+  pub(crate) fn run(mut self) -> Result {
+    let mut terminal = Terminal::new()?;
+
+    let (tx, rx) = channel();
+
+    let _input = Input::new(tx.clone());
+
+    let _watchers = self
+      .watch
+      .iter()
+      .map(|path| {
+        Watcher::new(
+          &WatchPaths {
+            source: Some(path.clone()),
+            ..WatchPaths::default()
+          },
+          &tx,
+        )
+      })
+      .collect::<Result<Vec<_>>>()?;
+
+    loop {
+      terminal.draw(|frame| {
+        self.terminal_height = frame.area().height;
+
+        for pane in [&mut self.old, &mut self.new] {
+          pane
+            .state
+            .ensure_cursor_in_view(&pane.tree, self.terminal_height);
+        }
+
+        self.draw(frame);
+      })?;
+
+      let event = match rx.recv() {
+        Ok(ChannelEvent::Crossterm(event)) => {
+          Event::from_crossterm(&event, &Mode::Normal)
+        }
+        Ok(ChannelEvent::FileChanged) => Some(Event::FileChanged),
+        Ok(
+          ChannelEvent::FileDeleted
+          | ChannelEvent::GrammarBuildFailed(_)
+          | ChannelEvent::GrammarChanged
+          | ChannelEvent::GrammarSourceChanged
+          | ChannelEvent::QueryChanged,
+        ) => None,
+        Err(_) => break,
+      };
+
+      if let Some(event) = event {
+        if self.handle_event(&event)?.is_break() {
+          break;
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Moves the old tree's cursor to the partner of the new tree's cursor, or
+  /// of its closest ancestor that has one.
+  fn sync(&mut self) {
+    let Ok(node) = self.new.state.node(&self.new.tree) else {
+      return;
+    };
+
+    let partner = self
+      .new
+      .tree
+      .root_node()
+      .path_to(node)
+      .into_iter()
+      .flatten()
+      .rev()
+      .find_map(|node| self.diff.partner(node.id()));
+
+    if let Some(id) = partner {
+      self.old.state.cursor = id;
+    }
+  }
+
+  /// Watches `paths` for changes, rebuilding both panes with `reload` when
+  /// one changes.
+  pub(crate) fn watch(
+    self,
+    paths: Vec<PathBuf>,
+    reload: impl Fn() -> Result<(Pane, Pane)> + 'static,
+  ) -> Self {
+    Self {
+      reload: Some(Box::new(reload)),
+      watch: paths,
+      ..self
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn file_changed_diffs_again() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let path = tempdir.path().join("foo.rs");
+
+    let mut view = view(&path);
+
+    view.new.state.cursor = view.new.tree.root_node().child(0).unwrap().id();
+    view.new.state.horizontal_offset = 2;
+
+    assert!(view.diff.new.is_empty());
+
+    fs::write(&path, "fn foo() {}\nstruct Bar;\n").unwrap();
+
+    assert!(view
+      .handle_event(&Event::FileChanged)
+      .unwrap()
+      .is_continue());
+
+    assert_eq!(view.new.source.bytes(), b"fn foo() {}\nstruct Bar;\n");
+    assert!(!view.diff.new.is_empty());
+
+    let function = view.new.tree.root_node().child(0).unwrap();
+
+    assert_eq!(view.new.state.cursor, function.id());
+    assert_eq!(view.new.state.horizontal_offset, 2);
+    assert_eq!(
+      view.old.state.cursor,
+      view.old.tree.root_node().child(0).unwrap().id()
+    );
+  }
+
+  #[test]
+  fn missing_file_is_kept_until_it_comes_back() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let mut view = view(&tempdir.path().join("foo.rs"));
+
+    let tree = view.new.tree.root_node().id();
+
+    assert!(view
+      .handle_event(&Event::FileChanged)
+      .unwrap()
+      .is_continue());
+
+    assert_eq!(view.new.tree.root_node().id(), tree);
+  }
+  fn pane(code: &str) -> Pane {
+    Pane::new(
+      "foo.rs".into(),
+      Source::new(code.into(), None),
+      &tree_sitter_rust::LANGUAGE.into(),
+    )
+    .unwrap()
+  }
+
+  fn view(path: &Path) -> DiffView {
+    let (old, new) = (pane("fn foo() {}"), pane("fn foo() {}"));
+
+    let diff =
+      TreeDiff::new((&old.tree, &old.source), (&new.tree, &new.source));
+
+    let path = path.to_owned();
+
+    DiffView::new(diff, old, new).watch(vec![path.clone()], move || {
+      Ok((pane("fn foo() {}"), pane(&fs::read_to_string(&path)?)))
+    })
+  }
+}
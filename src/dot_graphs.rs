@@ -0,0 +1,149 @@
+use super::*;
+
+/// Streams the parser's internal parse stack graphs to a file while parsing,
+/// rendering them to SVG with Graphviz's `dot` when the file ends in `.html`.
+pub(crate) struct DotGraphs {
+  /// Where graphs to render are collected until parsing finishes.
+  graphs: Option<NamedTempFile>,
+  path: PathBuf,
+}
+
+impl DotGraphs {
+  /// Stops printing graphs and, when rendering, renders them into the file.
+  /// Without `dot` installed, the raw graphs are written next to it instead.
+  pub(crate) fn finish(self, parser: &mut Parser) -> Result {
+    parser.stop_printing_dot_graphs();
+
+    let Some(graphs) = self.graphs else {
+      return Ok(());
+    };
+
+    let graphs =
+      fs::read_to_string(graphs.path()).context("failed to read dot graphs")?;
+
+    match Self::render(&graphs) {
+      Ok(html) => fs::write(&self.path, html).with_context(|| {
+        format!("failed to write dot graph file `{}`", self.path.display())
+      }),
+      Err(error)
+        if error
+          .downcast_ref::<io::Error>()
+          .is_some_and(|error| error.kind() == io::ErrorKind::NotFound) =>
+      {
+        let path = self.path.with_extension("dot");
+
+        eprintln!(
+          "warning: `dot` not found, is Graphviz installed? Writing unrendered \
+           graphs to `{}`",
+          path.display()
+        );
+
+        fs::write(&path, graphs).with_context(|| {
+          format!("failed to write dot graph file `{}`", path.display())
+        })
+      }
+      Err(error) => Err(error),
+    }
+  }
+
+  /// Renders each graph with its own `dot` process, since given several
+  /// graphs at once it writes one SVG document after another.
+  fn render(graphs: &str) -> Result<String> {
+    let mut html = String::from("<style>svg { width: 100%; }</style>\n");
+
+    for graph in Self::split(graphs) {
+      let mut dot = Command::new("dot")
+        .arg("-Tsvg")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+      if let Some(mut stdin) = dot.stdin.take() {
+        stdin.write_all(graph.as_bytes())?;
+      }
+
+      let output = dot.wait_with_output().context("failed to run `dot`")?;
+
+      if !output.status.success() {
+        return Err(anyhow!(
+          "`dot` failed to render graph: {}",
+          String::from_utf8_lossy(&output.stderr).trim()
+        ));
+      }
+
+      let svg = String::from_utf8_lossy(&output.stdout);
+
+      // Each SVG starts with an XML prolog that doesn't belong in HTML.
+      html.push_str(&svg[svg.find("<svg").unwrap_or(0)..]);
+    }
+
+    Ok(html)
+  }
+
+  /// Splits the parser's output into graphs, each starting at an unindented
+  /// `graph` or `digraph` line.
+  fn split(graphs: &str) -> Vec<&str> {
+    let mut starts = graphs
+      .match_indices('\n')
+      .map(|(i, _)| i + 1)
+      .filter(|&i| {
+        graphs[i..].starts_with("graph ") || graphs[i..].starts_with("digraph ")
+      })
+      .collect::<Vec<_>>();
+
+    starts.insert(0, 0);
+    starts.push(graphs.len());
+
+    starts
+      .windows(2)
+      .map(|window| graphs[window[0]..window[1]].trim())
+      .filter(|graph| !graph.is_empty())
+      .collect()
+  }
+
+  pub(crate) fn start(path: &Path, parser: &mut Parser) -> Result<Self> {
+    if path
+      .extension()
+      .is_some_and(|extension| extension == "html")
+    {
+      let graphs =
+        NamedTempFile::new().context("failed to create temporary file")?;
+
+      parser.print_dot_graphs(graphs.as_file());
+
+      return Ok(Self {
+        graphs: Some(graphs),
+        path: path.to_owned(),
+      });
+    }
+
+    let file = File::create(path).with_context(|| {
+      format!("failed to create dot graph file `{}`", path.display())
+    })?;
+
+    parser.print_dot_graphs(&file);
+
+    Ok(Self {
+      graphs: None,
+      path: path.to_owned(),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn split() {
+    assert_eq!(
+      DotGraphs::split(
+        "graph {\nlabel=\"a\"\n}\n\ndigraph stack {\n  node [shape=point]\n}\n\n"
+      ),
+      ["graph {\nlabel=\"a\"\n}", "digraph stack {\n  node [shape=point]\n}"],
+    );
+
+    assert!(DotGraphs::split("").is_empty());
+  }
+}
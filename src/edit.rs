@@ -0,0 +1,237 @@
+/// An edit being typed, with the cursor as a byte offset into its new text.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Draft {
+  pub(crate) cursor: usize,
+  pub(crate) edit: Edit,
+}
+
+impl Draft {
+  pub(crate) fn backspace(&mut self) {
+    if let Some(c) = self.edit.new_text[..self.cursor].chars().next_back() {
+      self.cursor -= c.len_utf8();
+      self.edit.new_text.remove(self.cursor);
+    }
+  }
+
+  pub(crate) fn delete(&mut self) {
+    if self.cursor < self.edit.new_text.len() {
+      self.edit.new_text.remove(self.cursor);
+    }
+  }
+
+  /// Moves the cursor to the end of its line.
+  pub(crate) fn end(&mut self) {
+    let rest = &self.edit.new_text[self.cursor..];
+
+    self.cursor += rest.find('\n').unwrap_or(rest.len());
+  }
+
+  /// Moves the cursor to the start of its line.
+  pub(crate) fn home(&mut self) {
+    self.cursor = self.edit.new_text[..self.cursor]
+      .rfind('\n')
+      .map_or(0, |i| i + 1);
+  }
+
+  pub(crate) fn insert(&mut self, c: char) {
+    self.edit.new_text.insert(self.cursor, c);
+    self.cursor += c.len_utf8();
+  }
+
+  pub(crate) fn left(&mut self) {
+    if let Some(c) = self.edit.new_text[..self.cursor].chars().next_back() {
+      self.cursor -= c.len_utf8();
+    }
+  }
+
+  /// Returns the line the cursor is on, split at the cursor.
+  pub(crate) fn line(&self) -> (&str, &str) {
+    let (before, after) = self.edit.new_text.split_at(self.cursor);
+
+    (
+      before.rsplit('\n').next().unwrap_or_default(),
+      after.split('\n').next().unwrap_or_default(),
+    )
+  }
+
+  /// Starts editing `text` at `start_byte`, with the cursor at its end.
+  pub(crate) fn new(text: String, start_byte: usize) -> Self {
+    Self {
+      cursor: text.len(),
+      edit: Edit {
+        new_text: text.clone(),
+        old_text: text,
+        start_byte,
+      },
+    }
+  }
+
+  pub(crate) fn right(&mut self) {
+    if let Some(c) = self.edit.new_text[self.cursor..].chars().next() {
+      self.cursor += c.len_utf8();
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Edit {
+  pub(crate) new_text: String,
+  pub(crate) old_text: String,
+  pub(crate) start_byte: usize,
+}
+
+impl Edit {
+  pub(crate) fn inverse(&self) -> Self {
+    Self {
+      new_text: self.old_text.clone(),
+      old_text: self.new_text.clone(),
+      start_byte: self.start_byte,
+    }
+  }
+}
+
+#[derive(Debug)]
+pub(crate) struct History {
+  redo: Vec<Edit>,
+  /// How many edits were on the undo stack when the file was last loaded or
+  /// written, or `None` once undoing and redoing can't get back there.
+  saved: Option<usize>,
+  undo: Vec<Edit>,
+}
+
+impl Default for History {
+  fn default() -> Self {
+    Self {
+      redo: Vec::new(),
+      saved: Some(0),
+      undo: Vec::new(),
+    }
+  }
+}
+
+impl History {
+  pub(crate) fn clear(&mut self) {
+    *self = Self::default();
+  }
+
+  /// Whether the edits applied differ from those last loaded or written.
+  pub(crate) fn is_modified(&self) -> bool {
+    self.saved != Some(self.undo.len())
+  }
+
+  pub(crate) fn mark_saved(&mut self) {
+    self.saved = Some(self.undo.len());
+  }
+
+  pub(crate) fn push(&mut self, edit: Edit) {
+    if self.saved.is_some_and(|saved| saved > self.undo.len()) {
+      self.saved = None;
+    }
+
+    self.redo.clear();
+    self.undo.push(edit);
+  }
+
+  /// Returns the edit to reapply, if any.
+  pub(crate) fn redo(&mut self) -> Option<Edit> {
+    let edit = self.redo.pop()?;
+    self.undo.push(edit.clone());
+    Some(edit)
+  }
+
+  /// Returns the edit that reverts the most recent change, if any.
+  pub(crate) fn undo(&mut self) -> Option<Edit> {
+    let edit = self.undo.pop()?;
+    let inverse = edit.inverse();
+    self.redo.push(edit);
+    Some(inverse)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn draft() {
+    let mut draft = Draft::new("fn foo() {\n}".into(), 3);
+
+    draft.left();
+    draft.home();
+    draft.insert('x');
+
+    assert_eq!(draft.line(), ("x", "}"));
+
+    draft.left();
+    draft.left();
+    draft.end();
+    draft.backspace();
+    draft.insert('é');
+    draft.left();
+    draft.delete();
+
+    assert_eq!(draft.edit.new_text, "fn foo() \nx}");
+    assert_eq!(draft.line(), ("fn foo() ", ""));
+
+    draft.right();
+    draft.right();
+
+    assert_eq!(draft.line(), ("x", "}"));
+    assert_eq!(draft.edit.old_text, "fn foo() {\n}");
+  }
+
+  fn edit(old_text: &str, new_text: &str) -> Edit {
+    Edit {
+      new_text: new_text.into(),
+      old_text: old_text.into(),
+      start_byte: 3,
+    }
+  }
+
+  #[test]
+  fn history() {
+    let mut history = History::default();
+
+    assert_eq!(history.undo(), None);
+
+    history.push(edit("foo", "bar"));
+    history.push(edit("bar", "baz"));
+
+    assert_eq!(history.undo(), Some(edit("baz", "bar")));
+    assert_eq!(history.undo(), Some(edit("bar", "foo")));
+    assert_eq!(history.undo(), None);
+
+    assert_eq!(history.redo(), Some(edit("foo", "bar")));
+
+    history.push(edit("bar", "qux"));
+
+    assert_eq!(history.redo(), None);
+    assert_eq!(history.undo(), Some(edit("qux", "bar")));
+  }
+
+  #[test]
+  fn history_modified() {
+    let mut history = History::default();
+
+    assert!(!history.is_modified());
+
+    history.push(edit("foo", "bar"));
+
+    assert!(history.is_modified());
+
+    history.mark_saved();
+    history.undo();
+
+    assert!(history.is_modified());
+
+    history.redo();
+
+    assert!(!history.is_modified());
+
+    history.undo();
+    history.push(edit("foo", "baz"));
+    history.undo();
+
+    assert!(history.is_modified());
+  }
+}
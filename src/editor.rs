@@ -0,0 +1,139 @@
+use super::*;
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Editor {
+  arguments: Vec<String>,
+  program: String,
+}
+
+impl Editor {
+  const DEFAULT: &'static str = "vi";
+
+  /// Opens `path` at `position`, whose column counts characters, not bytes.
+  pub(crate) fn command(&self, path: &Path, position: Point) -> Command {
+    let (row, column) = (position.row + 1, position.column + 1);
+
+    let path = path.display();
+
+    let name = Path::new(&self.program)
+      .file_stem()
+      .and_then(|name| name.to_str())
+      .unwrap_or_default();
+
+    let location = match name {
+      "code" | "code-insiders" | "codium" => {
+        vec!["--goto".into(), format!("{path}:{row}:{column}")]
+      }
+      "hx" | "helix" | "micro" | "subl" | "zed" => {
+        vec![format!("{path}:{row}:{column}")]
+      }
+      "emacs" | "emacsclient" | "kak" => {
+        vec![format!("+{row}:{column}"), path.to_string()]
+      }
+      "nano" => vec![format!("+{row},{column}"), path.to_string()],
+      "nvim" | "vim" | "gvim" | "mvim" => {
+        vec![
+          format!("+call setcursorcharpos({row}, {column})"),
+          path.to_string(),
+        ]
+      }
+      _ => vec![format!("+{row}"), path.to_string()],
+    };
+
+    let mut command = Command::new(&self.program);
+
+    command.args(&self.arguments).args(location);
+
+    command
+  }
+
+  pub(crate) fn from_env() -> Result<Self> {
+    Self::parse(
+      &env::var("VISUAL")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .or_else(|| env::var("EDITOR").ok())
+        .unwrap_or_default(),
+    )
+  }
+
+  /// Splits `value` into a program and its arguments with shell quoting
+  /// rules, so paths with spaces can be quoted.
+  fn parse(value: &str) -> Result<Self> {
+    let mut words = shell_words::split(value)
+      .with_context(|| format!("invalid editor command `{value}`"))?
+      .into_iter();
+
+    Ok(Self {
+      program: words.next().unwrap_or_else(|| Self::DEFAULT.into()),
+      arguments: words.collect(),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn command() {
+    #[track_caller]
+    fn case(editor: &str, expected: &[&str]) {
+      let command = Editor::parse(editor)
+        .unwrap()
+        .command(Path::new("foo.rs"), Point { row: 2, column: 4 });
+
+      assert_eq!(
+        std::iter::once(command.get_program())
+          .chain(command.get_args())
+          .collect::<Vec<_>>(),
+        expected,
+      );
+    }
+
+    case("vi", &["vi", "+3", "foo.rs"]);
+    case("nvim", &["nvim", "+call setcursorcharpos(3, 5)", "foo.rs"]);
+    case(
+      "/usr/bin/vim",
+      &["/usr/bin/vim", "+call setcursorcharpos(3, 5)", "foo.rs"],
+    );
+    case("nano", &["nano", "+3,5", "foo.rs"]);
+    case("emacsclient -t", &["emacsclient", "-t", "+3:5", "foo.rs"]);
+    case("hx", &["hx", "foo.rs:3:5"]);
+    case("code --wait", &["code", "--wait", "--goto", "foo.rs:3:5"]);
+    case("ed", &["ed", "+3", "foo.rs"]);
+    case(
+      "'/Applications/Sublime Text.app/subl' -w",
+      &["/Applications/Sublime Text.app/subl", "-w", "foo.rs:3:5"],
+    );
+  }
+
+  #[test]
+  fn parse() {
+    assert_eq!(
+      Editor::parse("  code  --wait ").unwrap(),
+      Editor {
+        arguments: vec!["--wait".into()],
+        program: "code".into(),
+      }
+    );
+
+    assert_eq!(
+      Editor::parse("").unwrap(),
+      Editor {
+        arguments: Vec::new(),
+        program: "vi".into(),
+      }
+    );
+
+    assert_eq!(
+      Editor::parse(r#""/opt/my editor/bin/edit" --title "a b""#).unwrap(),
+      Editor {
+        arguments: vec!["--title".into(), "a b".into()],
+        program: "/opt/my editor/bin/edit".into(),
+      }
+    );
+
+    assert!(Editor::parse("vim 'foo").is_err());
+  }
+}
@@ -0,0 +1,381 @@
+use super::*;
+
+/// Decodes Latin-1 for tree-sitter, where every byte is its own code point.
+struct Latin1;
+
+impl Decode for Latin1 {
+  fn decode(bytes: &[u8]) -> (i32, u32) {
+    bytes.first().map_or((0, 0), |&byte| (i32::from(byte), 1))
+  }
+}
+
+/// The encoding of a source file. Trees are always parsed from the file's own
+/// bytes, so byte offsets and columns count bytes of this encoding.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum Encoding {
+  Latin1,
+  Utf16Be,
+  Utf16Le,
+  #[default]
+  Utf8,
+}
+
+impl Display for Encoding {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Latin1 => write!(f, "latin-1"),
+      Self::Utf16Be => write!(f, "utf-16be"),
+      Self::Utf16Le => write!(f, "utf-16le"),
+      Self::Utf8 => write!(f, "utf-8"),
+    }
+  }
+}
+
+impl FromStr for Encoding {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s.to_lowercase().replace(['-', '_'], "").as_str() {
+      "latin1" | "iso88591" => Ok(Self::Latin1),
+      "utf16be" => Ok(Self::Utf16Be),
+      "utf16le" => Ok(Self::Utf16Le),
+      "utf8" => Ok(Self::Utf8),
+      _ => Err(anyhow!("unknown encoding `{s}`")),
+    }
+  }
+}
+
+impl Encoding {
+  /// Counts the characters in `bytes`, taking each byte or unpaired surrogate
+  /// that doesn't decode as one character.
+  pub(crate) fn char_count(self, bytes: &[u8]) -> usize {
+    match self {
+      Self::Latin1 => bytes.len(),
+      Self::Utf16Be | Self::Utf16Le => {
+        char::decode_utf16(self.units(bytes)).count() + bytes.len() % 2
+      }
+      Self::Utf8 => bytes
+        .utf8_chunks()
+        .map(|chunk| chunk.valid().chars().count() + chunk.invalid().len())
+        .sum(),
+    }
+  }
+
+  /// Decodes `bytes`, escaping anything that isn't valid in this encoding as
+  /// `\xNN` for stray bytes or `\u{NNNN}` for unpaired surrogates, so the
+  /// text can always be shown.
+  pub(crate) fn decode(self, bytes: &[u8]) -> Cow<'_, str> {
+    match self {
+      Self::Latin1 => bytes.iter().map(|&byte| char::from(byte)).collect(),
+      Self::Utf16Be | Self::Utf16Le => {
+        let mut text = String::new();
+
+        for c in char::decode_utf16(self.units(bytes)) {
+          match c {
+            Ok(c) => text.push(c),
+            Err(error) => {
+              write!(text, "\\u{{{:x}}}", error.unpaired_surrogate()).ok();
+            }
+          }
+        }
+
+        if let [.., byte] = bytes[bytes.len() / 2 * 2..] {
+          write!(text, "\\x{byte:02x}").ok();
+        }
+
+        Cow::Owned(text)
+      }
+      Self::Utf8 => {
+        if let Ok(text) = str::from_utf8(bytes) {
+          return Cow::Borrowed(text);
+        }
+
+        let mut text = String::new();
+
+        for chunk in bytes.utf8_chunks() {
+          text.push_str(chunk.valid());
+
+          for byte in chunk.invalid() {
+            write!(text, "\\x{byte:02x}").ok();
+          }
+        }
+
+        Cow::Owned(text)
+      }
+    }
+  }
+
+  /// Decodes `bytes` only if they are entirely valid in this encoding.
+  pub(crate) fn decode_exact(self, bytes: &[u8]) -> Option<String> {
+    match self {
+      Self::Latin1 => Some(self.decode(bytes).into_owned()),
+      Self::Utf16Be | Self::Utf16Le => {
+        if !bytes.len().is_multiple_of(2) {
+          return None;
+        }
+
+        char::decode_utf16(self.units(bytes))
+          .collect::<Result<_, _>>()
+          .ok()
+      }
+      Self::Utf8 => str::from_utf8(bytes).ok().map(str::to_string),
+    }
+  }
+
+  /// Guesses the encoding of `bytes` from a byte order mark or, failing that,
+  /// from the zero bytes UTF-16 puts next to every ASCII character. Anything
+  /// else is read as UTF-8 if it's valid UTF-8 and as Latin-1 otherwise, since
+  /// every byte is valid Latin-1.
+  pub(crate) fn detect(bytes: &[u8]) -> Self {
+    const SAMPLE: usize = 512;
+
+    match bytes {
+      [0xff, 0xfe, ..] => return Self::Utf16Le,
+      [0xfe, 0xff, ..] => return Self::Utf16Be,
+      _ => {}
+    }
+
+    let utf8 = str::from_utf8(bytes).is_ok();
+
+    if utf8 && !bytes.contains(&0) {
+      return Self::Utf8;
+    }
+
+    let pairs = bytes[..bytes.len().min(SAMPLE)].chunks_exact(2);
+
+    let total = pairs.len();
+
+    let (even, odd) = pairs.fold((0, 0), |(even, odd), pair| {
+      (
+        even + usize::from(pair[0] == 0),
+        odd + usize::from(pair[1] == 0),
+      )
+    });
+
+    if total > 0 && even == 0 && odd * 2 > total {
+      Self::Utf16Le
+    } else if total > 0 && odd == 0 && even * 2 > total {
+      Self::Utf16Be
+    } else if utf8 {
+      Self::Utf8
+    } else {
+      Self::Latin1
+    }
+  }
+
+  pub(crate) fn encode(self, text: &str) -> Result<Vec<u8>> {
+    match self {
+      Self::Latin1 => text
+        .chars()
+        .map(|c| {
+          u8::try_from(c)
+            .map_err(|_| anyhow!("`{c}` can't be written as {self}"))
+        })
+        .collect(),
+      Self::Utf16Be => {
+        Ok(text.encode_utf16().flat_map(u16::to_be_bytes).collect())
+      }
+      Self::Utf16Le => {
+        Ok(text.encode_utf16().flat_map(u16::to_le_bytes).collect())
+      }
+      Self::Utf8 => Ok(text.as_bytes().to_vec()),
+    }
+  }
+
+  /// Returns the number of bytes `text` takes up in this encoding.
+  pub(crate) fn encoded_len(self, text: &str) -> usize {
+    match self {
+      Self::Latin1 => text.chars().count(),
+      Self::Utf16Be | Self::Utf16Le => text.encode_utf16().count() * 2,
+      Self::Utf8 => text.len(),
+    }
+  }
+
+  /// Returns the byte offset just past each newline in `bytes`.
+  pub(crate) fn line_starts(
+    self,
+    bytes: &[u8],
+  ) -> impl Iterator<Item = usize> + '_ {
+    let width = self.width();
+
+    bytes
+      .chunks_exact(width)
+      .enumerate()
+      .filter(move |(_, unit)| match self {
+        Self::Latin1 | Self::Utf8 => unit == b"\n",
+        Self::Utf16Be => unit == &[0, b'\n'],
+        Self::Utf16Le => unit == &[b'\n', 0],
+      })
+      .map(move |(i, _)| (i + 1) * width)
+  }
+
+  pub(crate) fn parse(
+    self,
+    parser: &mut Parser,
+    bytes: &[u8],
+    old_tree: Option<&Tree>,
+  ) -> Option<Tree> {
+    match self {
+      Self::Latin1 => parser.parse_custom_encoding::<Latin1, _, _>(
+        &mut |i, _| bytes.get(i..).unwrap_or_default(),
+        old_tree,
+        None,
+      ),
+      Self::Utf16Be | Self::Utf16Le => {
+        // tree-sitter reads the units back as bytes in the order it's told
+        // they're in, so they keep the file's byte order in memory.
+        let units = bytes
+          .chunks_exact(2)
+          .map(|pair| u16::from_ne_bytes([pair[0], pair[1]]))
+          .collect::<Vec<_>>();
+
+        if self == Self::Utf16Be {
+          parser.parse_utf16_be(units, old_tree)
+        } else {
+          parser.parse_utf16_le(units, old_tree)
+        }
+      }
+      Self::Utf8 => parser.parse(bytes, old_tree),
+    }
+  }
+
+  fn units(self, bytes: &[u8]) -> impl Iterator<Item = u16> + '_ {
+    bytes.chunks_exact(2).map(move |pair| {
+      let pair = [pair[0], pair[1]];
+
+      if self == Self::Utf16Be {
+        u16::from_be_bytes(pair)
+      } else {
+        u16::from_le_bytes(pair)
+      }
+    })
+  }
+
+  /// The number of bytes in each code unit.
+  fn width(self) -> usize {
+    match self {
+      Self::Latin1 | Self::Utf8 => 1,
+      Self::Utf16Be | Self::Utf16Le => 2,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn decode_escapes_invalid_input() {
+    assert_eq!(Encoding::Utf8.decode(b"caf\xe9!"), "caf\\xe9!");
+
+    assert_eq!(Encoding::Latin1.decode(b"caf\xe9!"), "café!");
+
+    assert_eq!(
+      Encoding::Utf16Le.decode(&[b'a', 0, 0x00, 0xd8, b'b', 0, b'c']),
+      "a\\u{d800}b\\x63"
+    );
+  }
+
+  #[test]
+  fn decode_exact() {
+    assert_eq!(Encoding::Utf8.decode_exact(b"caf\xe9"), None);
+    assert_eq!(Encoding::Utf8.decode_exact(b"cafe"), Some("cafe".into()));
+    assert_eq!(
+      Encoding::Utf16Be.decode_exact(&utf16be("café")),
+      Some("café".into())
+    );
+    assert_eq!(Encoding::Utf16Le.decode_exact(b"a"), None);
+  }
+
+  #[test]
+  fn detect() {
+    #[track_caller]
+    fn case(bytes: &[u8], expected: Encoding) {
+      assert_eq!(Encoding::detect(bytes), expected);
+    }
+
+    case(b"fn foo() {}", Encoding::Utf8);
+    case(b"\xef\xbb\xbffn foo() {}", Encoding::Utf8);
+    case(b"caf\xe9", Encoding::Latin1);
+    case(b"caf\xc3\xa9", Encoding::Utf8);
+    case(b"ab\0\0cd", Encoding::Utf8);
+    case(b"\xe9\xe9\0\0\xe9\xe9", Encoding::Latin1);
+    case(&utf16le("\u{feff}fn foo() {}"), Encoding::Utf16Le);
+    case(&utf16be("\u{feff}fn foo() {}"), Encoding::Utf16Be);
+    case(&utf16le("fn foo() {}"), Encoding::Utf16Le);
+    case(&utf16be("fn foo() {}"), Encoding::Utf16Be);
+    case(b"", Encoding::Utf8);
+  }
+
+  #[test]
+  fn encode() {
+    assert_eq!(Encoding::Latin1.encode("café").unwrap(), b"caf\xe9");
+    assert_eq!(utf16le("a€"), [b'a', 0, 0xac, 0x20]);
+    assert_eq!(utf16be("a€"), [0, b'a', 0x20, 0xac]);
+    assert!(Encoding::Latin1.encode("€").is_err());
+  }
+
+  #[test]
+  fn from_str() {
+    assert_eq!("UTF-16LE".parse::<Encoding>().unwrap(), Encoding::Utf16Le);
+    assert_eq!("utf16be".parse::<Encoding>().unwrap(), Encoding::Utf16Be);
+    assert_eq!("utf_8".parse::<Encoding>().unwrap(), Encoding::Utf8);
+    assert_eq!("ISO-8859-1".parse::<Encoding>().unwrap(), Encoding::Latin1);
+    assert!("ebcdic".parse::<Encoding>().is_err());
+  }
+
+  #[test]
+  fn line_starts() {
+    assert_eq!(
+      Encoding::Utf8.line_starts(b"a\nb\n").collect::<Vec<_>>(),
+      [2, 4]
+    );
+
+    assert_eq!(
+      Encoding::Utf16Le
+        .line_starts(&utf16le("a\nb\u{a0a}"))
+        .collect::<Vec<_>>(),
+      [4]
+    );
+  }
+
+  #[test]
+  fn parse_keeps_byte_offsets() {
+    #[track_caller]
+    fn case(encoding: Encoding, bytes: &[u8], name: Range<usize>) {
+      let mut parser = Parser::new();
+
+      parser
+        .set_language(&tree_sitter_rust::LANGUAGE.into())
+        .unwrap();
+
+      let tree = encoding.parse(&mut parser, bytes, None).unwrap();
+
+      let identifier = tree
+        .root_node()
+        .named_children(&mut tree.walk())
+        .last()
+        .unwrap()
+        .child_by_field_name("name")
+        .unwrap();
+
+      assert_eq!(identifier.byte_range(), name);
+      assert_eq!(encoding.decode(&bytes[name]), "foo");
+    }
+
+    case(Encoding::Utf8, b"fn foo() {}", 3..6);
+    case(Encoding::Utf8, b"\xef\xbb\xbffn foo() {}", 6..9);
+    case(Encoding::Latin1, b"// caf\xe9\nfn foo() {}", 11..14);
+    case(Encoding::Utf16Le, &utf16le("fn foo() {}"), 6..12);
+    case(Encoding::Utf16Le, &utf16le("\u{feff}fn foo() {}"), 8..14);
+    case(Encoding::Utf16Be, &utf16be("fn foo() {}"), 6..12);
+  }
+
+  fn utf16be(text: &str) -> Vec<u8> {
+    Encoding::Utf16Be.encode(text).unwrap()
+  }
+
+  fn utf16le(text: &str) -> Vec<u8> {
+    Encoding::Utf16Le.encode(text).unwrap()
+  }
+}
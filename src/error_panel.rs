@@ -0,0 +1,83 @@
+use super::*;
+
+pub(crate) struct ErrorPanel<'a> {
+  errors: &'a VecDeque<(String, Instant)>,
+}
+
+impl Widget for ErrorPanel<'_> {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let visible = usize::from(area.height.saturating_sub(2));
+
+    let lines = if self.errors.is_empty() {
+      vec![Line::from(Span::styled(
+        "No errors",
+        Style::default().fg(Color::DarkGray),
+      ))]
+    } else {
+      self
+        .errors
+        .iter()
+        .skip(self.errors.len().saturating_sub(visible))
+        .map(|(error, time)| {
+          Line::from(vec![
+            Span::styled(
+              format!("{:>6} ", Self::age(time.elapsed())),
+              Style::default().fg(Color::DarkGray),
+            ),
+            Span::styled(error.as_str(), Style::default().fg(Color::Red)),
+          ])
+        })
+        .collect()
+    };
+
+    Paragraph::new(lines)
+      .block(
+        Block::default()
+          .borders(Borders::ALL)
+          .title(format!(" Errors ({}) (E to close) ", self.errors.len()))
+          .title_style(
+            Style::default()
+              .fg(Color::Magenta)
+              .add_modifier(Modifier::BOLD),
+          )
+          .border_style(Style::default().fg(Color::DarkGray)),
+      )
+      .render(area, buf);
+  }
+}
+
+impl<'a> ErrorPanel<'a> {
+  /// Formats how long ago an error happened, like `12s` or `3m`.
+  fn age(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+
+    match seconds {
+      0..60 => format!("{seconds}s"),
+      60..3600 => format!("{}m", seconds / 60),
+      _ => format!("{}h", seconds / 3600),
+    }
+  }
+
+  pub(crate) fn new(errors: &'a VecDeque<(String, Instant)>) -> Self {
+    Self { errors }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn age() {
+    #[track_caller]
+    fn case(seconds: u64, expected: &str) {
+      assert_eq!(ErrorPanel::age(Duration::from_secs(seconds)), expected);
+    }
+
+    case(0, "0s");
+    case(59, "59s");
+    case(60, "1m");
+    case(3599, "59m");
+    case(7200, "2h");
+  }
+}
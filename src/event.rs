@@ -3,14 +3,36 @@ use super::*;
 #[derive(Debug, PartialEq)]
 pub(crate) enum Event {
   ClearSearch,
-  Click { row: u16 },
+  Click { column: u16, row: u16 },
+  CollapseAll,
+  CollapseKind,
+  CollapseSiblings,
+  CollapseToDepth(usize),
+  CycleProfileSort,
+  CycleQueryScope,
+  EnterEdit,
+  EnterFold,
   EnterQuery,
   EnterSearch,
+  EnterYank,
+  ExpandAll,
+  ExtendSelection { forward: bool },
+  ExtendSiblings { forward: bool },
   FileChanged,
+  FileDeleted,
+  Focus,
+  GeneratePattern,
+  GrammarBuildFailed(String),
+  GrammarChanged,
   InputBackspace,
   InputCancel,
   InputChar(char),
   InputConfirm,
+  InputDelete,
+  InputEnd,
+  InputHome,
+  InputLeft,
+  InputRight,
   JumpToMatch { forward: bool },
   MoveDown,
   MoveLeft,
@@ -18,13 +40,25 @@ pub(crate) enum Event {
   MoveToBottom,
   MoveToTop,
   MoveUp,
+  OpenEditor,
+  QueryChanged,
   Quit,
+  Redo,
   ScrollDown,
+  ScrollLeft,
+  ScrollLog { down: bool },
+  ScrollRight,
   ScrollUp,
   ToggleCollapse,
+  ToggleErrors,
   ToggleHelp,
+  ToggleLog,
+  ToggleProfile,
   ToggleSelect,
-  Yank,
+  Undo,
+  Yank(YankFormat),
+  ZoomIn,
+  ZoomOut,
 }
 
 impl Event {
@@ -39,6 +73,36 @@ impl Event {
     }
   }
 
+  /// Edits have a cursor, and Alt-Enter inserts a newline since Enter
+  /// applies the edit.
+  fn from_edit_key(event: &KeyEvent) -> Option<Self> {
+    match event.code {
+      KeyCode::Delete => Some(Self::InputDelete),
+      KeyCode::End => Some(Self::InputEnd),
+      KeyCode::Enter if event.modifiers.contains(KeyModifiers::ALT) => {
+        Some(Self::InputChar('\n'))
+      }
+      KeyCode::Home => Some(Self::InputHome),
+      KeyCode::Left => Some(Self::InputLeft),
+      KeyCode::Right => Some(Self::InputRight),
+      _ => Self::from_input_key(event),
+    }
+  }
+
+  fn from_fold_key(event: &KeyEvent) -> Self {
+    match event.code {
+      KeyCode::Char('M') => Self::CollapseAll,
+      KeyCode::Char('R') => Self::ExpandAll,
+      KeyCode::Char('f') => Self::Focus,
+      KeyCode::Char('k') => Self::CollapseKind,
+      KeyCode::Char('s') => Self::CollapseSiblings,
+      KeyCode::Char(c @ '0'..='9') => {
+        Self::CollapseToDepth(c.to_digit(10).unwrap_or_default() as usize)
+      }
+      _ => Self::InputCancel,
+    }
+  }
+
   fn from_input_key(event: &KeyEvent) -> Option<Self> {
     match event.code {
       KeyCode::Enter => Some(Self::InputConfirm),
@@ -51,18 +115,34 @@ impl Event {
 
   fn from_key(event: &KeyEvent, mode: &Mode) -> Option<Self> {
     match mode {
+      Mode::Edit => Self::from_edit_key(event),
+      Mode::Fold => Some(Self::from_fold_key(event)),
       Mode::Normal => Self::from_normal_key(event),
-      Mode::Search | Mode::Query => Self::from_input_key(event),
+      Mode::Query | Mode::Search => Self::from_input_key(event),
+      Mode::Yank => Some(Self::from_yank_key(event)),
     }
   }
 
   fn from_mouse(event: crossterm::event::MouseEvent) -> Option<Self> {
     match event.kind {
-      MouseEventKind::Down(MouseButton::Left) => {
-        Some(Self::Click { row: event.row })
+      MouseEventKind::Down(MouseButton::Left) => Some(Self::Click {
+        column: event.column,
+        row: event.row,
+      }),
+      MouseEventKind::ScrollUp
+        if event.modifiers.contains(KeyModifiers::SHIFT) =>
+      {
+        Some(Self::ScrollLeft)
+      }
+      MouseEventKind::ScrollDown
+        if event.modifiers.contains(KeyModifiers::SHIFT) =>
+      {
+        Some(Self::ScrollRight)
       }
       MouseEventKind::ScrollUp => Some(Self::ScrollUp),
       MouseEventKind::ScrollDown => Some(Self::ScrollDown),
+      MouseEventKind::ScrollLeft => Some(Self::ScrollLeft),
+      MouseEventKind::ScrollRight => Some(Self::ScrollRight),
       _ => None,
     }
   }
@@ -89,6 +169,34 @@ impl Event {
         code: KeyCode::Char('l'),
         ..
       } => Some(Self::MoveRight),
+      KeyEvent {
+        code: KeyCode::Char('J'),
+        ..
+      }
+      | KeyEvent {
+        code: KeyCode::Down,
+        modifiers: KeyModifiers::SHIFT,
+        ..
+      } => Some(Self::ExtendSelection { forward: true }),
+      KeyEvent {
+        code: KeyCode::Char('K'),
+        ..
+      }
+      | KeyEvent {
+        code: KeyCode::Up,
+        modifiers: KeyModifiers::SHIFT,
+        ..
+      } => Some(Self::ExtendSelection { forward: false }),
+      KeyEvent {
+        code: KeyCode::Right,
+        modifiers: KeyModifiers::SHIFT,
+        ..
+      } => Some(Self::ExtendSiblings { forward: true }),
+      KeyEvent {
+        code: KeyCode::Left,
+        modifiers: KeyModifiers::SHIFT,
+        ..
+      } => Some(Self::ExtendSiblings { forward: false }),
       KeyEvent {
         code: KeyCode::Char(' '),
         ..
@@ -107,6 +215,27 @@ impl Event {
         modifiers: KeyModifiers::CONTROL,
         ..
       } => Some(Self::ScrollDown),
+      KeyEvent {
+        code: KeyCode::Char('r'),
+        modifiers: KeyModifiers::CONTROL,
+        ..
+      } => Some(Self::Redo),
+      KeyEvent {
+        code: KeyCode::Char('u'),
+        ..
+      } => Some(Self::Undo),
+      KeyEvent {
+        code: KeyCode::Char('i'),
+        ..
+      } => Some(Self::EnterEdit),
+      KeyEvent {
+        code: KeyCode::Char('H') | KeyCode::Left,
+        ..
+      } => Some(Self::ScrollLeft),
+      KeyEvent {
+        code: KeyCode::Char('L') | KeyCode::Right,
+        ..
+      } => Some(Self::ScrollRight),
       KeyEvent {
         code: KeyCode::Char('/'),
         ..
@@ -134,7 +263,7 @@ impl Event {
       KeyEvent {
         code: KeyCode::Char('y'),
         ..
-      } => Some(Self::Yank),
+      } => Some(Self::EnterYank),
       KeyEvent {
         code: KeyCode::Esc, ..
       } => Some(Self::ClearSearch),
@@ -142,9 +271,66 @@ impl Event {
         code: KeyCode::Char(':'),
         ..
       } => Some(Self::EnterQuery),
+      KeyEvent {
+        code: KeyCode::Char('o'),
+        ..
+      } => Some(Self::OpenEditor),
+      KeyEvent {
+        code: KeyCode::Char('p'),
+        ..
+      } => Some(Self::GeneratePattern),
+      KeyEvent {
+        code: KeyCode::Char('P'),
+        ..
+      } => Some(Self::ToggleProfile),
+      KeyEvent {
+        code: KeyCode::Char('S'),
+        ..
+      } => Some(Self::CycleProfileSort),
+      KeyEvent {
+        code: KeyCode::Char('Q'),
+        ..
+      } => Some(Self::CycleQueryScope),
+      KeyEvent {
+        code: KeyCode::Char('D'),
+        ..
+      } => Some(Self::ToggleLog),
+      KeyEvent {
+        code: KeyCode::Char('E'),
+        ..
+      } => Some(Self::ToggleErrors),
+      KeyEvent {
+        code: KeyCode::Char('['),
+        ..
+      } => Some(Self::ScrollLog { down: false }),
+      KeyEvent {
+        code: KeyCode::Char(']'),
+        ..
+      } => Some(Self::ScrollLog { down: true }),
+      KeyEvent {
+        code: KeyCode::Char('z'),
+        ..
+      } => Some(Self::EnterFold),
+      KeyEvent {
+        code: KeyCode::Char('>'),
+        ..
+      } => Some(Self::ZoomIn),
+      KeyEvent {
+        code: KeyCode::Char('<'),
+        ..
+      } => Some(Self::ZoomOut),
       _ => None,
     }
   }
+
+  fn from_yank_key(event: &KeyEvent) -> Self {
+    match event.code {
+      KeyCode::Char(c) => {
+        YankFormat::from_key(c).map_or(Self::InputCancel, Self::Yank)
+      }
+      _ => Self::InputCancel,
+    }
+  }
 }
 
 #[cfg(test)]
@@ -168,6 +354,44 @@ mod tests {
     KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
   }
 
+  #[test]
+  fn edit_keys() {
+    #[track_caller]
+    fn case(event: KeyEvent, expected: Event) {
+      assert_eq!(Event::from_key(&event, &Mode::Edit), Some(expected));
+    }
+
+    case(key(KeyCode::Delete), Event::InputDelete);
+    case(key(KeyCode::End), Event::InputEnd);
+    case(key(KeyCode::Home), Event::InputHome);
+    case(key(KeyCode::Left), Event::InputLeft);
+    case(key(KeyCode::Right), Event::InputRight);
+    case(
+      KeyEvent::new(KeyCode::Enter, KeyModifiers::ALT),
+      Event::InputChar('\n'),
+    );
+
+    assert_eq!(Event::from_key(&key(KeyCode::Left), &Mode::Search), None);
+  }
+
+  #[test]
+  fn fold_keys() {
+    #[track_caller]
+    fn case(event: KeyEvent, expected: Event) {
+      assert_eq!(Event::from_key(&event, &Mode::Fold), Some(expected));
+    }
+
+    case(key(KeyCode::Char('M')), Event::CollapseAll);
+    case(key(KeyCode::Char('R')), Event::ExpandAll);
+    case(key(KeyCode::Char('f')), Event::Focus);
+    case(key(KeyCode::Char('k')), Event::CollapseKind);
+    case(key(KeyCode::Char('s')), Event::CollapseSiblings);
+    case(key(KeyCode::Char('0')), Event::CollapseToDepth(0));
+    case(key(KeyCode::Char('3')), Event::CollapseToDepth(3));
+    case(key(KeyCode::Char('x')), Event::InputCancel);
+    case(key(KeyCode::Esc), Event::InputCancel);
+  }
+
   #[test]
   fn from_crossterm_ignores_resize() {
     assert_eq!(
@@ -186,19 +410,19 @@ mod tests {
       assert_eq!(Event::from_key(&event, &mode), Some(expected));
     }
 
-    for mode in [Mode::Search, Mode::Query] {
+    for mode in [Mode::Edit, Mode::Search, Mode::Query] {
       case(key(KeyCode::Enter), mode, Event::InputConfirm);
     }
 
-    for mode in [Mode::Search, Mode::Query] {
+    for mode in [Mode::Edit, Mode::Search, Mode::Query] {
       case(key(KeyCode::Esc), mode, Event::InputCancel);
     }
 
-    for mode in [Mode::Search, Mode::Query] {
+    for mode in [Mode::Edit, Mode::Search, Mode::Query] {
       case(key(KeyCode::Backspace), mode, Event::InputBackspace);
     }
 
-    for mode in [Mode::Search, Mode::Query] {
+    for mode in [Mode::Edit, Mode::Search, Mode::Query] {
       case(key(KeyCode::Char('a')), mode, Event::InputChar('a'));
     }
   }
@@ -215,7 +439,7 @@ mod tests {
   fn mouse(kind: MouseEventKind, row: u16) -> crossterm::event::MouseEvent {
     crossterm::event::MouseEvent {
       kind,
-      column: 0,
+      column: 3,
       row,
       modifiers: KeyModifiers::NONE,
     }
@@ -225,7 +449,7 @@ mod tests {
   fn mouse_click() {
     assert_eq!(
       Event::from_mouse(mouse(MouseEventKind::Down(MouseButton::Left), 5)),
-      Some(Event::Click { row: 5 }),
+      Some(Event::Click { column: 3, row: 5 }),
     );
   }
 
@@ -240,6 +464,34 @@ mod tests {
       Event::from_mouse(mouse(MouseEventKind::ScrollDown, 0)),
       Some(Event::ScrollDown),
     );
+
+    assert_eq!(
+      Event::from_mouse(mouse(MouseEventKind::ScrollLeft, 0)),
+      Some(Event::ScrollLeft),
+    );
+
+    assert_eq!(
+      Event::from_mouse(mouse(MouseEventKind::ScrollRight, 0)),
+      Some(Event::ScrollRight),
+    );
+  }
+
+  #[test]
+  fn mouse_shift_scroll_is_horizontal() {
+    let shifted = |kind| crossterm::event::MouseEvent {
+      modifiers: KeyModifiers::SHIFT,
+      ..mouse(kind, 0)
+    };
+
+    assert_eq!(
+      Event::from_mouse(shifted(MouseEventKind::ScrollUp)),
+      Some(Event::ScrollLeft),
+    );
+
+    assert_eq!(
+      Event::from_mouse(shifted(MouseEventKind::ScrollDown)),
+      Some(Event::ScrollRight),
+    );
   }
 
   #[test]
@@ -264,8 +516,41 @@ mod tests {
       Event::JumpToMatch { forward: false },
     );
 
+    case(
+      key(KeyCode::Char('J')),
+      Event::ExtendSelection { forward: true },
+    );
+
+    case(
+      key(KeyCode::Char('K')),
+      Event::ExtendSelection { forward: false },
+    );
+
+    case(
+      KeyEvent::new(KeyCode::Down, KeyModifiers::SHIFT),
+      Event::ExtendSelection { forward: true },
+    );
+
+    case(
+      KeyEvent::new(KeyCode::Up, KeyModifiers::SHIFT),
+      Event::ExtendSelection { forward: false },
+    );
+
+    case(
+      KeyEvent::new(KeyCode::Right, KeyModifiers::SHIFT),
+      Event::ExtendSiblings { forward: true },
+    );
+
+    case(
+      KeyEvent::new(KeyCode::Left, KeyModifiers::SHIFT),
+      Event::ExtendSiblings { forward: false },
+    );
+
     case(ctrl('d'), Event::ScrollDown);
     case(ctrl('u'), Event::ScrollUp);
+    case(ctrl('r'), Event::Redo);
+    case(key(KeyCode::Char('u')), Event::Undo);
+    case(key(KeyCode::Char('i')), Event::EnterEdit);
     case(key(KeyCode::Char(' ')), Event::ToggleSelect);
     case(key(KeyCode::Char('/')), Event::EnterSearch);
     case(key(KeyCode::Char('?')), Event::ToggleHelp);
@@ -276,17 +561,44 @@ mod tests {
     case(key(KeyCode::Char('k')), Event::MoveUp);
     case(key(KeyCode::Char('l')), Event::MoveRight);
     case(key(KeyCode::Char('q')), Event::Quit);
-    case(key(KeyCode::Char('y')), Event::Yank);
+    case(key(KeyCode::Char('y')), Event::EnterYank);
     case(key(KeyCode::Enter), Event::ToggleCollapse);
     case(key(KeyCode::Char(':')), Event::EnterQuery);
     case(key(KeyCode::Esc), Event::ClearSearch);
+    case(key(KeyCode::Char('o')), Event::OpenEditor);
+    case(key(KeyCode::Char('p')), Event::GeneratePattern);
+    case(key(KeyCode::Char('z')), Event::EnterFold);
+    case(key(KeyCode::Char('D')), Event::ToggleLog);
+    case(key(KeyCode::Char('E')), Event::ToggleErrors);
+    case(key(KeyCode::Char('[')), Event::ScrollLog { down: false });
+    case(key(KeyCode::Char(']')), Event::ScrollLog { down: true });
+    case(key(KeyCode::Char('>')), Event::ZoomIn);
+    case(key(KeyCode::Char('H')), Event::ScrollLeft);
+    case(key(KeyCode::Char('L')), Event::ScrollRight);
+    case(key(KeyCode::Left), Event::ScrollLeft);
+    case(key(KeyCode::Right), Event::ScrollRight);
+    case(key(KeyCode::Char('<')), Event::ZoomOut);
   }
 
   #[test]
   fn normal_unbound_key() {
     assert_eq!(
-      Event::from_key(&key(KeyCode::Char('z')), &Mode::Normal),
+      Event::from_key(&key(KeyCode::Char('Z')), &Mode::Normal),
       None,
     );
   }
+
+  #[test]
+  fn yank_keys() {
+    #[track_caller]
+    fn case(event: KeyEvent, expected: Event) {
+      assert_eq!(Event::from_key(&event, &Mode::Yank), Some(expected));
+    }
+
+    case(key(KeyCode::Char('y')), Event::Yank(YankFormat::Text));
+    case(key(KeyCode::Char('s')), Event::Yank(YankFormat::Sexp));
+    case(key(KeyCode::Char('j')), Event::Yank(YankFormat::Json));
+    case(key(KeyCode::Char('x')), Event::InputCancel);
+    case(key(KeyCode::Esc), Event::InputCancel);
+  }
 }
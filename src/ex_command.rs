@@ -0,0 +1,210 @@
+use super::*;
+
+/// A command typed at the `:` prompt. Query patterns always open with a
+/// delimiter like `(`, `[` or `"`, so input that starts with a letter is a
+/// command and never run as a query.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ExCommand {
+  Quit { force: bool },
+  Reload { force: bool },
+  Set(Setting),
+  Write,
+}
+
+/// A query option changed with `:set NAME VALUE`, named after the flag that
+/// sets it on the command line. A value of `off`, or none, turns it off.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Setting {
+  MatchLimit(Option<u32>),
+  MaxStartDepth(Option<u32>),
+  QueryRange(Option<QueryRange>),
+  QueryTimeout(Option<Duration>),
+}
+
+impl Display for Setting {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    fn value(
+      f: &mut Formatter<'_>,
+      name: &str,
+      value: Option<impl Display>,
+    ) -> fmt::Result {
+      match value {
+        Some(value) => write!(f, "{name} {value}"),
+        None => write!(f, "{name} off"),
+      }
+    }
+
+    match self {
+      Self::MatchLimit(limit) => value(f, "match-limit", *limit),
+      Self::MaxStartDepth(depth) => value(f, "max-start-depth", *depth),
+      Self::QueryRange(range) => value(
+        f,
+        "query-range",
+        range.as_ref().map(|range| match range {
+          QueryRange::Bytes(range) => {
+            format!("{}..{}", range.start, range.end)
+          }
+          QueryRange::Points(range) => format!(
+            "{}:{}..{}:{}",
+            range.start.row,
+            range.start.column,
+            range.end.row,
+            range.end.column
+          ),
+        }),
+      ),
+      Self::QueryTimeout(timeout) => value(
+        f,
+        "query-timeout",
+        timeout.map(|timeout| timeout.as_millis()),
+      ),
+    }
+  }
+}
+
+impl Setting {
+  pub(crate) fn apply(self, options: &mut QueryOptions) {
+    match self {
+      Self::MatchLimit(limit) => options.match_limit = limit,
+      Self::MaxStartDepth(depth) => options.max_start_depth = depth,
+      Self::QueryRange(range) => options.range = range,
+      Self::QueryTimeout(timeout) => options.timeout = timeout,
+    }
+  }
+
+  fn parse(name: &str, value: Option<&str>) -> Result<Self> {
+    fn number<T: FromStr>(
+      name: &str,
+      value: Option<&str>,
+    ) -> Result<Option<T>> {
+      value
+        .map(|value| {
+          value
+            .parse()
+            .map_err(|_| anyhow!("invalid {name} `{value}`"))
+        })
+        .transpose()
+    }
+
+    let value = value.filter(|&value| value != "off");
+
+    match name {
+      "match-limit" => Ok(Self::MatchLimit(number(name, value)?)),
+      "max-start-depth" => Ok(Self::MaxStartDepth(number(name, value)?)),
+      "query-range" => Ok(Self::QueryRange(value.map(str::parse).transpose()?)),
+      "query-timeout" => Ok(Self::QueryTimeout(
+        number(name, value)?.map(Duration::from_millis),
+      )),
+      _ => Err(anyhow!("unknown option `{name}`")),
+    }
+  }
+}
+
+impl FromStr for ExCommand {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s.trim() {
+      "e" | "edit" => Ok(Self::Reload { force: false }),
+      "e!" | "edit!" => Ok(Self::Reload { force: true }),
+      "q" | "quit" => Ok(Self::Quit { force: false }),
+      "q!" | "quit!" => Ok(Self::Quit { force: true }),
+      "w" | "write" => Ok(Self::Write),
+      command => {
+        let mut words = command.split_whitespace();
+
+        match (words.next(), words.next(), words.next(), words.next()) {
+          (Some("set"), Some(name), value, None) => {
+            Ok(Self::Set(Setting::parse(name, value)?))
+          }
+          _ => Err(anyhow!("unknown command `{command}`")),
+        }
+      }
+    }
+  }
+}
+
+impl ExCommand {
+  pub(crate) fn is_command(input: &str) -> bool {
+    input
+      .trim_start()
+      .starts_with(|c: char| c.is_ascii_alphabetic())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn from_str() {
+    assert_eq!("w".parse::<ExCommand>().unwrap(), ExCommand::Write);
+    assert_eq!(
+      " q! ".parse::<ExCommand>().unwrap(),
+      ExCommand::Quit { force: true }
+    );
+    assert_eq!(
+      "edit".parse::<ExCommand>().unwrap(),
+      ExCommand::Reload { force: false }
+    );
+    assert!("wq".parse::<ExCommand>().is_err());
+  }
+
+  #[test]
+  fn is_command() {
+    assert!(ExCommand::is_command("w"));
+    assert!(ExCommand::is_command(" quit"));
+    assert!(!ExCommand::is_command("(identifier)"));
+    assert!(!ExCommand::is_command("[\"fn\" \"let\"]"));
+    assert!(!ExCommand::is_command(""));
+  }
+
+  #[test]
+  fn set() {
+    #[track_caller]
+    fn case(command: &str, expected: Setting) {
+      assert_eq!(
+        command.parse::<ExCommand>().unwrap(),
+        ExCommand::Set(expected)
+      );
+    }
+
+    case("set match-limit 100", Setting::MatchLimit(Some(100)));
+    case("set match-limit", Setting::MatchLimit(None));
+    case("set match-limit off", Setting::MatchLimit(None));
+    case("set max-start-depth 2", Setting::MaxStartDepth(Some(2)));
+    case(
+      "set query-range 1:0..2:0",
+      Setting::QueryRange(Some(QueryRange::Points(
+        Point { row: 1, column: 0 }..Point { row: 2, column: 0 },
+      ))),
+    );
+    case(
+      "set query-timeout 50",
+      Setting::QueryTimeout(Some(Duration::from_millis(50))),
+    );
+
+    for invalid in [
+      "set",
+      "set depth 2",
+      "set match-limit lots",
+      "set query-range 10..3",
+      "set match-limit 1 2",
+    ] {
+      assert!(invalid.parse::<ExCommand>().is_err(), "{invalid}");
+    }
+  }
+
+  #[test]
+  fn setting_display() {
+    assert_eq!(
+      Setting::QueryRange(Some(QueryRange::Bytes(3..10))).to_string(),
+      "query-range 3..10"
+    );
+    assert_eq!(
+      Setting::QueryTimeout(Some(Duration::from_millis(50))).to_string(),
+      "query-timeout 50"
+    );
+    assert_eq!(Setting::MatchLimit(None).to_string(), "match-limit off");
+  }
+}
@@ -0,0 +1,224 @@
+use super::*;
+
+/// A grammar to parse with, either one of the built-in languages or a
+/// tree-sitter grammar compiled to a shared library.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Grammar {
+  Builtin(Language),
+  Library(PathBuf),
+}
+
+impl Display for Grammar {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Builtin(language) => write!(f, "{language}"),
+      Self::Library(path) => write!(f, "{}", path.display()),
+    }
+  }
+}
+
+impl FromStr for Grammar {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    if let Ok(language) = s.parse::<Language>() {
+      return Ok(Self::Builtin(language));
+    }
+
+    let path = Path::new(s);
+
+    let library = path
+      .extension()
+      .and_then(|extension| extension.to_str())
+      .is_some_and(|extension| {
+        Self::LIBRARY_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+      });
+
+    if library || path.is_file() {
+      Ok(Self::Library(path.into()))
+    } else {
+      Err(anyhow!("unknown language or grammar library `{s}`"))
+    }
+  }
+}
+
+impl Grammar {
+  const LIBRARY_EXTENSIONS: [&'static str; 3] = ["dll", "dylib", "so"];
+
+  /// Regenerates the parser in the grammar source `directory` and compiles it
+  /// to `library` with the tree-sitter CLI. Builds run one at a time, so a
+  /// save made during a build starts the next one once it's done.
+  pub(crate) fn build(directory: &Path, library: &Path) -> Result {
+    static BUILDING: Mutex<()> = Mutex::new(());
+
+    let _guard = BUILDING.lock();
+
+    let library = path::absolute(library).with_context(|| {
+      format!("invalid grammar library `{}`", library.display())
+    })?;
+
+    for arguments in [
+      vec!["generate".as_ref()],
+      vec!["build".as_ref(), "--output".as_ref(), library.as_os_str()],
+    ] {
+      let output = Command::new("tree-sitter")
+        .args(&arguments)
+        .current_dir(directory)
+        .output()
+        .context(
+          "failed to run `tree-sitter`, is the tree-sitter CLI installed?",
+        )?;
+
+      if !output.status.success() {
+        return Err(anyhow!(
+          "`tree-sitter {}` failed in `{}`: {}",
+          arguments[0].to_string_lossy(),
+          directory.display(),
+          String::from_utf8_lossy(&output.stderr).trim()
+        ));
+      }
+    }
+
+    Ok(())
+  }
+
+  pub(crate) fn load(&self) -> Result<TreeSitterLanguage> {
+    match self {
+      Self::Builtin(language) => Ok((*language).into()),
+      Self::Library(path) => Self::load_library(path, path),
+    }
+  }
+
+  /// Loads the library at `file`, naming it `path` in errors and when looking
+  /// up the language function.
+  fn load_library(path: &Path, file: &Path) -> Result<TreeSitterLanguage> {
+    let symbol = Self::symbol(path)?;
+
+    let library =
+      unsafe { libloading::Library::new(file) }.with_context(|| {
+        format!("failed to load grammar library `{}`", path.display())
+      })?;
+
+    let language = unsafe {
+      let function = library
+        .get::<unsafe extern "C" fn() -> *const ()>(symbol.as_bytes())
+        .with_context(|| {
+          format!("`{}` does not export `{symbol}`", path.display())
+        })?;
+
+      LanguageFn::from_raw(*function)
+    };
+
+    // Trees keep pointers into the library, so it must never be unloaded.
+    mem::forget(library);
+
+    Ok(language.into())
+  }
+
+  /// Loads a grammar again, picking up a rebuilt library. Libraries are never
+  /// unloaded and loading the same path twice returns the library already
+  /// loaded, so this loads a copy of the library under a new name instead.
+  pub(crate) fn reload(&self) -> Result<TreeSitterLanguage> {
+    static RELOADS: AtomicUsize = AtomicUsize::new(0);
+
+    let Self::Library(path) = self else {
+      return self.load();
+    };
+
+    let file_name = path
+      .file_name()
+      .ok_or_else(|| anyhow!("invalid grammar library `{}`", path.display()))?;
+
+    let copy = env::temp_dir().join(format!(
+      "axil-{}-{}-{}",
+      process::id(),
+      RELOADS.fetch_add(1, Ordering::Relaxed),
+      file_name.to_string_lossy(),
+    ));
+
+    fs::copy(path, &copy).with_context(|| {
+      format!("failed to copy grammar library `{}`", path.display())
+    })?;
+
+    let language = Self::load_library(path, &copy);
+
+    fs::remove_file(&copy).ok();
+
+    language
+  }
+
+  /// Returns the name of the function a grammar library exports, following
+  /// the `tree_sitter_<name>` convention, derived from a file name like
+  /// `libtree-sitter-rust.so` or `rust.so`.
+  fn symbol(path: &Path) -> Result<String> {
+    let stem = path
+      .file_stem()
+      .and_then(|stem| stem.to_str())
+      .ok_or_else(|| anyhow!("invalid grammar library `{}`", path.display()))?;
+
+    let name = stem.strip_prefix("lib").unwrap_or(stem).replace('-', "_");
+
+    let name = name.strip_prefix("tree_sitter_").unwrap_or(&name);
+
+    Ok(format!("tree_sitter_{name}"))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn from_str() {
+    assert_eq!(
+      "rust".parse::<Grammar>().unwrap(),
+      Grammar::Builtin(Language::Rust)
+    );
+
+    assert_eq!(
+      "fork/rust.so".parse::<Grammar>().unwrap(),
+      Grammar::Library("fork/rust.so".into())
+    );
+
+    assert!("foo".parse::<Grammar>().is_err());
+  }
+
+  #[test]
+  fn load_missing_library() {
+    assert!(Grammar::Library("missing.so".into())
+      .load()
+      .unwrap_err()
+      .to_string()
+      .starts_with("failed to load grammar library `missing.so`"));
+  }
+
+  #[test]
+  fn reload_builtin() {
+    assert_eq!(
+      Grammar::Builtin(Language::Rust).reload().unwrap(),
+      Language::Rust.into()
+    );
+  }
+
+  #[test]
+  fn reload_missing_library() {
+    assert!(Grammar::Library("missing.so".into())
+      .reload()
+      .unwrap_err()
+      .to_string()
+      .starts_with("failed to copy grammar library `missing.so`"));
+  }
+
+  #[test]
+  fn symbol() {
+    #[track_caller]
+    fn case(path: &str, expected: &str) {
+      assert_eq!(Grammar::symbol(Path::new(path)).unwrap(), expected);
+    }
+
+    case("rust.so", "tree_sitter_rust");
+    case("libtree-sitter-rust.so", "tree_sitter_rust");
+    case("build/tree_sitter_rust.dylib", "tree_sitter_rust");
+    case("c-sharp.dll", "tree_sitter_c_sharp");
+  }
+}
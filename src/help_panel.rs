@@ -12,16 +12,49 @@ impl HelpPanel {
     ("G", "Move to bottom"),
     ("Ctrl-u", "Scroll up"),
     ("Ctrl-d", "Scroll down"),
+    ("H / Left", "Scroll left"),
+    ("L / Right", "Scroll right"),
     ("Enter", "Toggle collapse"),
+    ("zM", "Collapse all"),
+    ("zR", "Expand all"),
+    ("zs", "Collapse siblings"),
+    ("zk", "Collapse nodes of this kind"),
+    ("z0-9", "Collapse to depth"),
+    ("zf", "Focus on cursor"),
+    (">", "Zoom into node"),
+    ("<", "Zoom out one level"),
     ("Space", "Toggle select"),
+    ("J / K", "Extend selection down / up"),
+    ("S-Left / S-Right", "Extend selection over siblings"),
     ("/", "Search"),
     ("n", "Next match"),
     ("N", "Previous match"),
     (":", "Tree-sitter query"),
-    ("y", "Yank node text"),
+    ("p", "Query pattern from cursor"),
+    ("Q", "Change query scope"),
+    (":set NAME VALUE", "Change a query limit"),
+    ("P", "Toggle query profile"),
+    ("S", "Change query profile sort"),
+    ("D", "Toggle parse log"),
+    ("[ / ]", "Scroll parse log up / down"),
+    ("E", "Toggle error history"),
+    ("yy", "Yank node or selection text"),
+    ("ys", "Yank S-expression"),
+    ("yp", "Yank position as path:row:col"),
+    ("yb", "Yank byte range"),
+    ("yk", "Yank kind"),
+    ("ya", "Yank ancestor path as query"),
+    ("yq", "Yank generated query pattern"),
+    ("yj", "Yank subtree as JSON"),
+    ("o", "Open in $EDITOR"),
+    ("i", "Edit node text"),
+    ("u", "Undo edit"),
+    ("Ctrl-r", "Redo edit"),
+    (":w", "Write edits to file"),
+    (":e / :e!", "Reload file / discard edits"),
     ("Esc", "Clear search"),
     ("?", "Toggle help"),
-    ("q", "Quit"),
+    ("q / :q!", "Quit / discard edits and quit"),
   ];
 }
 
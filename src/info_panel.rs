@@ -1,44 +1,42 @@
 use super::*;
 
 pub(crate) struct InfoPanel<'a> {
-  code: &'a str,
-  node: Node<'a>,
+  nodes: Vec<Node<'a>>,
+  source: &'a Source,
 }
 
 impl<'a> InfoPanel<'a> {
-  pub(crate) fn new(node: Node<'a>, code: &'a str) -> Self {
-    Self { code, node }
-  }
-}
-
-impl Widget for InfoPanel<'_> {
-  fn render(self, area: Rect, buf: &mut Buffer) {
-    let node_text = &self.code[self.node.start_byte()..self.node.end_byte()];
+  fn details(&self, node: &Node) -> Vec<Line<'a>> {
+    let node_text = self.source.text(node.byte_range());
 
-    let node_kind = self.node.kind();
+    let node_kind = node.kind();
 
-    let display_text = if node_text.len() > 100 {
-      format!("{}... ({})", &node_text[..100], node_text.len())
+    let display_text = if node_text.chars().count() > 100 {
+      format!(
+        "{}... ({})",
+        node_text.chars().take(100).collect::<String>(),
+        node.byte_range().len()
+      )
     } else {
-      node_text.to_string()
+      node_text.into_owned()
     };
 
     let style = Style::default().fg(Color::Green);
 
     let mut lines = vec![
       Line::from(vec![Span::styled(
-        node_kind,
+        node_kind.to_string(),
         Style::default()
-          .fg(self.node.color())
+          .fg(node.color())
           .add_modifier(Modifier::BOLD),
       )]),
       Line::from(vec![Span::styled(
         format!(
           "[{}:{} - {}:{}]",
-          self.node.start_position().row,
-          self.node.start_position().column,
-          self.node.end_position().row,
-          self.node.end_position().column
+          node.start_position().row,
+          node.start_position().column,
+          node.end_position().row,
+          node.end_position().column
         ),
         Style::default().fg(Color::Yellow),
       )]),
@@ -50,9 +48,57 @@ impl Widget for InfoPanel<'_> {
         .map(|line| Line::from(Span::styled(line.to_string(), style))),
     );
 
-    let node_info = Text::from(lines);
+    lines
+  }
+
+  fn list(&self) -> Vec<Line<'a>> {
+    let mut lines = vec![Line::from(Span::styled(
+      format!("{} nodes selected", self.nodes.len()),
+      Style::default().add_modifier(Modifier::BOLD),
+    ))];
+
+    for node in &self.nodes {
+      let text = self.source.text(node.byte_range());
+
+      let text = text.lines().next().unwrap_or_default();
+
+      lines.push(Line::from(vec![
+        Span::styled(
+          node.kind().to_string(),
+          Style::default()
+            .fg(node.color())
+            .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+          format!(
+            " [{}:{} - {}:{}] ",
+            node.start_position().row,
+            node.start_position().column,
+            node.end_position().row,
+            node.end_position().column
+          ),
+          Style::default().fg(Color::Yellow),
+        ),
+        Span::styled(text.to_string(), Style::default().fg(Color::Green)),
+      ]));
+    }
+
+    lines
+  }
+
+  pub(crate) fn new(nodes: Vec<Node<'a>>, source: &'a Source) -> Self {
+    Self { nodes, source }
+  }
+}
+
+impl Widget for InfoPanel<'_> {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let lines = match self.nodes.as_slice() {
+      [node] => self.details(node),
+      _ => self.list(),
+    };
 
-    Paragraph::new(node_info)
+    Paragraph::new(Text::from(lines))
       .block(
         Block::default()
           .borders(Borders::ALL)
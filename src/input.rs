@@ -0,0 +1,55 @@
+use super::*;
+
+pub(crate) struct Input {
+  lock: Arc<Mutex<()>>,
+  paused: Arc<AtomicBool>,
+}
+
+impl Input {
+  pub(crate) fn new(tx: Sender<ChannelEvent>) -> Self {
+    let lock = Arc::new(Mutex::new(()));
+    let paused = Arc::new(AtomicBool::new(false));
+
+    let (thread_lock, thread_paused) = (lock.clone(), paused.clone());
+
+    thread::spawn(move || loop {
+      if thread_paused.load(Ordering::SeqCst) {
+        thread::sleep(Duration::from_millis(10));
+        continue;
+      }
+
+      let Ok(_guard) = thread_lock.lock() else {
+        break;
+      };
+
+      if thread_paused.load(Ordering::SeqCst) {
+        continue;
+      }
+
+      if crossterm::event::poll(Duration::from_millis(100)).unwrap_or(false) {
+        if let Ok(event) = crossterm::event::read() {
+          if tx.send(ChannelEvent::Crossterm(event)).is_err() {
+            break;
+          }
+        }
+      }
+    });
+
+    Self { lock, paused }
+  }
+
+  /// Stops reading terminal events while `f` runs, so that a child process
+  /// gets the keyboard to itself.
+  pub(crate) fn pause<T>(&self, f: impl FnOnce() -> T) -> T {
+    self.paused.store(true, Ordering::SeqCst);
+
+    let value = {
+      let _guard = self.lock.lock();
+      f()
+    };
+
+    self.paused.store(false, Ordering::SeqCst);
+
+    value
+  }
+}
@@ -0,0 +1,51 @@
+use super::*;
+
+pub(crate) struct LogPanel<'a> {
+  lines: &'a [String],
+  scroll: usize,
+}
+
+impl Widget for LogPanel<'_> {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let lines = self
+      .lines
+      .iter()
+      .skip(self.scroll)
+      .take(usize::from(area.height))
+      .map(|line| {
+        if line.starts_with(' ') {
+          Line::from(Span::styled(
+            line.as_str(),
+            Style::default().fg(Color::DarkGray),
+          ))
+        } else {
+          Line::from(line.as_str())
+        }
+      })
+      .collect::<Vec<_>>();
+
+    Paragraph::new(lines)
+      .block(
+        Block::default()
+          .borders(Borders::ALL)
+          .title(format!(
+            " Parse log {}/{} ([ and ] to scroll, L to close) ",
+            (self.scroll + 1).min(self.lines.len()),
+            self.lines.len()
+          ))
+          .title_style(
+            Style::default()
+              .fg(Color::Magenta)
+              .add_modifier(Modifier::BOLD),
+          )
+          .border_style(Style::default().fg(Color::DarkGray)),
+      )
+      .render(area, buf);
+  }
+}
+
+impl<'a> LogPanel<'a> {
+  pub(crate) fn new(lines: &'a [String], scroll: usize) -> Self {
+    Self { lines, scroll }
+  }
+}
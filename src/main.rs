@@ -1,68 +1,162 @@
 use {
-  anyhow::{anyhow, Error},
+  anyhow::{anyhow, Context, Error},
   app::App,
   arguments::Arguments,
+  assertion::Assertion,
+  base64::{engine::general_purpose::STANDARD as BASE64, Engine},
+  bench::Bench,
+  breadcrumb::Breadcrumb,
   channel_event::ChannelEvent,
   clap::Parser as Clap,
+  compare::Compare,
+  corpus::Example,
+  corpus_test::CorpusTest,
   crossterm::{
     event::{
       DisableMouseCapture, EnableMouseCapture, KeyCode, KeyEvent, KeyModifiers,
       MouseButton, MouseEventKind,
     },
     execute,
+    style::ContentStyle,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen},
   },
+  diff::Diff,
+  diff_view::{DiffView, Pane},
+  dot_graphs::DotGraphs,
+  edit::{Draft, Edit, History},
+  editor::Editor,
+  encoding::Encoding,
+  error_panel::ErrorPanel,
   event::Event,
+  ex_command::ExCommand,
+  grammar::Grammar,
   help_panel::HelpPanel,
   info_panel::InfoPanel,
+  input::Input,
   language::Language,
+  log_panel::LogPanel,
   mode::Mode,
   node_ext::NodeExt,
+  node_index::NodeIndex,
+  node_key::NodeKey,
+  node_set::NodeSet,
+  parse_log::ParseLog,
+  pattern::Pattern,
   printer::Printer,
+  profile_panel::ProfilePanel,
+  query_options::{QueryOptions, QueryOutcome},
+  query_profile::{ProfileSort, QueryProfile},
+  query_range::QueryRange,
+  query_scope::QueryScope,
+  query_test::QueryTest,
   ratatui::{
     prelude::*,
     style::{Modifier, Style},
     widgets::{Block, Borders, Paragraph},
   },
+  rows::Rows,
+  serde::{Deserialize, Serialize},
+  session::{Session, SessionStore},
+  sexp::Sexp,
+  source::Source,
   state::State,
+  stats::Stats,
   status_line::StatusLine,
   std::{
-    collections::HashSet,
-    fmt::{self, Display, Formatter},
-    fs,
-    io::{self, Read, Stdout},
-    ops::ControlFlow,
-    path::{Path, PathBuf},
-    process,
-    str::FromStr,
-    sync::mpsc::{channel, RecvTimeoutError, Sender},
+    borrow::Cow,
+    cell::{OnceCell, Ref, RefCell},
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    env,
+    fmt::{self, Display, Formatter, Write as _},
+    fs::{self, File},
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{self, IsTerminal, Read, Stdout, Write as _},
+    iter, mem,
+    ops::{ControlFlow, Deref, Range},
+    path::{self, Path, PathBuf},
+    process::{self, Command, Stdio},
+    rc::Rc,
+    str::{self, FromStr},
+    sync::{
+      atomic::{AtomicBool, AtomicUsize, Ordering},
+      mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+      Arc, Mutex,
+    },
     thread,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
   },
+  subcommand::Subcommand,
+  tempfile::NamedTempFile,
   terminal::Terminal,
+  tree_diff::{Change, TreeDiff},
   tree_panel::TreePanel,
   tree_sitter::{
-    Language as TreeSitterLanguage, Node, Parser, Query, QueryCursor,
-    StreamingIterator, Tree,
+    Decode, InputEdit, Language as TreeSitterLanguage, LogType, Node, Parser,
+    Point, Query, QueryCursor, QueryCursorOptions, QueryCursorState,
+    QueryMatch, StreamingIterator, TextProvider, Tree,
   },
-  watcher::Watcher,
+  tree_sitter_language::LanguageFn,
+  watcher::{WatchPaths, Watcher},
+  yank_format::YankFormat,
 };
 
+#[cfg(test)]
+use test_utils::{deeply_nested, parse, parse_with};
+
 mod app;
 mod arguments;
+mod assertion;
+mod bench;
+mod breadcrumb;
 mod channel_event;
+mod compare;
+mod corpus;
+mod corpus_test;
+mod diff;
+mod diff_view;
+mod dot_graphs;
+mod edit;
+mod editor;
+mod encoding;
+mod error_panel;
 mod event;
+mod ex_command;
+mod grammar;
 mod help_panel;
 mod info_panel;
+mod input;
 mod language;
+mod log_panel;
 mod mode;
 mod node_ext;
+mod node_index;
+mod node_key;
+mod node_set;
+mod parse_log;
+mod pattern;
 mod printer;
+mod profile_panel;
+mod query_options;
+mod query_profile;
+mod query_range;
+mod query_scope;
+mod query_test;
+mod rows;
+mod session;
+mod sexp;
+mod source;
 mod state;
+mod stats;
 mod status_line;
+mod subcommand;
 mod terminal;
+#[cfg(test)]
+mod test_utils;
+mod tree_diff;
 mod tree_panel;
 mod watcher;
+mod yank_format;
 
 unsafe extern "C" {
   pub(crate) fn tree_sitter_just() -> TreeSitterLanguage;
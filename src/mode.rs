@@ -1,7 +1,10 @@
 #[derive(Debug, Default, PartialEq)]
 pub(crate) enum Mode {
+  Edit,
+  Fold,
   #[default]
   Normal,
   Query,
   Search,
+  Yank,
 }
@@ -1,12 +1,15 @@
 use super::*;
 
-pub(crate) trait NodeExt {
+pub(crate) trait NodeExt<'tree> {
   fn child_count_u32(&self) -> u32;
   fn color(&self) -> Color;
   fn find_at_byte(&self, byte: usize) -> Option<usize>;
+  fn path_to(&self, descendant: Node<'tree>) -> Option<Vec<Node<'tree>>>;
+  fn preorder(&self, visit: impl FnMut(Node<'tree>, usize) -> bool);
+  fn sexp(&self, source: &Source) -> String;
 }
 
-impl NodeExt for Node<'_> {
+impl<'tree> NodeExt<'tree> for Node<'tree> {
   #[allow(clippy::cast_possible_truncation)]
   fn child_count_u32(&self) -> u32 {
     self.child_count() as u32
@@ -26,11 +29,258 @@ impl NodeExt for Node<'_> {
   }
 
   fn find_at_byte(&self, byte: usize) -> Option<usize> {
-    (byte >= self.start_byte() && byte < self.end_byte()).then(|| {
-      (0..self.child_count_u32())
-        .filter_map(|i| self.child(i))
-        .find_map(|child| child.find_at_byte(byte))
-        .unwrap_or_else(|| self.id())
-    })
+    (byte >= self.start_byte() && byte < self.end_byte())
+      .then(|| self.descendant_for_byte_range(byte, byte + 1))
+      .flatten()
+      .map(|node| node.id())
+  }
+
+  /// Returns the chain of nodes from `self` down to `descendant` inclusive,
+  /// or `None` if `descendant` is not part of this subtree.
+  fn path_to(&self, descendant: Node<'tree>) -> Option<Vec<Node<'tree>>> {
+    let mut path = vec![*self];
+
+    let mut node = *self;
+
+    while node.id() != descendant.id() {
+      node = node.child_with_descendant(descendant)?;
+      path.push(node);
+    }
+
+    Some(path)
+  }
+
+  /// Visits every node of the subtree in pre-order along with its depth
+  /// relative to `self`, skipping the children of nodes for which `visit`
+  /// returns `false`.
+  fn preorder(&self, mut visit: impl FnMut(Node<'tree>, usize) -> bool) {
+    let mut cursor = self.walk();
+    let mut depth = 0;
+
+    loop {
+      if visit(cursor.node(), depth) && cursor.goto_first_child() {
+        depth += 1;
+        continue;
+      }
+
+      loop {
+        if depth == 0 {
+          return;
+        }
+
+        if cursor.goto_next_sibling() {
+          break;
+        }
+
+        cursor.goto_parent();
+        depth -= 1;
+      }
+    }
+  }
+
+  /// Renders the subtree as an S-expression exactly like `Node::to_sexp`,
+  /// which recurses in C and overflows the stack on deeply nested input.
+  ///
+  /// Error leaves show the character they start with, which is taken from
+  /// `source` since nodes don't keep it.
+  fn sexp(&self, source: &Source) -> String {
+    let mut sexp = String::new();
+
+    let mut open = Vec::new();
+
+    let mut cursor = self.walk();
+    let mut depth = 0;
+
+    loop {
+      let node = cursor.node();
+
+      while open.last().is_some_and(|&open| open >= depth) {
+        open.pop();
+        sexp.push(')');
+      }
+
+      if node.is_named() || node.is_missing() {
+        if depth > 0 {
+          sexp.push(' ');
+
+          if let Some(field) = cursor.field_name() {
+            sexp.push_str(field);
+            sexp.push_str(": ");
+          }
+        }
+
+        if node.is_error()
+          && node.child_count() == 0
+          && !node.byte_range().is_empty()
+        {
+          let c = match source.text(node.byte_range()).chars().next() {
+            Some('\0') => "'\\0'".into(),
+            Some('\n') => "'\\n'".into(),
+            Some('\t') => "'\\t'".into(),
+            Some('\r') => "'\\r'".into(),
+            Some(c) if c == ' ' || c.is_ascii_graphic() => format!("'{c}'"),
+            Some(c) => u32::from(c).to_string(),
+            None => "INVALID".into(),
+          };
+
+          sexp.push_str("(UNEXPECTED ");
+          sexp.push_str(&c);
+        } else if node.is_missing() && node.is_named() {
+          sexp.push_str("(MISSING ");
+          sexp.push_str(node.kind());
+        } else if node.is_missing() {
+          sexp.push_str("(MISSING \"");
+          sexp.push_str(node.kind());
+          sexp.push('"');
+        } else {
+          sexp.push('(');
+          sexp.push_str(node.kind());
+        }
+
+        open.push(depth);
+      } else if depth == 0 {
+        sexp.push_str("(\"");
+        sexp.push_str(node.kind());
+        sexp.push_str("\")");
+      }
+
+      if cursor.goto_first_child() {
+        depth += 1;
+        continue;
+      }
+
+      loop {
+        if depth == 0 {
+          for _ in open.drain(..) {
+            sexp.push(')');
+          }
+
+          return sexp;
+        }
+
+        if cursor.goto_next_sibling() {
+          break;
+        }
+
+        cursor.goto_parent();
+        depth -= 1;
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn find_at_byte() {
+    let tree = parse("fn foo() {}");
+    let root = tree.root_node();
+
+    let function = root.child(0).unwrap();
+
+    assert_eq!(root.find_at_byte(0), Some(function.child(0).unwrap().id()));
+    assert_eq!(root.find_at_byte(4), Some(function.child(1).unwrap().id()));
+    assert_eq!(root.find_at_byte(2), Some(function.id()));
+    assert_eq!(root.find_at_byte(11), None);
+  }
+
+  #[test]
+  fn path_to() {
+    let tree = parse("fn foo() {} fn bar() {}");
+    let root = tree.root_node();
+
+    let first = root.child(0).unwrap();
+    let second = root.child(1).unwrap();
+    let identifier = second.child(1).unwrap();
+
+    assert_eq!(
+      root.path_to(identifier),
+      Some(vec![root, second, identifier])
+    );
+
+    assert_eq!(root.path_to(root), Some(vec![root]));
+    assert_eq!(first.path_to(identifier), None);
+  }
+
+  #[test]
+  fn preorder() {
+    let tree = parse("fn foo() {}");
+
+    let mut kinds = Vec::new();
+
+    tree.root_node().preorder(|node, depth| {
+      kinds.push((node.kind(), depth));
+      true
+    });
+
+    assert_eq!(
+      kinds,
+      vec![
+        ("source_file", 0),
+        ("function_item", 1),
+        ("fn", 2),
+        ("identifier", 2),
+        ("parameters", 2),
+        ("(", 3),
+        (")", 3),
+        ("block", 2),
+        ("{", 3),
+        ("}", 3),
+      ],
+    );
+  }
+
+  #[test]
+  fn preorder_skips_children() {
+    let tree = parse("fn foo() {} fn bar() {}");
+
+    let mut kinds = Vec::new();
+
+    tree.root_node().preorder(|node, _| {
+      kinds.push(node.kind());
+      node.kind() != "function_item"
+    });
+
+    assert_eq!(kinds, vec!["source_file", "function_item", "function_item"]);
+  }
+
+  #[test]
+  fn preorder_subtree() {
+    let tree = parse("fn foo() {} fn bar() {}");
+
+    let mut count = 0;
+
+    tree.root_node().child(0).unwrap().preorder(|_, _| {
+      count += 1;
+      true
+    });
+
+    assert_eq!(count, 9);
+  }
+
+  #[test]
+  fn sexp() {
+    for code in [
+      "fn foo(a: u8) -> u8 { a } // done",
+      "fn foo( {",
+      "fn foo() { let x = ; }",
+      "fn foo() { a $ b; }",
+      "fn foo() { let x = 1 }",
+      "fn foo() { ` }",
+      "fn foo() { \t\u{e9} }",
+      "\u{e9}",
+      "#",
+      "struct",
+      "",
+    ] {
+      let tree = parse(code);
+
+      tree.root_node().preorder(|node, _| {
+        assert_eq!(node.sexp(&code.into()), node.to_sexp(), "{code}");
+        true
+      });
+    }
   }
 }
@@ -0,0 +1,71 @@
+use super::*;
+
+/// Maps node ids to their pre-order descendant indexes, so finding a node by
+/// id doesn't walk the whole tree.
+#[derive(Debug)]
+pub(crate) struct NodeIndex {
+  descendants: HashMap<usize, usize>,
+}
+
+impl NodeIndex {
+  pub(crate) fn new(tree: &Tree) -> Self {
+    let mut descendants = HashMap::new();
+
+    tree.root_node().preorder(|node, _| {
+      descendants.insert(node.id(), descendants.len());
+      true
+    });
+
+    Self { descendants }
+  }
+
+  /// Returns the node with `id` in `tree`, or `None` if it isn't there, which
+  /// includes when `tree` isn't the tree this index was built from.
+  pub(crate) fn node<'a>(&self, tree: &'a Tree, id: usize) -> Option<Node<'a>> {
+    let mut cursor = tree.walk();
+
+    cursor.goto_descendant(*self.descendants.get(&id)?);
+
+    Some(cursor.node()).filter(|node| node.id() == id)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn finds_every_node() {
+    let tree = parse("fn foo() { let x = 1; }");
+
+    let index = NodeIndex::new(&tree);
+
+    let mut cursor = tree.walk();
+    let mut count = 0;
+
+    loop {
+      let node = cursor.node();
+
+      assert_eq!(index.node(&tree, node.id()), Some(node));
+
+      count += 1;
+
+      if cursor.goto_first_child() {
+        continue;
+      }
+
+      while !cursor.goto_next_sibling() {
+        if !cursor.goto_parent() {
+          assert_eq!(count, tree.root_node().descendant_count());
+          assert_eq!(index.node(&tree, usize::MAX), None);
+
+          for other in [tree.clone(), parse("fn foo() { let x = 1; }")] {
+            assert_eq!(index.node(&other, other.root_node().id()), None);
+          }
+
+          return;
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,205 @@
+use super::*;
+
+/// Identifies a node across reparses by its child index path from the root,
+/// falling back to its kind and byte range when the path no longer leads to a
+/// node of the same kind.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub(crate) struct NodeKey {
+  pub(crate) end_byte: usize,
+  pub(crate) kind: String,
+  #[serde(default)]
+  pub(crate) path: Option<Vec<u32>>,
+  pub(crate) start_byte: usize,
+}
+
+impl NodeKey {
+  /// Shifts the byte range to account for an edit elsewhere in the source.
+  pub(crate) fn edit(&mut self, edit: &InputEdit) {
+    let shift = |byte: usize| {
+      if byte >= edit.old_end_byte {
+        byte - edit.old_end_byte + edit.new_end_byte
+      } else {
+        byte
+      }
+    };
+
+    self.start_byte = shift(self.start_byte);
+    self.end_byte = shift(self.end_byte);
+  }
+
+  pub(crate) fn new(node: &Node, path: Vec<u32>) -> Self {
+    Self {
+      end_byte: node.end_byte(),
+      kind: node.kind().to_string(),
+      path: Some(path),
+      start_byte: node.start_byte(),
+    }
+  }
+
+  pub(crate) fn resolve<'a>(&self, root: Node<'a>) -> Option<Node<'a>> {
+    self.resolve_path(root).or_else(|| self.resolve_range(root))
+  }
+
+  fn resolve_path<'a>(&self, root: Node<'a>) -> Option<Node<'a>> {
+    let node = self
+      .path
+      .as_ref()?
+      .iter()
+      .try_fold(root, |node, &i| node.child(i))?;
+
+    (node.kind() == self.kind).then_some(node)
+  }
+
+  fn resolve_range<'a>(&self, root: Node<'a>) -> Option<Node<'a>> {
+    let mut node =
+      root.descendant_for_byte_range(self.start_byte, self.end_byte)?;
+
+    loop {
+      if node.start_byte() != self.start_byte
+        || node.end_byte() != self.end_byte
+      {
+        return None;
+      }
+
+      if node.kind() == self.kind {
+        return Some(node);
+      }
+
+      node = node.parent()?;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn edit_shifts_later_ranges() {
+    let code = "fn foo() {} fn bar() {}";
+
+    let tree = parse(code);
+    let root = tree.root_node();
+
+    let mut first = NodeKey::new(&root.child(0).unwrap(), vec![0]);
+    let mut second = NodeKey::new(&root.child(1).unwrap(), vec![1]);
+
+    let edit = InputEdit {
+      start_byte: 3,
+      old_end_byte: 6,
+      new_end_byte: 9,
+      start_position: Point { row: 0, column: 3 },
+      old_end_position: Point { row: 0, column: 6 },
+      new_end_position: Point { row: 0, column: 9 },
+    };
+
+    first.edit(&edit);
+    second.edit(&edit);
+
+    assert_eq!((first.start_byte, first.end_byte), (0, 14));
+    assert_eq!((second.start_byte, second.end_byte), (15, 26));
+
+    let tree = parse("fn foobar() {} fn bar() {}");
+    let root = tree.root_node();
+
+    second.path = None;
+
+    assert_eq!(
+      second.resolve(root).map(|node| node.id()),
+      Some(root.child(1).unwrap().id())
+    );
+  }
+
+  #[test]
+  fn resolve_finds_ancestor_with_same_range() {
+    let tree = parse("fn foo() {}");
+
+    let root = tree.root_node();
+
+    assert_eq!(root.byte_range(), root.child(0).unwrap().byte_range());
+
+    assert_eq!(
+      NodeKey::new(&root, Vec::new())
+        .resolve(root)
+        .map(|node| node.id()),
+      Some(root.id())
+    );
+  }
+
+  #[test]
+  fn resolve_missing_kind() {
+    let tree = parse("fn foo() {}");
+
+    let key = NodeKey {
+      end_byte: 11,
+      kind: "struct_item".into(),
+      path: Some(vec![0]),
+      start_byte: 0,
+    };
+
+    assert!(key.resolve(tree.root_node()).is_none());
+  }
+
+  #[test]
+  fn resolve_path_after_edit() {
+    let tree = parse("fn foo() {} fn bar() {}");
+
+    let key = NodeKey::new(&tree.root_node().child(1).unwrap(), vec![1]);
+
+    let tree = parse("fn foobar() {} fn bar() {}");
+    let root = tree.root_node();
+
+    assert_eq!(
+      key.resolve(root).map(|node| node.id()),
+      Some(root.child(1).unwrap().id())
+    );
+  }
+
+  #[test]
+  fn resolve_prefers_path_over_range() {
+    let tree = parse("fn foo() {} fn bar() {}");
+    let root = tree.root_node();
+
+    let key = NodeKey {
+      path: Some(vec![1]),
+      ..NodeKey::new(&root.child(0).unwrap(), Vec::new())
+    };
+
+    assert_eq!(
+      key.resolve(root).map(|node| node.id()),
+      Some(root.child(1).unwrap().id())
+    );
+  }
+
+  #[test]
+  fn resolve_round_trip() {
+    let tree = parse("fn foo() {} fn bar() {}");
+
+    let root = tree.root_node();
+
+    let second = root.child(1).unwrap();
+
+    assert_eq!(
+      NodeKey::new(&second, vec![1])
+        .resolve(root)
+        .map(|node| node.id()),
+      Some(second.id())
+    );
+  }
+
+  #[test]
+  fn resolve_without_path_uses_range() {
+    let tree = parse("fn foo() {} fn bar() {}");
+    let root = tree.root_node();
+
+    let key = NodeKey {
+      path: None,
+      ..NodeKey::new(&root.child(1).unwrap(), Vec::new())
+    };
+
+    assert_eq!(
+      key.resolve(root).map(|node| node.id()),
+      Some(root.child(1).unwrap().id())
+    );
+  }
+}
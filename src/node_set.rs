@@ -0,0 +1,131 @@
+use super::*;
+
+/// Node ids in the order they were added, without duplicates, so that
+/// checking whether a node is in the set while drawing each row doesn't scan
+/// the whole list.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct NodeSet {
+  ids: Vec<usize>,
+  positions: HashMap<usize, usize>,
+}
+
+impl NodeSet {
+  pub(crate) fn clear(&mut self) {
+    self.ids.clear();
+    self.positions.clear();
+  }
+
+  pub(crate) fn contains(&self, id: usize) -> bool {
+    self.positions.contains_key(&id)
+  }
+
+  /// Adds `id` at the end unless it's already in the set, returning whether
+  /// it was added.
+  pub(crate) fn insert(&mut self, id: usize) -> bool {
+    if self.contains(id) {
+      return false;
+    }
+
+    self.positions.insert(id, self.ids.len());
+    self.ids.push(id);
+
+    true
+  }
+
+  pub(crate) fn position(&self, id: usize) -> Option<usize> {
+    self.positions.get(&id).copied()
+  }
+
+  pub(crate) fn remove(&mut self, id: usize) -> bool {
+    let Some(position) = self.positions.remove(&id) else {
+      return false;
+    };
+
+    self.ids.remove(position);
+
+    for (i, id) in self.ids.iter().enumerate().skip(position) {
+      self.positions.insert(*id, i);
+    }
+
+    true
+  }
+
+  pub(crate) fn retain(&mut self, mut keep: impl FnMut(usize) -> bool) {
+    self.ids.retain(|&id| keep(id));
+
+    self.positions = self
+      .ids
+      .iter()
+      .enumerate()
+      .map(|(i, &id)| (id, i))
+      .collect();
+  }
+}
+
+impl Deref for NodeSet {
+  type Target = [usize];
+
+  fn deref(&self) -> &[usize] {
+    &self.ids
+  }
+}
+
+impl Extend<usize> for NodeSet {
+  fn extend<T: IntoIterator<Item = usize>>(&mut self, ids: T) {
+    for id in ids {
+      self.insert(id);
+    }
+  }
+}
+
+impl From<Vec<usize>> for NodeSet {
+  fn from(ids: Vec<usize>) -> Self {
+    ids.into_iter().collect()
+  }
+}
+
+impl FromIterator<usize> for NodeSet {
+  fn from_iter<T: IntoIterator<Item = usize>>(ids: T) -> Self {
+    let mut set = Self::default();
+    set.extend(ids);
+    set
+  }
+}
+
+impl<T: AsRef<[usize]> + ?Sized> PartialEq<T> for NodeSet {
+  fn eq(&self, other: &T) -> bool {
+    self.ids == other.as_ref()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn insert_keeps_first_position() {
+    let mut set = NodeSet::from(vec![3, 1, 3, 2, 1]);
+
+    assert_eq!(set, [3, 1, 2]);
+    assert!(!set.insert(1));
+    assert!(set.insert(4));
+    assert_eq!(set.position(2), Some(2));
+    assert_eq!(set.position(5), None);
+  }
+
+  #[test]
+  fn remove_and_retain_update_positions() {
+    let mut set = NodeSet::from(vec![1, 2, 3, 4]);
+
+    assert!(set.remove(2));
+    assert!(!set.remove(2));
+    assert!(!set.contains(2));
+    assert_eq!(set.position(3), Some(1));
+
+    set.retain(|id| id != 1);
+
+    assert_eq!(set, [3, 4]);
+    assert_eq!(set.position(4), Some(1));
+    assert_eq!(set.position(1), None);
+  }
+}
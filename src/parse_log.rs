@@ -0,0 +1,150 @@
+use super::*;
+
+/// Where tree-sitter's lexer and parser log goes while parsing: a file, a
+/// buffer shown in the log panel, or both.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ParseLog {
+  lines: Option<Rc<RefCell<Vec<String>>>>,
+  path: Option<PathBuf>,
+}
+
+impl ParseLog {
+  /// Attaches a logger to `parser` if anything wants the log, truncating the
+  /// file and clearing the buffer so they only hold the next parse.
+  pub(crate) fn attach(&self, parser: &mut Parser) -> Result {
+    if self.lines.is_none() && self.path.is_none() {
+      parser.set_logger(None);
+      return Ok(());
+    }
+
+    let mut file = self
+      .path
+      .as_ref()
+      .map(|path| {
+        File::create(path).with_context(|| {
+          format!("failed to create debug log `{}`", path.display())
+        })
+      })
+      .transpose()?;
+
+    let lines = self.lines.clone();
+
+    if let Some(lines) = &lines {
+      lines.borrow_mut().clear();
+    }
+
+    parser.set_logger(Some(Box::new(move |log_type, message| {
+      let line = Self::line(&log_type, message);
+
+      if let Some(file) = &mut file {
+        writeln!(file, "{line}").ok();
+      }
+
+      if let Some(lines) = &lines {
+        lines.borrow_mut().push(line);
+      }
+    })));
+
+    Ok(())
+  }
+
+  pub(crate) fn capture(&mut self, capture: bool) {
+    self.lines = capture.then(Rc::default);
+  }
+
+  pub(crate) fn file(path: PathBuf) -> Self {
+    Self {
+      lines: None,
+      path: Some(path),
+    }
+  }
+
+  pub(crate) fn is_capturing(&self) -> bool {
+    self.lines.is_some()
+  }
+
+  /// Formats a log message the way `tree-sitter parse --debug` does, with
+  /// lexer messages indented under the parser message that asked for them.
+  fn line(log_type: &LogType, message: &str) -> String {
+    match log_type {
+      LogType::Lex => format!("  {message}"),
+      LogType::Parse => message.to_string(),
+    }
+  }
+
+  pub(crate) fn lines(&self) -> Option<Ref<'_, Vec<String>>> {
+    self.lines.as_ref().map(|lines| lines.borrow())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn capture_collects_lines() {
+    let mut log = ParseLog::default();
+
+    log.capture(true);
+
+    parse(&log, "fn main() {}");
+
+    let lines = log.lines().unwrap();
+
+    assert_eq!(lines.first().map(String::as_str), Some("new_parse"));
+    assert!(lines.iter().any(|line| line.starts_with("  ")));
+  }
+
+  #[test]
+  fn capture_only_keeps_latest_parse() {
+    let mut log = ParseLog::default();
+
+    log.capture(true);
+
+    parse(&log, "fn main() {}");
+    parse(&log, "fn main() {}");
+
+    assert_eq!(
+      log
+        .lines()
+        .unwrap()
+        .iter()
+        .filter(|line| line.as_str() == "new_parse")
+        .count(),
+      1,
+    );
+  }
+
+  #[test]
+  fn default_collects_nothing() {
+    let log = ParseLog::default();
+
+    parse(&log, "fn main() {}");
+
+    assert!(!log.is_capturing());
+    assert!(log.lines().is_none());
+  }
+
+  #[test]
+  fn file_writes_log() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let path = tempdir.path().join("parse.log");
+
+    parse(&ParseLog::file(path.clone()), "fn main() {}");
+
+    assert!(fs::read_to_string(path).unwrap().starts_with("new_parse\n"));
+  }
+
+  fn parse(log: &ParseLog, code: &str) {
+    let mut parser = Parser::new();
+
+    parser
+      .set_language(&tree_sitter_rust::LANGUAGE.into())
+      .unwrap();
+
+    log.attach(&mut parser).unwrap();
+
+    parser.parse(code, None).unwrap();
+  }
+}
@@ -0,0 +1,307 @@
+use super::*;
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Pattern {
+  anonymous: bool,
+  depth: usize,
+}
+
+impl Default for Pattern {
+  fn default() -> Self {
+    Self {
+      anonymous: false,
+      depth: Self::DEFAULT_DEPTH,
+    }
+  }
+}
+
+impl Pattern {
+  pub(crate) const DEFAULT_DEPTH: usize = 3;
+
+  pub(crate) fn anonymous(self, anonymous: bool) -> Self {
+    Self { anonymous, ..self }
+  }
+
+  fn children<'a>(
+    &self,
+    node: &Node<'a>,
+  ) -> Vec<(Option<&'static str>, Node<'a>)> {
+    let mut cursor = node.walk();
+
+    let mut children = Vec::new();
+
+    if cursor.goto_first_child() {
+      loop {
+        let child = cursor.node();
+
+        if (self.anonymous || child.is_named()) && !child.is_extra() {
+          children.push((cursor.field_name(), child));
+        }
+
+        if !cursor.goto_next_sibling() {
+          break;
+        }
+      }
+    }
+
+    children
+  }
+
+  pub(crate) fn common(&self, nodes: &[Node]) -> Option<String> {
+    if nodes.is_empty() {
+      return None;
+    }
+
+    let mut pattern = String::new();
+
+    self.shape(nodes, &mut pattern);
+
+    pattern.push_str(" @node");
+
+    Some(pattern)
+  }
+
+  pub(crate) fn depth(self, depth: usize) -> Self {
+    Self { depth, ..self }
+  }
+
+  /// Writes the pattern shared by `nodes` to `pattern`, with an explicit
+  /// stack rather than recursion so deeply nested input can't overflow it.
+  fn shape(&self, nodes: &[Node], pattern: &mut String) {
+    enum Step<'a> {
+      Shape(Vec<Node<'a>>, usize),
+      Text(&'static str),
+    }
+
+    let mut stack = vec![Step::Shape(nodes.to_vec(), 0)];
+
+    while let Some(step) = stack.pop() {
+      let (nodes, depth) = match step {
+        Step::Shape(nodes, depth) => (nodes, depth),
+        Step::Text(text) => {
+          pattern.push_str(text);
+          continue;
+        }
+      };
+
+      let first = nodes[0];
+
+      if nodes.iter().any(|node| node.kind_id() != first.kind_id()) {
+        pattern.push_str(if nodes.iter().all(Node::is_named) {
+          "(_)"
+        } else {
+          "_"
+        });
+
+        continue;
+      }
+
+      if !first.is_named() {
+        pattern.push('"');
+        pattern.push_str(&Breadcrumb::escape(first.kind()));
+        pattern.push('"');
+        continue;
+      }
+
+      pattern.push('(');
+      pattern.push_str(first.kind());
+
+      stack.push(Step::Text(")"));
+
+      let children = nodes
+        .iter()
+        .map(|node| self.children(node))
+        .collect::<Vec<_>>();
+
+      let count = children[0].len();
+
+      if depth < self.depth
+        && children.iter().all(|children| children.len() == count)
+      {
+        for i in (0..count).rev() {
+          let field = children[0][i].0;
+
+          stack.push(Step::Shape(
+            children.iter().map(|children| children[i].1).collect(),
+            depth + 1,
+          ));
+
+          if let Some(field) = field
+            .filter(|_| children.iter().all(|children| children[i].0 == field))
+          {
+            stack.push(Step::Text(": "));
+            stack.push(Step::Text(field));
+          }
+
+          stack.push(Step::Text(" "));
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn anonymous_children_are_quoted() {
+    let tree = parse("fn foo() {}");
+
+    let node = tree.root_node().child(0).unwrap();
+
+    assert_eq!(
+      Pattern::default().anonymous(true).common(&[node]),
+      Some(
+        "(function_item \"fn\" name: (identifier) parameters: (parameters \"(\" \")\") \
+         body: (block \"{\" \"}\")) @node"
+          .into()
+      ),
+    );
+  }
+
+  fn captures(code: &str, tree: &Tree, pattern: &str) -> Vec<usize> {
+    let query =
+      Query::new(&tree_sitter_rust::LANGUAGE.into(), pattern).unwrap();
+
+    let mut cursor = QueryCursor::new();
+
+    let mut matches = cursor.matches(&query, tree.root_node(), code.as_bytes());
+
+    let mut captured = Vec::new();
+
+    while let Some(m) = matches.next() {
+      captured.extend(m.captures.iter().map(|capture| capture.node.id()));
+    }
+
+    captured
+  }
+
+  #[test]
+  fn deeply_nested_input() {
+    const DEPTH: usize = 100_000;
+
+    let (_, tree) = deeply_nested(DEPTH);
+
+    assert_eq!(
+      Pattern::default()
+        .depth(usize::MAX)
+        .common(&[tree.root_node()])
+        .unwrap(),
+      format!(
+        "(document {}(array){} @node",
+        "(array ".repeat(DEPTH - 1),
+        ")".repeat(DEPTH)
+      )
+    );
+  }
+
+  #[test]
+  fn depth() {
+    #[track_caller]
+    fn case(node: Node, depth: usize, expected: &str) {
+      assert_eq!(
+        Pattern::default().depth(depth).common(&[node]).unwrap(),
+        expected
+      );
+    }
+
+    let tree = parse("fn foo() { bar; }");
+
+    let node = tree.root_node().child(0).unwrap();
+
+    case(node, 0, "(function_item) @node");
+
+    case(
+      node,
+      1,
+      "(function_item name: (identifier) parameters: (parameters) \
+       body: (block)) @node",
+    );
+
+    case(
+      node,
+      2,
+      "(function_item name: (identifier) parameters: (parameters) \
+       body: (block (expression_statement))) @node",
+    );
+  }
+
+  #[test]
+  fn different_kinds() {
+    let tree = parse("fn foo() {} struct Bar;");
+    let root = tree.root_node();
+
+    assert_eq!(
+      Pattern::default()
+        .common(&[root.child(0).unwrap(), root.child(1).unwrap()]),
+      Some("(_) @node".into()),
+    );
+  }
+
+  #[test]
+  fn differing_children_are_generalized() {
+    let code = "fn foo() { let x = 1; } fn bar(a: u8) { a; }";
+
+    let tree = parse(code);
+    let root = tree.root_node();
+
+    let nodes = [root.child(0).unwrap(), root.child(1).unwrap()];
+
+    let pattern = Pattern::default().common(&nodes).unwrap();
+
+    assert_eq!(
+      pattern,
+      "(function_item name: (identifier) parameters: (parameters) \
+       body: (block (_))) @node",
+    );
+
+    let captured = captures(code, &tree, &pattern);
+
+    assert!(nodes.iter().all(|node| captured.contains(&node.id())));
+  }
+
+  #[test]
+  fn empty() {
+    assert_eq!(Pattern::default().common(&[]), None);
+  }
+
+  #[test]
+  fn nesting_is_limited() {
+    let tree = parse("fn foo() { if a { if b { c; } } }");
+
+    let node = tree.root_node().child(0).unwrap();
+
+    assert_eq!(
+      Pattern::default().common(&[node]),
+      Some(
+        "(function_item name: (identifier) parameters: (parameters) \
+         body: (block (expression_statement (if_expression)))) @node"
+          .into()
+      ),
+    );
+  }
+
+  #[test]
+  fn same_shape() {
+    let code = "fn foo() {} fn bar() {}";
+
+    let tree = parse(code);
+    let root = tree.root_node();
+
+    let nodes = [root.child(0).unwrap(), root.child(1).unwrap()];
+
+    let pattern = Pattern::default().common(&nodes).unwrap();
+
+    assert_eq!(
+      pattern,
+      "(function_item name: (identifier) parameters: (parameters) \
+       body: (block)) @node",
+    );
+
+    assert_eq!(
+      captures(code, &tree, &pattern),
+      nodes.iter().map(Node::id).collect::<Vec<_>>(),
+    );
+  }
+}
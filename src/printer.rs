@@ -1,72 +1,121 @@
 use super::*;
 
 pub(crate) struct Printer<'a> {
-  code: &'a str,
   matches: HashSet<usize>,
+  source: &'a Source,
   tree: &'a Tree,
 }
 
 impl<'a> Printer<'a> {
-  fn has_match_descendant(&self, node: &Node) -> bool {
-    if self.matches.contains(&node.id()) {
-      return true;
-    }
-
-    for i in 0..node.child_count_u32() {
-      if let Some(child) = node.child(i) {
-        if self.has_match_descendant(&child) {
-          return true;
-        }
-      }
-    }
-
-    false
-  }
-
   pub(crate) fn new(
     tree: &'a Tree,
-    code: &'a str,
+    source: &'a Source,
     matches: HashSet<usize>,
   ) -> Self {
     Self {
-      code,
       matches,
+      source,
       tree,
     }
   }
 
   pub(crate) fn print(&self) {
-    self.print_node(&self.tree.root_node(), 0);
-  }
+    self.visit(|node, depth| {
+      let indent = "  ".repeat(depth);
 
-  fn print_node(&self, node: &Node, depth: usize) {
-    let filtering = !self.matches.is_empty();
+      let text = if node.child_count() == 0 {
+        format!(" \"{}\"", self.source.text(node.byte_range()))
+      } else {
+        String::new()
+      };
 
-    if filtering && !self.has_match_descendant(node) {
-      return;
+      println!(
+        "{indent}{} [{}:{}..{}:{}]{text}",
+        node.kind(),
+        node.start_position().row,
+        node.start_position().column,
+        node.end_position().row,
+        node.end_position().column,
+      );
+    });
+  }
+
+  /// Returns the matched nodes and all of their ancestors, or `None` when
+  /// there are no matches and the whole tree should be printed.
+  fn visible(&self) -> Option<HashSet<usize>> {
+    if self.matches.is_empty() {
+      return None;
     }
 
-    let indent = "  ".repeat(depth);
-
-    let text = if node.child_count() == 0 {
-      format!(" \"{}\"", &self.code[node.start_byte()..node.end_byte()])
-    } else {
-      String::new()
-    };
-
-    println!(
-      "{indent}{} [{}:{}..{}:{}]{text}",
-      node.kind(),
-      node.start_position().row,
-      node.start_position().column,
-      node.end_position().row,
-      node.end_position().column,
-    );
-
-    for i in 0..node.child_count_u32() {
-      if let Some(child) = node.child(i) {
-        self.print_node(&child, depth + 1);
+    let mut visible = HashSet::new();
+    let mut path = Vec::new();
+
+    self.tree.root_node().preorder(|node, depth| {
+      path.truncate(depth);
+      path.push(node.id());
+
+      if self.matches.contains(&node.id()) {
+        for id in path.iter().rev() {
+          if !visible.insert(*id) {
+            break;
+          }
+        }
       }
-    }
+
+      true
+    });
+
+    Some(visible)
+  }
+
+  /// Visits each node to print in pre-order along with its depth.
+  fn visit(&self, mut visit: impl FnMut(Node<'a>, usize)) {
+    let visible = self.visible();
+
+    self.tree.root_node().preorder(|node, depth| {
+      if visible
+        .as_ref()
+        .is_some_and(|visible| !visible.contains(&node.id()))
+      {
+        return false;
+      }
+
+      visit(node, depth);
+
+      true
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn deeply_nested_input() {
+    const DEPTH: usize = 100_000;
+
+    let (code, tree) = deeply_nested(DEPTH);
+    let root = tree.root_node();
+
+    let source = code.as_str().into();
+
+    let mut count = 0;
+
+    Printer::new(&tree, &source, HashSet::new()).visit(|_, _| count += 1);
+
+    assert_eq!(count, root.descendant_count());
+
+    let mut visited = Vec::new();
+
+    Printer::new(
+      &tree,
+      &source,
+      HashSet::from([root.find_at_byte(DEPTH).unwrap()]),
+    )
+    .visit(|node, depth| visited.push((node.kind(), depth)));
+
+    assert_eq!(visited.len(), DEPTH + 2);
+    assert_eq!(visited.last(), Some(&("]", DEPTH + 1)));
   }
 }
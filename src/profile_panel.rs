@@ -0,0 +1,51 @@
+use super::*;
+
+pub(crate) struct ProfilePanel<'a> {
+  profile: &'a QueryProfile,
+}
+
+impl Widget for ProfilePanel<'_> {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let mut lines = self
+      .profile
+      .lines()
+      .into_iter()
+      .map(|(pattern, line)| match pattern {
+        None => Line::from(Span::styled(
+          line,
+          Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Some(pattern) if pattern.measurement.outcome.warning().is_some() => {
+          Line::from(Span::styled(line, Style::default().fg(Color::Red)))
+        }
+        Some(_) => Line::from(line),
+      })
+      .collect::<Vec<_>>();
+
+    lines.push(Line::default());
+    lines.push(Line::from(self.profile.summary()));
+
+    Paragraph::new(lines)
+      .block(
+        Block::default()
+          .borders(Borders::ALL)
+          .title(format!(
+            " Query profile by {} (S to sort, P to close) ",
+            self.profile.sort
+          ))
+          .title_style(
+            Style::default()
+              .fg(Color::Magenta)
+              .add_modifier(Modifier::BOLD),
+          )
+          .border_style(Style::default().fg(Color::DarkGray)),
+      )
+      .render(area, buf);
+  }
+}
+
+impl<'a> ProfilePanel<'a> {
+  pub(crate) fn new(profile: &'a QueryProfile) -> Self {
+    Self { profile }
+  }
+}
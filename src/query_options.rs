@@ -0,0 +1,182 @@
+use super::*;
+
+/// Settings for running a query, mirroring the knobs `QueryCursor` offers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct QueryOptions {
+  pub(crate) match_limit: Option<u32>,
+  pub(crate) max_start_depth: Option<u32>,
+  pub(crate) range: Option<QueryRange>,
+  pub(crate) timeout: Option<Duration>,
+}
+
+/// Why a query run stopped before finding every match, if it did.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct QueryOutcome {
+  pub(crate) exceeded_match_limit: bool,
+  pub(crate) timed_out: bool,
+}
+
+impl QueryOutcome {
+  pub(crate) fn warning(&self) -> Option<&'static str> {
+    if self.timed_out {
+      Some("query timed out")
+    } else if self.exceeded_match_limit {
+      Some("query exceeded match limit")
+    } else {
+      None
+    }
+  }
+}
+
+impl QueryOptions {
+  /// Runs `query` over `node`, calling `f` with every match found before the
+  /// options stop it.
+  pub(crate) fn execute<'tree>(
+    &self,
+    query: &Query,
+    node: Node<'tree>,
+    source: &Source,
+    mut f: impl FnMut(&QueryMatch<'_, 'tree>),
+  ) -> QueryOutcome {
+    let mut cursor = QueryCursor::new();
+
+    if let Some(limit) = self.match_limit {
+      cursor.set_match_limit(limit);
+    }
+
+    cursor.set_max_start_depth(self.max_start_depth);
+
+    match &self.range {
+      Some(QueryRange::Bytes(range)) => {
+        cursor.set_byte_range(range.clone());
+      }
+      Some(QueryRange::Points(range)) => {
+        cursor.set_point_range(range.clone());
+      }
+      None => {}
+    }
+
+    let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+
+    let mut timed_out = false;
+
+    let mut progress = |_: &QueryCursorState| {
+      if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+        timed_out = true;
+        ControlFlow::Break(())
+      } else {
+        ControlFlow::Continue(())
+      }
+    };
+
+    let mut matches = cursor.matches_with_options(
+      query,
+      node,
+      source,
+      QueryCursorOptions::new().progress_callback(&mut progress),
+    );
+
+    while let Some(m) = matches.next() {
+      f(m);
+    }
+
+    drop(matches);
+
+    QueryOutcome {
+      exceeded_match_limit: cursor.did_exceed_match_limit(),
+      timed_out,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn byte_range() {
+    let options = QueryOptions {
+      range: Some(QueryRange::Bytes(12..23)),
+      ..QueryOptions::default()
+    };
+
+    assert_eq!(
+      captures(&options, "fn foo() {}\nfn bar() {}\nfn baz() {}").0,
+      ["bar"]
+    );
+  }
+
+  fn captures(
+    options: &QueryOptions,
+    code: &str,
+  ) -> (Vec<String>, QueryOutcome) {
+    let language = tree_sitter_rust::LANGUAGE.into();
+
+    let tree = parse_with(&language, code);
+
+    let query = Query::new(&language, "(identifier) @id").unwrap();
+
+    let mut captures = Vec::new();
+
+    let outcome =
+      options.execute(&query, tree.root_node(), &code.into(), |m| {
+        for capture in m.captures {
+          captures.push(code[capture.node.byte_range()].to_string());
+        }
+      });
+
+    (captures, outcome)
+  }
+
+  #[test]
+  fn default_finds_everything() {
+    assert_eq!(
+      captures(&QueryOptions::default(), "fn foo() {}\nfn bar() {}"),
+      (vec!["foo".into(), "bar".into()], QueryOutcome::default())
+    );
+  }
+
+  #[test]
+  fn max_start_depth() {
+    let options = QueryOptions {
+      max_start_depth: Some(1),
+      ..QueryOptions::default()
+    };
+
+    assert_eq!(
+      captures(&options, "fn foo() { bar(); }").0,
+      Vec::<String>::new()
+    );
+  }
+
+  #[test]
+  fn point_range() {
+    let options = QueryOptions {
+      range: Some(QueryRange::Points(
+        Point { row: 2, column: 0 }..Point { row: 3, column: 0 },
+      )),
+      ..QueryOptions::default()
+    };
+
+    assert_eq!(
+      captures(&options, "fn foo() {}\nfn bar() {}\nfn baz() {}").0,
+      ["baz"]
+    );
+  }
+
+  #[test]
+  fn timeout() {
+    let options = QueryOptions {
+      timeout: Some(Duration::ZERO),
+      ..QueryOptions::default()
+    };
+
+    let code = "fn foo() {}\n".repeat(10_000);
+
+    let (captures, outcome) = captures(&options, &code);
+
+    assert!(outcome.timed_out);
+    assert!(captures.len() < 10_000);
+    assert_eq!(outcome.warning(), Some("query timed out"));
+  }
+}
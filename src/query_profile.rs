@@ -0,0 +1,384 @@
+use super::*;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum ProfileSort {
+  Matches,
+  Pattern,
+  #[default]
+  Time,
+}
+
+impl Display for ProfileSort {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(
+      f,
+      "{}",
+      match self {
+        Self::Matches => "matches",
+        Self::Pattern => "pattern",
+        Self::Time => "time",
+      }
+    )
+  }
+}
+
+impl ProfileSort {
+  pub(crate) fn next(self) -> Self {
+    match self {
+      Self::Matches => Self::Pattern,
+      Self::Pattern => Self::Time,
+      Self::Time => Self::Matches,
+    }
+  }
+}
+
+/// How long a query, or one of its patterns, took to run over a tree, and
+/// whether the query options cut it short.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Measurement {
+  pub(crate) matches: usize,
+  pub(crate) outcome: QueryOutcome,
+  pub(crate) time: Duration,
+}
+
+impl Measurement {
+  fn new(
+    query: &Query,
+    tree: &Tree,
+    source: &Source,
+    options: &QueryOptions,
+  ) -> Self {
+    let start = Instant::now();
+
+    let mut matches = 0;
+
+    let outcome = options.execute(query, tree.root_node(), source, |_| {
+      matches += 1;
+    });
+
+    let time = start.elapsed();
+
+    Self {
+      matches,
+      outcome,
+      time,
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct PatternProfile {
+  pub(crate) index: usize,
+  pub(crate) measurement: Measurement,
+  pub(crate) row: usize,
+  pub(crate) source: String,
+}
+
+/// Per-pattern timings and match counts for a query, measured by running
+/// each pattern on its own.
+#[derive(Debug, PartialEq)]
+pub(crate) struct QueryProfile {
+  pub(crate) patterns: Vec<PatternProfile>,
+  pub(crate) sort: ProfileSort,
+  pub(crate) total: Measurement,
+}
+
+impl Display for QueryProfile {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    for (_, line) in self.lines() {
+      writeln!(f, "{line}")?;
+    }
+
+    writeln!(f, "\n{}", self.summary())
+  }
+}
+
+impl QueryProfile {
+  const SOURCE_WIDTH: usize = 60;
+
+  /// Returns the table header, then a line for each pattern in the current
+  /// sort order along with the pattern it describes.
+  pub(crate) fn lines(&self) -> Vec<(Option<&PatternProfile>, String)> {
+    let mut rows = vec![(
+      None,
+      [
+        "pattern".to_string(),
+        "line".into(),
+        "matches".into(),
+        "time".into(),
+        String::new(),
+      ],
+    )];
+
+    for pattern in self.sorted() {
+      rows.push((
+        Some(pattern),
+        [
+          pattern.index.to_string(),
+          pattern.row.to_string(),
+          pattern.measurement.matches.to_string(),
+          format!("{:?}", pattern.measurement.time),
+          match pattern.measurement.outcome.warning() {
+            Some(warning) => format!("{}  ({warning})", pattern.source),
+            None => pattern.source.clone(),
+          },
+        ],
+      ));
+    }
+
+    let widths = (0..4)
+      .map(|i| {
+        rows
+          .iter()
+          .map(|(_, row)| row[i].chars().count())
+          .max()
+          .unwrap_or_default()
+      })
+      .collect::<Vec<_>>();
+
+    rows
+      .into_iter()
+      .map(|(pattern, row)| {
+        let line = format!(
+          "{:>w0$}  {:>w1$}  {:>w2$}  {:<w3$}  {}",
+          row[0],
+          row[1],
+          row[2],
+          row[3],
+          row[4],
+          w0 = widths[0],
+          w1 = widths[1],
+          w2 = widths[2],
+          w3 = widths[3],
+        );
+
+        (pattern, line.trim_end().to_string())
+      })
+      .collect()
+  }
+
+  /// Profiles each pattern of `source` over `tree` with `options`, so limits
+  /// that cut the real query short show up in the profile too.
+  pub(crate) fn new(
+    language: &TreeSitterLanguage,
+    source: &str,
+    tree: &Tree,
+    code: &Source,
+    options: &QueryOptions,
+  ) -> Result<Self> {
+    let query = Query::new(language, source).map_err(|error| {
+      anyhow!("{}:{}: {}", error.row + 1, error.column + 1, error.message)
+    })?;
+
+    let mut patterns = Vec::new();
+
+    for index in 0..query.pattern_count() {
+      let (start, end) = (
+        query.start_byte_for_pattern(index),
+        query.end_byte_for_pattern(index),
+      );
+
+      // Compiling each pattern on its own is much cheaper than compiling the
+      // whole query once per pattern, which is only needed when a pattern
+      // can't stand alone.
+      let pattern =
+        if let Ok(pattern) = Query::new(language, &source[start..end]) {
+          pattern
+        } else {
+          let mut pattern = Query::new(language, source)?;
+
+          for other in (0..query.pattern_count()).filter(|&i| i != index) {
+            pattern.disable_pattern(other);
+          }
+
+          pattern
+        };
+
+      let text = source[start..end].split_whitespace().collect::<Vec<_>>();
+
+      let mut text = text.join(" ");
+
+      if text.chars().count() > Self::SOURCE_WIDTH {
+        text = format!(
+          "{}…",
+          text
+            .chars()
+            .take(Self::SOURCE_WIDTH - 1)
+            .collect::<String>()
+        );
+      }
+
+      patterns.push(PatternProfile {
+        index,
+        measurement: Measurement::new(&pattern, tree, code, options),
+        row: source[..start].matches('\n').count() + 1,
+        source: text,
+      });
+    }
+
+    Ok(Self {
+      patterns,
+      sort: ProfileSort::default(),
+      total: Measurement::new(&query, tree, code, options),
+    })
+  }
+
+  /// Returns the patterns in the current sort order, slowest or most
+  /// matched first.
+  pub(crate) fn sorted(&self) -> Vec<&PatternProfile> {
+    let mut patterns = self.patterns.iter().collect::<Vec<_>>();
+
+    match self.sort {
+      ProfileSort::Matches => {
+        patterns.sort_by_key(|pattern| Reverse(pattern.measurement.matches));
+      }
+      ProfileSort::Pattern => patterns.sort_by_key(|pattern| pattern.index),
+      ProfileSort::Time => {
+        patterns.sort_by_key(|pattern| Reverse(pattern.measurement.time));
+      }
+    }
+
+    patterns
+  }
+
+  /// Returns the total match count and time of the whole query.
+  pub(crate) fn summary(&self) -> String {
+    let mut summary =
+      format!("{} matches in {:?}", self.total.matches, self.total.time);
+
+    if let Some(warning) = self.total.outcome.warning() {
+      summary = format!("{summary}, {warning}");
+    }
+
+    summary
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn counts_matches_per_pattern() {
+    let profile = profile(
+      "fn foo() {}\nfn bar() { baz(); }",
+      "(identifier) @id\n\n(function_item\n  name: (identifier) @name)\n",
+    );
+
+    assert_eq!(
+      profile
+        .patterns
+        .iter()
+        .map(|pattern| (
+          pattern.index,
+          pattern.row,
+          pattern.measurement.matches,
+          pattern.source.as_str()
+        ))
+        .collect::<Vec<_>>(),
+      [
+        (0, 1, 3, "(identifier) @id"),
+        (1, 3, 2, "(function_item name: (identifier) @name)"),
+      ]
+    );
+
+    assert_eq!(profile.total.matches, 5);
+  }
+
+  #[test]
+  fn display() {
+    let pattern = |index, matches, millis, source: &str| PatternProfile {
+      index,
+      measurement: Measurement {
+        matches,
+        outcome: QueryOutcome {
+          exceeded_match_limit: index == 1,
+          timed_out: false,
+        },
+        time: Duration::from_millis(millis),
+      },
+      row: index * 2 + 1,
+      source: source.into(),
+    };
+
+    let mut profile = QueryProfile {
+      patterns: vec![
+        pattern(0, 12, 1, "(identifier) @variable"),
+        pattern(1, 3, 25, "(call_expression) @call"),
+      ],
+      sort: ProfileSort::Time,
+      total: Measurement {
+        matches: 15,
+        outcome: QueryOutcome::default(),
+        time: Duration::from_millis(26),
+      },
+    };
+
+    assert_eq!(
+      profile.to_string(),
+      [
+        "pattern  line  matches  time",
+        "      1     3        3  25ms  (call_expression) @call  (query exceeded match limit)",
+        "      0     1       12  1ms   (identifier) @variable",
+        "",
+        "15 matches in 26ms",
+        "",
+      ]
+      .join("\n")
+    );
+
+    profile.sort = profile.sort.next();
+
+    assert_eq!(profile.sorted()[0].index, 0);
+  }
+
+  #[test]
+  fn invalid_query() {
+    let language = tree_sitter_rust::LANGUAGE.into();
+
+    let tree = parse_with(&language, "");
+
+    assert!(QueryProfile::new(
+      &language,
+      "(nope) @x",
+      &tree,
+      &"".into(),
+      &QueryOptions::default()
+    )
+    .unwrap_err()
+    .to_string()
+    .starts_with("1:2: "));
+  }
+
+  #[test]
+  fn match_limit() {
+    let profile = profile_with(
+      "fn foo() { a; b; c; }",
+      "(block (expression_statement) @a (expression_statement) @b)",
+      &QueryOptions {
+        match_limit: Some(1),
+        ..QueryOptions::default()
+      },
+    );
+
+    assert!(profile.patterns[0].measurement.outcome.exceeded_match_limit);
+    assert!(profile.total.outcome.exceeded_match_limit);
+    assert!(profile.summary().ends_with(", query exceeded match limit"));
+  }
+
+  fn profile(code: &str, source: &str) -> QueryProfile {
+    profile_with(code, source, &QueryOptions::default())
+  }
+
+  fn profile_with(
+    code: &str,
+    source: &str,
+    options: &QueryOptions,
+  ) -> QueryProfile {
+    let language = tree_sitter_rust::LANGUAGE.into();
+
+    let tree = parse_with(&language, code);
+
+    QueryProfile::new(&language, source, &tree, &code.into(), options).unwrap()
+  }
+}
@@ -0,0 +1,79 @@
+use super::*;
+
+/// A range of the source to run a query over, given either in bytes as
+/// `START..END` or in zero-based positions as `ROW:COLUMN..ROW:COLUMN`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum QueryRange {
+  Bytes(Range<usize>),
+  Points(Range<Point>),
+}
+
+impl FromStr for QueryRange {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    fn point(s: &str) -> Option<Point> {
+      let (row, column) = s.split_once(':')?;
+
+      Some(Point {
+        row: row.trim().parse().ok()?,
+        column: column.trim().parse().ok()?,
+      })
+    }
+
+    let invalid = || {
+      anyhow!(
+        "invalid range `{s}`, expected `START..END` or \
+         `ROW:COLUMN..ROW:COLUMN`"
+      )
+    };
+
+    let (start, end) = s.split_once("..").ok_or_else(invalid)?;
+
+    let range = if start.contains(':') || end.contains(':') {
+      Self::Points(
+        point(start).ok_or_else(invalid)?..point(end).ok_or_else(invalid)?,
+      )
+    } else {
+      Self::Bytes(
+        start.trim().parse().map_err(|_| invalid())?
+          ..end.trim().parse().map_err(|_| invalid())?,
+      )
+    };
+
+    let empty = match &range {
+      Self::Bytes(range) => range.start > range.end,
+      Self::Points(range) => range.start > range.end,
+    };
+
+    if empty {
+      return Err(anyhow!("range `{s}` ends before it starts"));
+    }
+
+    Ok(range)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn from_str() {
+    assert_eq!(
+      "3..10".parse::<QueryRange>().unwrap(),
+      QueryRange::Bytes(3..10)
+    );
+
+    assert_eq!(
+      "1:0..4:2".parse::<QueryRange>().unwrap(),
+      QueryRange::Points(
+        Point { row: 1, column: 0 }..Point { row: 4, column: 2 }
+      )
+    );
+
+    for invalid in ["", "3", "a..b", "1:0..4", "10..3"] {
+      assert!(invalid.parse::<QueryRange>().is_err(), "{invalid}");
+    }
+  }
+}
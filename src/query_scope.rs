@@ -0,0 +1,37 @@
+use super::*;
+
+/// The part of the tree that queries typed into the TUI run over.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum QueryScope {
+  /// The subtree of the node with this id.
+  Subtree(usize),
+  #[default]
+  Tree,
+  /// The rows visible when the query runs.
+  Viewport,
+}
+
+impl Display for QueryScope {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(
+      f,
+      "{}",
+      match self {
+        Self::Subtree(_) => "subtree",
+        Self::Tree => "tree",
+        Self::Viewport => "viewport",
+      }
+    )
+  }
+}
+
+impl QueryScope {
+  /// Returns the scope after this one, scoping subtrees to `cursor`.
+  pub(crate) fn next(self, cursor: usize) -> Self {
+    match self {
+      Self::Subtree(_) => Self::Viewport,
+      Self::Tree => Self::Subtree(cursor),
+      Self::Viewport => Self::Tree,
+    }
+  }
+}
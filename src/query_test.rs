@@ -0,0 +1,137 @@
+use super::*;
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct QueryTest {
+  /// Read the files as `utf-8`, `utf-16le`, `utf-16be` or `latin-1` instead
+  /// of detecting their encodings
+  #[clap(long, value_name = "ENCODING")]
+  encoding: Option<Encoding>,
+  /// Annotated source files to check
+  #[clap(required = true)]
+  files: Vec<PathBuf>,
+  /// Grammar to use, as a language name or a path to a grammar library
+  /// (detected from each file's extension if omitted)
+  #[clap(short, long)]
+  language: Option<Grammar>,
+  /// Query file whose captures the assertions are checked against
+  #[clap(short, long, value_name = "FILE")]
+  query: PathBuf,
+}
+
+impl QueryTest {
+  /// Checks the assertions in one file, printing a line for each mismatch,
+  /// and returns the number of assertions that passed and failed.
+  fn check(
+    &self,
+    path: &Path,
+    query_source: &str,
+    parser: &mut Parser,
+  ) -> Result<(usize, usize)> {
+    let source = Source::new(
+      fs::read(path)
+        .with_context(|| format!("failed to read `{}`", path.display()))?,
+      self.encoding,
+    );
+
+    let language = match &self.language {
+      Some(grammar) => grammar.load()?,
+      None => Language::try_from(path.to_path_buf())
+        .with_context(|| {
+          format!("failed to detect language for `{}`", path.display())
+        })?
+        .into(),
+    };
+
+    parser.set_language(&language)?;
+
+    let query = Query::new(&language, query_source)
+      .with_context(|| format!("invalid query `{}`", self.query.display()))?;
+
+    let tree = source
+      .parse(parser, None)
+      .ok_or_else(|| anyhow!("failed to parse `{}`", path.display()))?;
+
+    let mut captures = Vec::new();
+
+    let mut cursor = QueryCursor::new();
+
+    let mut matches = cursor.matches(&query, tree.root_node(), &source);
+
+    while let Some(m) = matches.next() {
+      for capture in m.captures {
+        captures.push((
+          capture.node.start_position(),
+          capture.node.end_position(),
+          query.capture_names()[capture.index as usize],
+        ));
+      }
+    }
+
+    let (mut passed, mut failed) = (0, 0);
+
+    for assertion in Assertion::parse(&tree, &source) {
+      let names = captures
+        .iter()
+        .filter(|(start, end, _)| {
+          *start <= assertion.position && assertion.position < *end
+        })
+        .map(|(.., name)| *name)
+        .collect::<Vec<_>>();
+
+      if assertion.check(&names) {
+        passed += 1;
+        continue;
+      }
+
+      let found = if names.is_empty() {
+        "no captures".into()
+      } else {
+        names
+          .iter()
+          .map(|name| format!("`{name}`"))
+          .collect::<Vec<_>>()
+          .join(", ")
+      };
+
+      println!(
+        "{}:{}:{}: expected `{assertion}`, found {found}",
+        path.display(),
+        assertion.position.row + 1,
+        assertion.position.column + 1,
+      );
+
+      failed += 1;
+    }
+
+    Ok((passed, failed))
+  }
+
+  pub(crate) fn run(self) -> Result {
+    let query_source = fs::read_to_string(&self.query).with_context(|| {
+      format!("failed to read query `{}`", self.query.display())
+    })?;
+
+    let mut parser = Parser::new();
+
+    let (mut passed, mut failed) = (0, 0);
+
+    for path in &self.files {
+      let (file_passed, file_failed) =
+        self.check(path, &query_source, &mut parser)?;
+
+      passed += file_passed;
+      failed += file_failed;
+    }
+
+    println!("{passed} passed, {failed} failed");
+
+    if failed > 0 {
+      return Err(anyhow!(
+        "{failed} of {} query assertions failed",
+        passed + failed
+      ));
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,109 @@
+use super::*;
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Row {
+  pub(crate) depth: usize,
+  pub(crate) id: usize,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Rows {
+  positions: HashMap<usize, usize>,
+  rows: Vec<Row>,
+}
+
+impl Rows {
+  pub(crate) fn get(&self, index: usize) -> Option<&Row> {
+    self.rows.get(index)
+  }
+
+  pub(crate) fn iter(&self) -> impl Iterator<Item = &Row> {
+    self.rows.iter()
+  }
+
+  pub(crate) fn last(&self) -> Option<&Row> {
+    self.rows.last()
+  }
+
+  pub(crate) fn len(&self) -> usize {
+    self.rows.len()
+  }
+
+  pub(crate) fn new(root: Node, collapsed: &HashSet<usize>) -> Self {
+    let mut rows = Self::default();
+
+    root.preorder(|node, depth| {
+      rows.positions.insert(node.id(), rows.rows.len());
+
+      rows.rows.push(Row {
+        depth,
+        id: node.id(),
+      });
+
+      !collapsed.contains(&node.id())
+    });
+
+    rows
+  }
+
+  pub(crate) fn position(&self, id: usize) -> Option<usize> {
+    self.positions.get(&id).copied()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn preorder_with_depths() {
+    let tree = parse("fn foo() {}");
+    let root = tree.root_node();
+
+    let rows = Rows::new(root, &HashSet::new());
+
+    assert_eq!(
+      rows.iter().map(|row| row.depth).collect::<Vec<_>>(),
+      vec![0, 1, 2, 2, 2, 3, 3, 2, 3, 3],
+    );
+
+    assert_eq!(rows.position(root.id()), Some(0));
+    assert_eq!(rows.position(root.child(0).unwrap().id()), Some(1));
+    assert_eq!(rows.last().map(|row| row.depth), Some(3));
+  }
+
+  #[test]
+  fn skips_collapsed_children() {
+    let tree = parse("fn foo() {} fn bar() {}");
+    let root = tree.root_node();
+
+    let first = root.child(0).unwrap();
+    let second = root.child(1).unwrap();
+
+    let rows = Rows::new(root, &HashSet::from([first.id()]));
+
+    assert_eq!(rows.position(first.id()), Some(1));
+    assert_eq!(rows.position(first.child(0).unwrap().id()), None);
+    assert_eq!(rows.position(second.id()), Some(2));
+  }
+
+  #[test]
+  fn subtree_root() {
+    let tree = parse("fn foo() {} fn bar() {}");
+    let root = tree.root_node();
+
+    let second = root.child(1).unwrap();
+
+    let rows = Rows::new(second, &HashSet::new());
+
+    assert_eq!(
+      rows.get(0),
+      Some(&Row {
+        depth: 0,
+        id: second.id()
+      })
+    );
+    assert_eq!(rows.position(root.child(0).unwrap().id()), None);
+    assert_eq!(rows.len(), 9);
+  }
+}
@@ -0,0 +1,415 @@
+use super::*;
+
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+pub(crate) struct Session {
+  pub(crate) collapsed: Vec<NodeKey>,
+  pub(crate) cursor: Option<NodeKey>,
+  /// The node a subtree query scope is on, which only needs to survive
+  /// reparses, not sessions.
+  #[serde(skip)]
+  pub(crate) query_scope: Option<NodeKey>,
+  /// When the session was last saved, so that the store can forget the
+  /// oldest ones. Missing from sessions saved by older versions.
+  #[serde(default)]
+  pub(crate) saved_at: Option<SystemTime>,
+  pub(crate) scroll_offset: u16,
+  pub(crate) search_query: String,
+  #[serde(default)]
+  pub(crate) selected: Vec<NodeKey>,
+  pub(crate) ts_query: String,
+  #[serde(default)]
+  pub(crate) zoom: Option<NodeKey>,
+}
+
+impl Session {
+  pub(crate) fn capture(state: &State, tree: &Tree) -> Self {
+    let mut collapsed = Vec::new();
+    let mut cursor = None;
+    let mut query_scope = None;
+    let mut selected = Vec::new();
+    let mut zoom = None;
+
+    let mut path = Vec::new();
+
+    tree.root_node().preorder(|node, depth| {
+      if path.len() >= depth && depth > 0 {
+        path.truncate(depth);
+        path[depth - 1] += 1;
+      } else if depth > 0 {
+        path.push(0);
+      }
+
+      let id = node.id();
+
+      let key = || NodeKey::new(&node, path.clone());
+
+      if state.collapsed_nodes().contains(&id) {
+        collapsed.push(key());
+      }
+
+      if id == state.cursor {
+        cursor = Some(key());
+      }
+
+      if state.query_scope == QueryScope::Subtree(id) {
+        query_scope = Some(key());
+      }
+
+      if state.selected.contains(id) {
+        selected.push(key());
+      }
+
+      if state.zoom() == Some(id) {
+        zoom = Some(key());
+      }
+
+      true
+    });
+
+    Self {
+      collapsed,
+      cursor,
+      query_scope,
+      saved_at: None,
+      scroll_offset: state.scroll_offset,
+      search_query: state.search_query.clone(),
+      selected,
+      ts_query: state.ts_query.clone(),
+      zoom,
+    }
+  }
+
+  pub(crate) fn edit(&mut self, edit: &InputEdit) {
+    for key in self
+      .collapsed
+      .iter_mut()
+      .chain(&mut self.cursor)
+      .chain(&mut self.query_scope)
+      .chain(&mut self.selected)
+      .chain(&mut self.zoom)
+    {
+      key.edit(edit);
+    }
+  }
+
+  pub(crate) fn restore(&self, state: &mut State, tree: &Tree) {
+    let root = tree.root_node();
+
+    let resolve = |key: &Option<NodeKey>| {
+      key
+        .as_ref()
+        .and_then(|key| key.resolve(root))
+        .map(|node| node.id())
+    };
+
+    state.set_collapsed_nodes(
+      self
+        .collapsed
+        .iter()
+        .filter_map(|key| key.resolve(root))
+        .map(|node| node.id())
+        .collect(),
+    );
+
+    if let Some(node) = self.cursor.as_ref().and_then(|key| {
+      key.resolve(root).or_else(|| {
+        root.descendant_for_byte_range(key.start_byte, key.start_byte)
+      })
+    }) {
+      state.cursor = node.id();
+    }
+
+    state.selected = self
+      .selected
+      .iter()
+      .filter_map(|key| key.resolve(root))
+      .map(|node| node.id())
+      .collect();
+
+    state.set_zoom(resolve(&self.zoom));
+    state.scroll_offset = self.scroll_offset;
+  }
+
+  /// Moves a subtree query scope to the captured node in `tree`, falling back
+  /// to the whole tree if it's gone.
+  pub(crate) fn restore_query_scope(&self, state: &mut State, tree: &Tree) {
+    if let QueryScope::Subtree(_) = state.query_scope {
+      state.query_scope = self
+        .query_scope
+        .as_ref()
+        .and_then(|key| key.resolve(tree.root_node()))
+        .map_or(QueryScope::Tree, |node| QueryScope::Subtree(node.id()));
+    }
+  }
+}
+
+#[derive(Debug)]
+pub(crate) struct SessionStore {
+  path: PathBuf,
+}
+
+impl SessionStore {
+  const MAX_SESSIONS: usize = 100;
+
+  fn key(source: &Path) -> Result<PathBuf> {
+    fs::canonicalize(source)
+      .with_context(|| format!("failed to canonicalize `{}`", source.display()))
+  }
+
+  pub(crate) fn load(&self, source: &Path) -> Result<Option<Session>> {
+    Ok(self.read()?.remove(&Self::key(source)?))
+  }
+
+  pub(crate) fn new() -> Result<Self> {
+    let directory = dirs::state_dir()
+      .or_else(dirs::data_local_dir)
+      .ok_or_else(|| anyhow!("failed to locate state directory"))?;
+
+    Ok(Self::with_path(
+      directory.join("axil").join("sessions.json"),
+    ))
+  }
+
+  fn read(&self) -> Result<BTreeMap<PathBuf, Session>> {
+    match fs::read_to_string(&self.path) {
+      Ok(content) => serde_json::from_str(&content).with_context(|| {
+        format!("failed to parse session file `{}`", self.path.display())
+      }),
+      Err(error) if error.kind() == io::ErrorKind::NotFound => {
+        Ok(BTreeMap::new())
+      }
+      Err(error) => Err(error.into()),
+    }
+  }
+
+  /// Saves the session for `source`, dropping sessions for files that no
+  /// longer exist and, past `MAX_SESSIONS`, the least recently saved ones.
+  pub(crate) fn save(&self, source: &Path, mut session: Session) -> Result {
+    let key = Self::key(source)?;
+
+    let mut sessions = self.read()?;
+
+    sessions.remove(&key);
+
+    sessions.retain(|path, _| path.exists());
+
+    if sessions.len() >= Self::MAX_SESSIONS {
+      let mut saved = sessions
+        .iter()
+        .map(|(path, session)| (session.saved_at, path.clone()))
+        .collect::<Vec<_>>();
+
+      saved.sort();
+
+      for (_, path) in &saved[..=sessions.len() - Self::MAX_SESSIONS] {
+        sessions.remove(path);
+      }
+    }
+
+    session.saved_at = Some(SystemTime::now());
+
+    sessions.insert(key, session);
+
+    if let Some(parent) = self.path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+
+    fs::write(&self.path, serde_json::to_string_pretty(&sessions)?)?;
+
+    Ok(())
+  }
+
+  pub(crate) fn with_path(path: PathBuf) -> Self {
+    Self { path }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn capture_and_restore() {
+    let code = "fn foo() {} fn bar() {}";
+
+    let tree = parse(code);
+    let root = tree.root_node();
+
+    let mut state = State::new(root.id());
+    state.cursor = root.child(1).unwrap().id();
+    state.set_collapsed_nodes(HashSet::from([root.child(0).unwrap().id()]));
+    state.scroll_offset = 3;
+    state.search_query = "bar".into();
+    state.ts_query = "(identifier) @name".into();
+
+    let session = Session::capture(&state, &tree);
+
+    let tree = parse(code);
+    let root = tree.root_node();
+
+    let mut restored = State::new(root.id());
+
+    session.restore(&mut restored, &tree);
+
+    assert_eq!(restored.cursor, root.child(1).unwrap().id());
+    assert_eq!(
+      restored.collapsed_nodes(),
+      &HashSet::from([root.child(0).unwrap().id()])
+    );
+    assert_eq!(restored.scroll_offset, 3);
+  }
+
+  #[test]
+  fn capture_records_paths() {
+    let tree = parse("fn foo() {} fn bar() { let x = 1; }");
+    let root = tree.root_node();
+
+    let block = root.child(1).unwrap().child(3).unwrap();
+    let statement = block.child(1).unwrap();
+
+    let mut state = State::new(statement.id());
+    state.selected = vec![block.id()].into();
+    state.set_zoom(Some(root.child(1).unwrap().id()));
+
+    let session = Session::capture(&state, &tree);
+
+    assert_eq!(session.cursor.unwrap().path, Some(vec![1, 3, 1]));
+    assert_eq!(session.selected[0].path, Some(vec![1, 3]));
+    assert_eq!(session.zoom.unwrap().path, Some(vec![1]));
+  }
+
+  #[test]
+  fn restore_after_edit_that_shifts_ranges() {
+    let tree = parse("fn foo() {} fn bar() { let x = 1; }");
+    let root = tree.root_node();
+
+    let second = root.child(1).unwrap();
+    let statement = second.child(3).unwrap().child(1).unwrap();
+
+    let mut state = State::new(statement.id());
+    state.set_collapsed_nodes(HashSet::from([second.id()]));
+    state.selected = vec![second.id(), statement.id()].into();
+    state.set_zoom(Some(second.id()));
+
+    let session = Session::capture(&state, &tree);
+
+    let tree = parse("fn foobar() {} fn bar() { let xyz = 123; }");
+    let root = tree.root_node();
+
+    let second = root.child(1).unwrap();
+    let statement = second.child(3).unwrap().child(1).unwrap();
+
+    let mut restored = State::new(root.id());
+
+    session.restore(&mut restored, &tree);
+
+    assert_eq!(restored.cursor, statement.id());
+    assert_eq!(restored.selected, vec![second.id(), statement.id()]);
+    assert_eq!(restored.zoom(), Some(second.id()));
+    assert_eq!(restored.collapsed_nodes(), &HashSet::from([second.id()]));
+  }
+
+  #[test]
+  fn restore_falls_back_to_cursor_offset() {
+    let tree = parse("fn foo() {}");
+    let root = tree.root_node();
+
+    let session = Session {
+      cursor: Some(NodeKey {
+        end_byte: 6,
+        kind: "struct_item".into(),
+        path: None,
+        start_byte: 3,
+      }),
+      ..Session::default()
+    };
+
+    let mut state = State::new(root.id());
+
+    session.restore(&mut state, &tree);
+
+    assert_eq!(state.node(&tree).unwrap().kind(), "identifier");
+  }
+
+  #[test]
+  fn save_drops_missing_files() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let (kept, removed) = (
+      tempdir.path().join("kept.rs"),
+      tempdir.path().join("removed.rs"),
+    );
+
+    fs::write(&kept, "").unwrap();
+    fs::write(&removed, "").unwrap();
+
+    let store = SessionStore::with_path(tempdir.path().join("sessions.json"));
+
+    store.save(&removed, Session::default()).unwrap();
+
+    fs::remove_file(&removed).unwrap();
+
+    store.save(&kept, Session::default()).unwrap();
+
+    assert_eq!(
+      store.read().unwrap().into_keys().collect::<Vec<_>>(),
+      [fs::canonicalize(&kept).unwrap()],
+    );
+  }
+
+  #[test]
+  fn save_forgets_least_recently_saved() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let store = SessionStore::with_path(tempdir.path().join("sessions.json"));
+
+    let sources = (0..=SessionStore::MAX_SESSIONS)
+      .map(|i| {
+        let source = tempdir.path().join(format!("{i}.rs"));
+        fs::write(&source, "").unwrap();
+        source
+      })
+      .collect::<Vec<_>>();
+
+    for source in &sources {
+      store.save(source, Session::default()).unwrap();
+    }
+
+    store.save(&sources[1], Session::default()).unwrap();
+
+    let sessions = store.read().unwrap();
+
+    assert_eq!(sessions.len(), SessionStore::MAX_SESSIONS);
+    assert!(!sessions.contains_key(&fs::canonicalize(&sources[0]).unwrap()));
+    assert!(sessions.contains_key(&fs::canonicalize(&sources[1]).unwrap()));
+  }
+
+  #[test]
+  fn store_round_trip() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let source = tempdir.path().join("foo.rs");
+
+    fs::write(&source, "fn foo() {}").unwrap();
+
+    let store =
+      SessionStore::with_path(tempdir.path().join("state/sessions.json"));
+
+    assert_eq!(store.load(&source).unwrap(), None);
+
+    let session = Session {
+      search_query: "foo".into(),
+      ..Session::default()
+    };
+
+    store.save(&source, session).unwrap();
+
+    assert_eq!(
+      store
+        .load(&source)
+        .unwrap()
+        .map(|session| session.search_query),
+      Some("foo".into())
+    );
+  }
+}
@@ -0,0 +1,194 @@
+use super::*;
+
+/// A tokenized S-expression, as produced by `NodeExt::sexp` or written by
+/// hand in a corpus file.
+pub(crate) struct Sexp<'a> {
+  tokens: Vec<&'a str>,
+}
+
+impl<'a> Sexp<'a> {
+  /// Returns whether any node is labelled with a field name.
+  pub(crate) fn fields(&self) -> bool {
+    self.tokens.iter().any(|token| Self::is_field(token))
+  }
+
+  fn is_field(token: &str) -> bool {
+    token.len() > 1 && token.ends_with(':') && !token.starts_with('"')
+  }
+
+  pub(crate) fn new(text: &'a str) -> Self {
+    let mut tokens = Vec::new();
+
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+      let end = match c {
+        c if c.is_whitespace() => continue,
+        '(' | ')' => start + 1,
+        '"' => {
+          let mut escaped = false;
+
+          chars
+            .by_ref()
+            .find(|&(_, c)| {
+              let end = c == '"' && !escaped;
+              escaped = c == '\\' && !escaped;
+              end
+            })
+            .map_or(text.len(), |(i, _)| i + 1)
+        }
+        _ => {
+          while chars
+            .next_if(|&(_, c)| !c.is_whitespace() && c != '(' && c != ')')
+            .is_some()
+          {}
+
+          chars.peek().map_or(text.len(), |&(i, _)| i)
+        }
+      };
+
+      tokens.push(&text[start..end]);
+    }
+
+    Self { tokens }
+  }
+
+  /// Renders the expression on one line with canonical spacing, leaving out
+  /// field names unless `fields` is set.
+  pub(crate) fn normalize(&self, fields: bool) -> String {
+    let mut output = String::new();
+
+    for &token in &self.tokens {
+      if !fields && Self::is_field(token) {
+        continue;
+      }
+
+      if !output.is_empty() && !output.ends_with('(') && token != ")" {
+        output.push(' ');
+      }
+
+      output.push_str(token);
+    }
+
+    output
+  }
+
+  /// Renders the expression with one node per line, the way corpus files
+  /// are conventionally written, leaving out field names unless `fields` is
+  /// set.
+  pub(crate) fn pretty(&self, fields: bool) -> String {
+    let mut output = String::new();
+
+    let (mut depth, mut field) = (0, false);
+
+    for &token in &self.tokens {
+      match token {
+        "(" => {
+          if !output.is_empty() && !field {
+            output.push('\n');
+            output.push_str(&"  ".repeat(depth));
+          }
+
+          output.push('(');
+
+          depth += 1;
+          field = false;
+        }
+        ")" => {
+          output.push(')');
+          depth = depth.saturating_sub(1);
+        }
+        token if Self::is_field(token) && !fields => {}
+        token if Self::is_field(token) => {
+          output.push('\n');
+          output.push_str(&"  ".repeat(depth));
+          output.push_str(token);
+          output.push(' ');
+          field = true;
+        }
+        token => {
+          if !output.is_empty() && !output.ends_with(['(', ' ']) {
+            output.push(' ');
+          }
+
+          output.push_str(token);
+        }
+      }
+    }
+
+    output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn deeply_nested_input() {
+    const DEPTH: usize = 100_000;
+
+    let (code, tree) = deeply_nested(DEPTH);
+
+    let sexp = tree.root_node().sexp(&code.as_str().into());
+
+    let sexp = Sexp::new(&sexp);
+
+    assert!(!sexp.fields());
+
+    assert_eq!(
+      sexp.normalize(false),
+      format!(
+        "(document {}(array){}",
+        "(array ".repeat(DEPTH - 1),
+        ")".repeat(DEPTH)
+      )
+    );
+  }
+
+  #[test]
+  fn fields() {
+    assert!(Sexp::new("(a name: (b))").fields());
+    assert!(!Sexp::new("(a (b) (MISSING \":\"))").fields());
+  }
+
+  #[test]
+  fn normalize() {
+    let sexp = Sexp::new(
+      "(source_file\n  (function_item\n    name: (identifier)\n    body: (block)))",
+    );
+
+    assert_eq!(
+      sexp.normalize(true),
+      "(source_file (function_item name: (identifier) body: (block)))"
+    );
+
+    assert_eq!(
+      sexp.normalize(false),
+      "(source_file (function_item (identifier) (block)))"
+    );
+
+    assert_eq!(
+      Sexp::new("( a  (MISSING \"; )\") )").normalize(false),
+      "(a (MISSING \"; )\"))"
+    );
+  }
+
+  #[test]
+  fn pretty() {
+    assert_eq!(
+      Sexp::new("(a name: (b) (c))").pretty(false),
+      "(a\n  (b)\n  (c))"
+    );
+
+    assert_eq!(
+      Sexp::new(
+        "(source_file (function_item name: (identifier) \
+         parameters: (parameters) body: (block (MISSING \"}\"))))"
+      )
+      .pretty(true),
+      "(source_file\n  (function_item\n    name: (identifier)\n    \
+       parameters: (parameters)\n    body: (block\n      (MISSING \"}\"))))"
+    );
+  }
+}
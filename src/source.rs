@@ -0,0 +1,225 @@
+use super::*;
+
+/// A source file as raw bytes in its own encoding, which is what trees are
+/// parsed from, so node byte ranges always index into `bytes`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Source {
+  bytes: Vec<u8>,
+  encoding: Encoding,
+}
+
+impl From<&str> for Source {
+  fn from(text: &str) -> Self {
+    Self::new(text.into(), Some(Encoding::Utf8))
+  }
+}
+
+/// Gives query predicates like `#eq?` and `#match?` node text as UTF-8,
+/// whatever the source is encoded in.
+impl<'a> TextProvider<Cow<'a, [u8]>> for &'a Source {
+  type I = iter::Once<Cow<'a, [u8]>>;
+
+  fn text(&mut self, node: Node) -> Self::I {
+    let bytes = self.slice(node.byte_range());
+
+    iter::once(match self.encoding {
+      Encoding::Utf8 => Cow::Borrowed(bytes),
+      encoding => Cow::Owned(encoding.decode(bytes).into_owned().into_bytes()),
+    })
+  }
+}
+
+impl Source {
+  pub(crate) fn bytes(&self) -> &[u8] {
+    &self.bytes
+  }
+
+  /// Returns the column of `byte` in characters rather than bytes, which is
+  /// what editors expect.
+  pub(crate) fn char_column(&self, byte: usize) -> usize {
+    let line_start = byte - self.point(byte).column;
+
+    self.encoding.char_count(self.slice(line_start..byte))
+  }
+
+  pub(crate) fn encoding(&self) -> Encoding {
+    self.encoding
+  }
+
+  /// Returns the text in `range` if it decodes cleanly, for edits, which must
+  /// be able to write back exactly what they replace.
+  pub(crate) fn exact_text(&self, range: Range<usize>) -> Option<String> {
+    self.encoding.decode_exact(self.slice(range))
+  }
+
+  pub(crate) fn len(&self) -> usize {
+    self.bytes.len()
+  }
+
+  /// Wraps `bytes`, detecting their encoding unless one is given.
+  pub(crate) fn new(bytes: Vec<u8>, encoding: Option<Encoding>) -> Self {
+    Self {
+      encoding: encoding.unwrap_or_else(|| Encoding::detect(&bytes)),
+      bytes,
+    }
+  }
+
+  pub(crate) fn parse(
+    &self,
+    parser: &mut Parser,
+    old_tree: Option<&Tree>,
+  ) -> Option<Tree> {
+    self.encoding.parse(parser, &self.bytes, old_tree)
+  }
+
+  /// Returns the position of `byte`, with the column in bytes as tree-sitter
+  /// counts it.
+  pub(crate) fn point(&self, byte: usize) -> Point {
+    let before = self.slice(0..byte);
+
+    let (row, line_start) = self
+      .encoding
+      .line_starts(before)
+      .fold((0, 0), |(row, _), start| (row + 1, start));
+
+    Point {
+      row,
+      column: before.len() - line_start,
+    }
+  }
+
+  /// Re-reads the file from `bytes`, keeping the encoding it was opened with.
+  pub(crate) fn reload(&self, bytes: Vec<u8>) -> Self {
+    Self::new(bytes, Some(self.encoding))
+  }
+
+  /// Replaces `range` with `text` in this source's encoding, returning the
+  /// byte where the new text ends.
+  pub(crate) fn replace(
+    &mut self,
+    range: Range<usize>,
+    text: &str,
+  ) -> Result<usize> {
+    let encoded = self.encoding.encode(text)?;
+
+    let end = range.start + encoded.len();
+
+    self.bytes.splice(range, encoded);
+
+    Ok(end)
+  }
+
+  fn slice(&self, range: Range<usize>) -> &[u8] {
+    self.bytes.get(range).unwrap_or_default()
+  }
+
+  /// Returns the text in `range`, with anything that doesn't decode escaped.
+  pub(crate) fn text(&self, range: Range<usize>) -> Cow<'_, str> {
+    self.encoding.decode(self.slice(range))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn char_column() {
+    #[track_caller]
+    fn case(source: &Source, byte: usize, expected: usize) {
+      assert_eq!(source.char_column(byte), expected);
+    }
+
+    case(&"fn foo() {}".into(), 3, 3);
+    case(&"// café\nlet x = 'é';".into(), 21, 11);
+    case(&"// café\nlet x".into(), 10, 1);
+
+    let source = Source::new(
+      Encoding::Utf16Le.encode("é\n// 😀 x").unwrap(),
+      Some(Encoding::Utf16Le),
+    );
+
+    case(&source, 16, 5);
+
+    let source = Source::new(b"caf\xe9 x".to_vec(), Some(Encoding::Latin1));
+
+    case(&source, 5, 5);
+
+    let source = Source::new(b"caf\xe9 x".to_vec(), Some(Encoding::Utf8));
+
+    case(&source, 5, 5);
+  }
+
+  #[test]
+  fn exact_text() {
+    let source = Source::new(b"caf\xe9 au lait".to_vec(), Some(Encoding::Utf8));
+
+    assert_eq!(source.exact_text(0..5), None);
+    assert_eq!(source.exact_text(5..7), Some("au".into()));
+    assert_eq!(source.text(0..5), "caf\\xe9 ");
+  }
+
+  #[test]
+  fn point() {
+    #[track_caller]
+    fn case(source: &Source, byte: usize, row: usize, column: usize) {
+      assert_eq!(source.point(byte), Point { row, column });
+    }
+
+    case(&"".into(), 0, 0, 0);
+    case(&"foo".into(), 2, 0, 2);
+    case(&"foo\nbar".into(), 4, 1, 0);
+    case(&"foo\nbar".into(), 6, 1, 2);
+    case(&"a\nb\nc".into(), 5, 2, 1);
+
+    let source = Source::new(Encoding::Utf16Le.encode("ab\ncd").unwrap(), None);
+
+    case(&source, 4, 0, 4);
+    case(&source, 6, 1, 0);
+    case(&source, 8, 1, 2);
+  }
+
+  #[test]
+  fn replace() {
+    let mut source =
+      Source::new(Encoding::Utf16Be.encode("fn foo() {}").unwrap(), None);
+
+    assert_eq!(source.replace(6..12, "bär").unwrap(), 12);
+    assert_eq!(source.text(0..source.len()), "fn bär() {}");
+
+    let mut source = Source::new(b"caf\xe9".to_vec(), Some(Encoding::Latin1));
+
+    assert!(source.replace(0..3, "€").is_err());
+    assert_eq!(source.replace(0..3, "th").unwrap(), 2);
+    assert_eq!(source.bytes(), b"th\xe9");
+  }
+
+  #[test]
+  fn text_provider_decodes_to_utf8() {
+    let source = Source::new(
+      Encoding::Utf16Le.encode("fn foo() {} fn bar() {}").unwrap(),
+      None,
+    );
+
+    let language = tree_sitter_rust::LANGUAGE.into();
+
+    let mut parser = Parser::new();
+
+    parser.set_language(&language).unwrap();
+
+    let tree = source.parse(&mut parser, None).unwrap();
+
+    let query =
+      Query::new(&language, r#"((identifier) @id (#eq? @id "bar"))"#).unwrap();
+
+    let mut cursor = QueryCursor::new();
+
+    let mut matches = cursor.matches(&query, tree.root_node(), &source);
+
+    let m = matches.next().unwrap();
+
+    assert_eq!(source.text(m.captures[0].node.byte_range()), "bar");
+
+    assert!(matches.next().is_none());
+  }
+}
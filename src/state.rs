@@ -2,73 +2,98 @@ use super::*;
 
 #[derive(Debug)]
 pub(crate) struct State {
-  pub(crate) collapsed_nodes: HashSet<usize>,
+  anchor: Option<usize>,
+  collapsed_nodes: HashSet<usize>,
   pub(crate) cursor: usize,
-  pub(crate) matches: Vec<usize>,
+  extended: Vec<usize>,
+  pub(crate) horizontal_offset: u16,
+  index: OnceCell<NodeIndex>,
+  pub(crate) matches: NodeSet,
+  queried_viewport: Option<Range<usize>>,
+  pub(crate) query_options: QueryOptions,
+  pub(crate) query_scope: QueryScope,
+  rows: OnceCell<Rows>,
   pub(crate) scroll_offset: u16,
   pub(crate) search_query: String,
-  pub(crate) selected: Option<usize>,
+  pub(crate) selected: NodeSet,
   pub(crate) ts_query: String,
   pub(crate) ts_query_error: Option<String>,
-  pub(crate) ts_query_matches: Vec<usize>,
+  pub(crate) ts_query_matches: NodeSet,
+  pub(crate) ts_query_warning: Option<&'static str>,
+  viewport_rows: usize,
+  zoom: Option<usize>,
 }
 
 impl State {
-  fn calculate_node_position(
-    &self,
-    node: &Node,
-    target_id: usize,
-    position: &mut usize,
-  ) -> bool {
-    if node.id() == target_id {
-      return true;
-    }
-
-    *position += 1;
-
-    if self.collapsed_nodes.contains(&node.id()) {
-      return false;
-    }
+  const HORIZONTAL_SCROLL_STEP: u16 = 4;
+
+  fn clamp_cursor_to_folds(&mut self, tree: &Tree) {
+    let Some(path) = self
+      .node(tree)
+      .ok()
+      .and_then(|node| tree.root_node().path_to(node))
+    else {
+      return;
+    };
 
-    for i in 0..node.child_count_u32() {
-      if let Some(child) = node.child(i) {
-        if self.calculate_node_position(&child, target_id, position) {
-          return true;
-        }
-      }
+    if let Some(ancestor) = path
+      .iter()
+      .take(path.len() - 1)
+      .find(|ancestor| self.collapsed_nodes.contains(&ancestor.id()))
+    {
+      self.cursor = ancestor.id();
     }
-
-    false
   }
 
-  #[allow(clippy::cast_possible_truncation)]
   fn clamp_cursor_to_viewport(&mut self, tree: &Tree, terminal_height: u16) {
-    let mut ids = Vec::new();
-    self.collect_node_ids(&tree.root_node(), &mut ids);
+    let rows = self.rows(tree);
 
-    let display_area = terminal_height.saturating_sub(2) as usize;
-
-    let mut position = 0;
-    self.calculate_node_position(&tree.root_node(), self.cursor, &mut position);
+    let Some(position) = rows.position(self.cursor) else {
+      return;
+    };
 
     let top = self.scroll_offset as usize;
-    let bottom = top + display_area;
+    let bottom = top + Self::display_rows(terminal_height);
 
-    if position < top {
-      if let Some(&id) = ids.get(top) {
-        self.cursor = id;
-      }
+    let row = if position < top {
+      rows.get(top)
     } else if position >= bottom {
-      if let Some(&id) = ids.get(bottom.saturating_sub(1)) {
-        self.cursor = id;
-      }
+      rows.get(bottom.saturating_sub(1))
+    } else {
+      None
+    };
+
+    if let Some(id) = row.map(|row| row.id) {
+      self.cursor = id;
+    }
+  }
+
+  fn clamp_zoom(&mut self, tree: &Tree) {
+    let Some(zoom) = self.zoom.and_then(|id| self.find(tree, id)) else {
+      return;
+    };
+
+    if self
+      .node(tree)
+      .ok()
+      .and_then(|node| zoom.path_to(node))
+      .is_none()
+    {
+      self.set_zoom(None);
     }
   }
 
   pub(crate) fn clear_query(&mut self) {
     self.ts_query.clear();
+    self.clear_query_results();
+  }
+
+  /// Clears the matches and diagnostics of the last query run, leaving the
+  /// query itself.
+  pub(crate) fn clear_query_results(&mut self) {
     self.ts_query_matches.clear();
     self.ts_query_error = None;
+    self.ts_query_warning = None;
   }
 
   pub(crate) fn clear_search(&mut self) {
@@ -76,42 +101,71 @@ impl State {
     self.matches.clear();
   }
 
-  fn collect_matches(
-    node: Node,
-    code: &str,
-    query: &str,
-    matches: &mut Vec<usize>,
-  ) {
-    let kind_matches = node.kind().to_lowercase().contains(query);
+  pub(crate) fn collapse_all(&mut self, tree: &Tree) {
+    self.collapse_where(tree, |_, _| true);
+    self.clamp_cursor_to_folds(tree);
+  }
 
-    let text_matches = node.child_count() == 0
-      && code[node.start_byte()..node.end_byte()]
-        .to_lowercase()
-        .contains(query);
+  pub(crate) fn collapse_kind(&mut self, tree: &Tree) -> Result {
+    let kind = self.node(tree)?.kind_id();
 
-    if kind_matches || text_matches {
-      matches.push(node.id());
-    }
+    self.collapse_where(tree, |node, _| node.kind_id() == kind);
+
+    self.clamp_cursor_to_folds(tree);
+
+    Ok(())
+  }
+
+  pub(crate) fn collapse_siblings(&mut self, tree: &Tree) -> Result {
+    let current = self.node(tree)?;
 
-    for i in 0..node.child_count_u32() {
-      if let Some(child) = node.child(i) {
-        Self::collect_matches(child, code, query, matches);
+    let Some(parent) = current.parent() else {
+      return Ok(());
+    };
+
+    for i in 0..parent.child_count_u32() {
+      if let Some(sibling) = parent.child(i) {
+        if sibling.id() != current.id() && sibling.child_count() > 0 {
+          self.collapsed_nodes.insert(sibling.id());
+        }
       }
     }
+
+    self.invalidate_rows();
+
+    Ok(())
   }
 
-  fn collect_node_ids(&self, node: &Node, ids: &mut Vec<usize>) {
-    ids.push(node.id());
+  pub(crate) fn collapse_to_depth(&mut self, tree: &Tree, depth: usize) {
+    self.expand_all();
+    self.collapse_where(tree, |_, d| d == depth);
+    self.clamp_cursor_to_folds(tree);
+  }
 
-    if self.collapsed_nodes.contains(&node.id()) {
-      return;
-    }
+  fn collapse_where(
+    &mut self,
+    tree: &Tree,
+    predicate: impl Fn(&Node, usize) -> bool,
+  ) {
+    let collapsed = &mut self.collapsed_nodes;
 
-    for i in 0..node.child_count_u32() {
-      if let Some(child) = node.child(i) {
-        self.collect_node_ids(&child, ids);
+    tree.root_node().preorder(|node, depth| {
+      if node.child_count() > 0 && predicate(&node, depth) {
+        collapsed.insert(node.id());
       }
-    }
+
+      true
+    });
+
+    self.invalidate_rows();
+  }
+
+  pub(crate) fn collapsed_nodes(&self) -> &HashSet<usize> {
+    &self.collapsed_nodes
+  }
+
+  fn display_rows(terminal_height: u16) -> usize {
+    terminal_height.saturating_sub(3) as usize
   }
 
   #[allow(clippy::cast_possible_truncation)]
@@ -120,13 +174,15 @@ impl State {
     tree: &Tree,
     terminal_height: u16,
   ) {
-    let mut position = 0;
+    self.clamp_zoom(tree);
 
-    let root = tree.root_node();
+    let display_area = Self::display_rows(terminal_height);
 
-    self.calculate_node_position(&root, self.cursor, &mut position);
+    self.viewport_rows = display_area;
 
-    let display_area = terminal_height.saturating_sub(2) as usize;
+    let Some(position) = self.rows(tree).position(self.cursor) else {
+      return;
+    };
 
     if position < self.scroll_offset as usize {
       self.scroll_offset = position as u16;
@@ -139,10 +195,9 @@ impl State {
     &mut self,
     language: &TreeSitterLanguage,
     tree: &Tree,
-    code: &str,
+    source: &Source,
   ) {
-    self.ts_query_matches.clear();
-    self.ts_query_error = None;
+    self.clear_query_results();
 
     if self.ts_query.is_empty() {
       return;
@@ -150,17 +205,32 @@ impl State {
 
     match Query::new(language, &self.ts_query) {
       Ok(query) => {
-        let mut cursor = QueryCursor::new();
-        let mut matches =
-          cursor.matches(&query, tree.root_node(), code.as_bytes());
+        let mut options = self.query_options.clone();
 
-        while let Some(m) = matches.next() {
-          for capture in m.captures {
-            self.ts_query_matches.push(capture.node.id());
+        let node = match self.query_scope {
+          QueryScope::Subtree(id) => {
+            self.find(tree, id).unwrap_or_else(|| tree.root_node())
           }
-        }
+          QueryScope::Tree => tree.root_node(),
+          QueryScope::Viewport => {
+            self.queried_viewport = self.viewport_range(tree);
 
-        self.ts_query_matches.dedup();
+            if let Some(range) = self.queried_viewport.clone() {
+              options.range = Some(QueryRange::Bytes(range));
+            }
+
+            tree.root_node()
+          }
+        };
+
+        let mut matches = NodeSet::default();
+
+        let outcome = options.execute(&query, node, source, |m| {
+          matches.extend(m.captures.iter().map(|capture| capture.node.id()));
+        });
+
+        self.ts_query_matches = matches;
+        self.ts_query_warning = outcome.warning();
 
         if let Some(&first) = self.ts_query_matches.first() {
           self.cursor = first;
@@ -172,22 +242,126 @@ impl State {
     }
   }
 
-  fn find_node(id: usize, node: Node<'_>) -> Option<Node<'_>> {
-    if node.id() == id {
-      return Some(node);
+  pub(crate) fn expand_all(&mut self) {
+    self.collapsed_nodes.clear();
+    self.invalidate_rows();
+  }
+
+  /// Moves the cursor to the next or previous visible row, selecting every
+  /// row from the anchor to the cursor, so reversing shrinks the selection.
+  pub(crate) fn extend_selection(&mut self, tree: &Tree, forward: bool) {
+    self.start_extension();
+
+    let rows = self.rows(tree);
+
+    let (Some(anchor), Some(cursor)) = (
+      self.anchor.and_then(|anchor| rows.position(anchor)),
+      rows.position(self.cursor),
+    ) else {
+      return;
+    };
+
+    let Some(next) = (if forward {
+      cursor.checked_add(1).filter(|&next| next < rows.len())
+    } else {
+      cursor.checked_sub(1)
+    }) else {
+      return;
+    };
+
+    let range = (anchor.min(next)..=anchor.max(next))
+      .filter_map(|position| rows.get(position).map(|row| row.id))
+      .collect::<Vec<_>>();
+
+    if let Some(row) = rows.get(next) {
+      self.cursor = row.id;
     }
 
-    (0..node.child_count_u32())
-      .filter_map(|i| node.child(i))
-      .find_map(|child| Self::find_node(id, child))
+    self.select_range(range);
+  }
+
+  /// Like `extend_selection`, but moves between and selects the siblings of
+  /// the anchor.
+  pub(crate) fn extend_siblings(
+    &mut self,
+    tree: &Tree,
+    forward: bool,
+  ) -> Result {
+    self.start_extension();
+
+    let current = self.node(tree)?;
+
+    let Some(parent) = current.parent() else {
+      return Ok(());
+    };
+
+    let siblings = parent
+      .children(&mut parent.walk())
+      .map(|node| node.id())
+      .collect::<Vec<_>>();
+
+    let Some(cursor) = siblings.iter().position(|&id| id == current.id())
+    else {
+      return Ok(());
+    };
+
+    let anchor = if let Some(anchor) = self
+      .anchor
+      .and_then(|anchor| siblings.iter().position(|&id| id == anchor))
+    {
+      anchor
+    } else {
+      self.anchor = Some(current.id());
+      self.extended.clear();
+      cursor
+    };
+
+    let Some(next) = (if forward {
+      cursor.checked_add(1).filter(|&next| next < siblings.len())
+    } else {
+      cursor.checked_sub(1)
+    }) else {
+      return Ok(());
+    };
+
+    self.cursor = siblings[next];
+
+    self.select_range(siblings[anchor.min(next)..=anchor.max(next)].to_vec());
+
+    Ok(())
+  }
+
+  pub(crate) fn find<'a>(&self, tree: &'a Tree, id: usize) -> Option<Node<'a>> {
+    self
+      .index
+      .get_or_init(|| NodeIndex::new(tree))
+      .node(tree, id)
   }
 
-  fn jump_in(&mut self, matches: &[usize], forward: bool) {
+  pub(crate) fn focus(&mut self, tree: &Tree) -> Result {
+    let current = self.node(tree)?;
+
+    self.collapse_where(tree, |_, _| true);
+
+    for ancestor in tree.root_node().path_to(current).unwrap_or_default() {
+      self.collapsed_nodes.remove(&ancestor.id());
+    }
+
+    self.invalidate_rows();
+
+    Ok(())
+  }
+
+  fn invalidate_rows(&mut self) {
+    self.rows.take();
+  }
+
+  fn jump_in(&mut self, matches: &NodeSet, forward: bool) {
     if matches.is_empty() {
       return;
     }
 
-    let current = matches.iter().position(|&id| id == self.cursor);
+    let current = matches.position(self.cursor);
 
     let index = if forward {
       match current {
@@ -231,6 +405,10 @@ impl State {
   pub(crate) fn move_left(&mut self, tree: &Tree) -> Result {
     let current = self.node(tree)?;
 
+    if current.id() == self.root(tree).id() {
+      return Ok(());
+    }
+
     if let Some(prev) = current.prev_sibling() {
       self.cursor = prev.id();
     } else if let Some(parent) = current.parent() {
@@ -243,6 +421,10 @@ impl State {
   pub(crate) fn move_right(&mut self, tree: &Tree) -> Result {
     let current = self.node(tree)?;
 
+    if current.id() == self.root(tree).id() {
+      return Ok(());
+    }
+
     if let Some(next) = current.next_sibling() {
       self.cursor = next.id();
     }
@@ -251,21 +433,22 @@ impl State {
   }
 
   pub(crate) fn move_to_bottom(&mut self, tree: &Tree) {
-    let mut ids = Vec::new();
-    self.collect_node_ids(&tree.root_node(), &mut ids);
-
-    if let Some(&id) = ids.last() {
+    if let Some(id) = self.rows(tree).last().map(|row| row.id) {
       self.cursor = id;
     }
   }
 
   pub(crate) fn move_to_top(&mut self, tree: &Tree) {
-    self.cursor = tree.root_node().id();
+    self.cursor = self.root(tree).id();
   }
 
   pub(crate) fn move_up(&mut self, tree: &Tree) -> Result {
     let current = self.node(tree)?;
 
+    if current.id() == self.root(tree).id() {
+      return Ok(());
+    }
+
     if let Some(parent) = current.parent() {
       self.cursor = parent.id();
     }
@@ -275,45 +458,92 @@ impl State {
 
   pub(crate) fn new(cursor: usize) -> Self {
     Self {
+      anchor: None,
       collapsed_nodes: HashSet::new(),
       cursor,
-      matches: Vec::new(),
+      extended: Vec::new(),
+      horizontal_offset: 0,
+      index: OnceCell::new(),
+      matches: NodeSet::default(),
+      queried_viewport: None,
+      query_options: QueryOptions::default(),
+      query_scope: QueryScope::default(),
+      rows: OnceCell::new(),
       scroll_offset: 0,
       search_query: String::new(),
-      selected: None,
+      selected: NodeSet::default(),
       ts_query: String::new(),
       ts_query_error: None,
-      ts_query_matches: Vec::new(),
+      ts_query_matches: NodeSet::default(),
+      ts_query_warning: None,
+      viewport_rows: 0,
+      zoom: None,
     }
   }
 
   pub(crate) fn node<'a>(&self, tree: &'a Tree) -> Result<Node<'a>> {
-    Self::find_node(self.cursor, tree.root_node())
+    self
+      .find(tree, self.cursor)
       .ok_or_else(|| anyhow!("cursor node `{}` not found in tree", self.cursor))
   }
 
   pub(crate) fn node_at_row(&self, tree: &Tree, row: u16) -> Option<usize> {
     let index = self.scroll_offset as usize + row as usize;
-    let mut ids = Vec::new();
-    self.collect_node_ids(&tree.root_node(), &mut ids);
-    ids.get(index).copied()
+    self.rows(tree).get(index).map(|row| row.id)
   }
 
   pub(crate) fn reconcile(&mut self, cursor: usize) {
     self.cursor = cursor;
     self.collapsed_nodes.clear();
-    self.selected = None;
+    self.selected.clear();
+    self.anchor = None;
+    self.extended.clear();
     self.scroll_offset = 0;
     self.matches.clear();
     self.ts_query_matches.clear();
+    self.zoom = None;
+    self.index.take();
+    self.invalidate_rows();
+  }
+
+  /// Re-runs a query scoped to the viewport once the viewport has moved off
+  /// the rows it last ran over, leaving the cursor where it is.
+  pub(crate) fn refresh_viewport_query(
+    &mut self,
+    language: &TreeSitterLanguage,
+    tree: &Tree,
+    source: &Source,
+  ) {
+    if self.query_scope != QueryScope::Viewport
+      || self.ts_query.is_empty()
+      || self.viewport_range(tree) == self.queried_viewport
+    {
+      return;
+    }
+
+    let cursor = self.cursor;
+
+    self.execute_query(language, tree, source);
+
+    self.cursor = cursor;
+  }
+
+  pub(crate) fn root<'a>(&self, tree: &'a Tree) -> Node<'a> {
+    self
+      .zoom
+      .and_then(|id| self.find(tree, id))
+      .unwrap_or_else(|| tree.root_node())
+  }
+
+  pub(crate) fn rows(&self, tree: &Tree) -> &Rows {
+    self
+      .rows
+      .get_or_init(|| Rows::new(self.root(tree), &self.collapsed_nodes))
   }
 
   #[allow(clippy::cast_possible_truncation)]
   pub(crate) fn scroll_down(&mut self, tree: &Tree, terminal_height: u16) {
-    let mut ids = Vec::new();
-    self.collect_node_ids(&tree.root_node(), &mut ids);
-
-    let max_offset = ids.len().saturating_sub(1);
+    let max_offset = self.rows(tree).len().saturating_sub(1);
 
     self.scroll_offset =
       ((self.scroll_offset as usize + 1).min(max_offset)) as u16;
@@ -321,18 +551,47 @@ impl State {
     self.clamp_cursor_to_viewport(tree, terminal_height);
   }
 
+  pub(crate) fn scroll_left(&mut self) {
+    self.horizontal_offset = self
+      .horizontal_offset
+      .saturating_sub(Self::HORIZONTAL_SCROLL_STEP);
+  }
+
+  /// Scrolls right, but not past `max_offset` unless already there.
+  pub(crate) fn scroll_right(&mut self, max_offset: u16) {
+    self.horizontal_offset = self
+      .horizontal_offset
+      .saturating_add(Self::HORIZONTAL_SCROLL_STEP)
+      .min(max_offset.max(self.horizontal_offset));
+  }
+
   pub(crate) fn scroll_up(&mut self, tree: &Tree, terminal_height: u16) {
     self.scroll_offset = self.scroll_offset.saturating_sub(1);
 
     self.clamp_cursor_to_viewport(tree, terminal_height);
   }
 
-  pub(crate) fn search(&mut self, tree: &Tree, code: &str) {
+  pub(crate) fn search(&mut self, tree: &Tree, source: &Source) {
     self.matches.clear();
 
     if !self.search_query.is_empty() {
       let query = self.search_query.to_lowercase();
-      Self::collect_matches(tree.root_node(), code, &query, &mut self.matches);
+
+      tree.root_node().preorder(|node, _| {
+        let kind_matches = node.kind().to_lowercase().contains(&query);
+
+        let text_matches = node.child_count() == 0
+          && source
+            .text(node.byte_range())
+            .to_lowercase()
+            .contains(&query);
+
+        if kind_matches || text_matches {
+          self.matches.insert(node.id());
+        }
+
+        true
+      });
     }
 
     if let Some(&first) = self.matches.first() {
@@ -340,6 +599,47 @@ impl State {
     }
   }
 
+  /// Replaces the nodes selected by the current extension with `range`,
+  /// keeping any selected by other means.
+  fn select_range(&mut self, range: Vec<usize>) {
+    self.selected.retain(|id| !self.extended.contains(&id));
+
+    self.selected.extend(range.iter().copied());
+
+    self.extended = range;
+  }
+
+  pub(crate) fn selected_nodes<'a>(&self, tree: &'a Tree) -> Vec<Node<'a>> {
+    let mut nodes = self
+      .selected
+      .iter()
+      .filter_map(|&id| self.find(tree, id))
+      .collect::<Vec<_>>();
+
+    nodes.sort_by_key(|node| (node.start_byte(), Reverse(node.end_byte())));
+
+    nodes
+  }
+
+  pub(crate) fn set_collapsed_nodes(&mut self, collapsed: HashSet<usize>) {
+    self.collapsed_nodes = collapsed;
+    self.invalidate_rows();
+  }
+
+  pub(crate) fn set_zoom(&mut self, zoom: Option<usize>) {
+    self.zoom = zoom;
+    self.invalidate_rows();
+  }
+
+  /// Anchors a new extension at the cursor unless the cursor is still at the
+  /// end of the last one.
+  fn start_extension(&mut self) {
+    if self.anchor.is_none() || !self.extended.contains(&self.cursor) {
+      self.anchor = Some(self.cursor);
+      self.extended.clear();
+    }
+  }
+
   pub(crate) fn toggle_collapse(&mut self, tree: &Tree) -> Result {
     let current = self.node(tree)?;
 
@@ -351,22 +651,257 @@ impl State {
       self.collapsed_nodes.insert(id);
     }
 
+    self.invalidate_rows();
+
     Ok(())
   }
 
   pub(crate) fn toggle_select(&mut self) {
-    if self.selected == Some(self.cursor) {
-      self.selected = None;
-    } else {
-      self.selected = Some(self.cursor);
+    if !self.selected.remove(self.cursor) {
+      self.selected.insert(self.cursor);
     }
   }
+
+  /// Returns the bytes covered by the rows visible at the last draw, from the
+  /// start of the first to the end of the last.
+  fn viewport_range(&self, tree: &Tree) -> Option<Range<usize>> {
+    if self.viewport_rows == 0 {
+      return None;
+    }
+
+    let rows = self.rows(tree);
+
+    let first = rows.get(self.scroll_offset as usize)?;
+
+    let last = rows
+      .get(self.scroll_offset as usize + self.viewport_rows - 1)
+      .or_else(|| rows.last())?;
+
+    let (first, last) = (self.find(tree, first.id)?, self.find(tree, last.id)?);
+
+    Some(first.start_byte()..last.end_byte().max(first.start_byte()))
+  }
+
+  pub(crate) fn zoom(&self) -> Option<usize> {
+    self.zoom
+  }
+
+  pub(crate) fn zoom_in(&mut self, tree: &Tree) -> Result {
+    let current = self.node(tree)?;
+
+    self.set_zoom(current.parent().is_some().then_some(current.id()));
+    self.scroll_offset = 0;
+
+    Ok(())
+  }
+
+  pub(crate) fn zoom_out(&mut self, tree: &Tree) {
+    self.set_zoom(
+      self
+        .root(tree)
+        .parent()
+        .filter(|parent| parent.parent().is_some())
+        .map(|parent| parent.id()),
+    );
+
+    self.scroll_offset = 0;
+  }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  #[test]
+  fn collapse_all_moves_cursor_to_visible_ancestor() {
+    let tree = parse("fn foo() {} fn bar() {}");
+    let root = tree.root_node();
+
+    let identifier = root.child(1).unwrap().child(1).unwrap();
+
+    let mut state = State::new(identifier.id());
+
+    state.collapse_all(&tree);
+
+    assert!(state.collapsed_nodes.contains(&root.id()));
+    assert!(state.collapsed_nodes.contains(&root.child(0).unwrap().id()));
+    assert_eq!(state.cursor, root.id());
+  }
+
+  #[test]
+  fn collapse_kind() {
+    let tree = parse("fn foo() {} fn bar() { 1; }");
+    let root = tree.root_node();
+
+    let mut state = State::new(root.child(0).unwrap().id());
+
+    state.collapse_kind(&tree).unwrap();
+
+    assert_eq!(
+      state.collapsed_nodes,
+      HashSet::from([root.child(0).unwrap().id(), root.child(1).unwrap().id()])
+    );
+  }
+
+  #[test]
+  fn collapse_siblings() {
+    let tree = parse("fn foo() {} fn bar() {} fn baz() {}");
+    let root = tree.root_node();
+
+    let mut state = State::new(root.child(1).unwrap().id());
+
+    state.collapse_siblings(&tree).unwrap();
+
+    assert_eq!(
+      state.collapsed_nodes,
+      HashSet::from([root.child(0).unwrap().id(), root.child(2).unwrap().id()])
+    );
+  }
+
+  #[test]
+  fn collapse_to_depth() {
+    let tree = parse("fn foo() {} fn bar() {}");
+    let root = tree.root_node();
+
+    let mut state = State::new(root.id());
+    state.collapsed_nodes.insert(root.id());
+
+    state.collapse_to_depth(&tree, 1);
+
+    assert_eq!(
+      state.collapsed_nodes,
+      HashSet::from([root.child(0).unwrap().id(), root.child(1).unwrap().id()])
+    );
+
+    assert_eq!(state.rows(&tree).len(), 3);
+  }
+
+  #[test]
+  fn deeply_nested_input() {
+    const DEPTH: usize = 100_000;
+
+    let (code, tree) = deeply_nested(DEPTH);
+    let root = tree.root_node();
+
+    let mut state = State::new(root.id());
+
+    state.cursor = root.find_at_byte(DEPTH).unwrap();
+    assert_eq!(state.node(&tree).unwrap().kind(), "]");
+
+    state.search_query = "]".into();
+    state.search(&tree, &code.as_str().into());
+    assert_eq!(state.matches.len(), DEPTH);
+
+    let session = Session::capture(&state, &tree);
+    assert!(session.cursor.is_some());
+
+    state.collapse_all(&tree);
+    assert_eq!(state.cursor, root.id());
+
+    state.expand_all();
+    state.move_to_bottom(&tree);
+    assert_eq!(state.rows(&tree).len(), root.descendant_count());
+    assert_eq!(state.node(&tree).unwrap().end_byte(), code.len());
+  }
+
+  #[test]
+  fn expand_all() {
+    let tree = parse("fn foo() {}");
+
+    let mut state = State::new(tree.root_node().id());
+
+    state.collapse_all(&tree);
+    assert!(!state.collapsed_nodes.is_empty());
+
+    state.expand_all();
+    assert!(state.collapsed_nodes.is_empty());
+  }
+
+  #[test]
+  fn extend_selection() {
+    let tree = parse("fn a() {} fn b() {} fn c() {}");
+    let root = tree.root_node();
+
+    let functions = (0..3)
+      .map(|i| root.child(i).unwrap().id())
+      .collect::<Vec<_>>();
+
+    let mut state = State::new(functions[0]);
+
+    state.collapse_to_depth(&tree, 1);
+
+    state.extend_selection(&tree, true);
+    state.extend_selection(&tree, true);
+
+    assert_eq!(state.selected, functions);
+    assert_eq!(state.cursor, functions[2]);
+
+    state.extend_selection(&tree, false);
+
+    assert_eq!(state.selected, &functions[..2]);
+    assert_eq!(state.cursor, functions[1]);
+
+    state.extend_selection(&tree, false);
+    state.extend_selection(&tree, false);
+
+    assert_eq!(state.selected, [root.id(), functions[0]]);
+    assert_eq!(state.cursor, root.id());
+  }
+
+  #[test]
+  fn extend_siblings() {
+    let tree = parse("fn a() {} fn b() {} fn c() {}");
+    let root = tree.root_node();
+
+    let functions = (0..3)
+      .map(|i| root.child(i).unwrap().id())
+      .collect::<Vec<_>>();
+
+    let mut state = State::new(functions[1]);
+
+    state.extend_siblings(&tree, false).unwrap();
+
+    assert_eq!(state.selected, &functions[..2]);
+
+    state.extend_siblings(&tree, true).unwrap();
+    state.extend_siblings(&tree, true).unwrap();
+
+    assert_eq!(state.selected, &functions[1..]);
+    assert_eq!(state.cursor, functions[2]);
+
+    state.extend_siblings(&tree, true).unwrap();
+
+    assert_eq!(state.cursor, functions[2]);
+
+    state.cursor = functions[0];
+    state.toggle_select();
+    state.extend_siblings(&tree, true).unwrap();
+
+    assert_eq!(state.selected, [functions[1], functions[2], functions[0]]);
+  }
+
+  #[test]
+  fn focus_keeps_path_to_cursor_open() {
+    let tree = parse("fn foo() {} fn bar() {}");
+    let root = tree.root_node();
+
+    let second = root.child(1).unwrap();
+    let parameters = second.child(2).unwrap();
+
+    let mut state = State::new(parameters.id());
+
+    state.focus(&tree).unwrap();
+
+    assert!(!state.collapsed_nodes.contains(&root.id()));
+    assert!(!state.collapsed_nodes.contains(&second.id()));
+    assert!(!state.collapsed_nodes.contains(&parameters.id()));
+    assert!(state.collapsed_nodes.contains(&root.child(0).unwrap().id()));
+    assert!(state
+      .collapsed_nodes
+      .contains(&second.child(3).unwrap().id()));
+    assert_eq!(state.cursor, parameters.id());
+  }
+
   fn language() -> TreeSitterLanguage {
     tree_sitter_rust::LANGUAGE.into()
   }
@@ -452,9 +987,7 @@ mod tests {
 
     state.move_to_bottom(&tree);
 
-    let mut ids = Vec::new();
-    state.collect_node_ids(&root, &mut ids);
-    assert_eq!(state.cursor, *ids.last().unwrap());
+    assert_eq!(state.cursor, state.rows(&tree).last().unwrap().id);
   }
 
   #[test]
@@ -542,14 +1075,6 @@ mod tests {
     assert!(state.node(&tree).is_err());
   }
 
-  fn parse(code: &str) -> Tree {
-    let mut parser = Parser::new();
-
-    parser.set_language(&language()).unwrap();
-
-    parser.parse(code, None).unwrap()
-  }
-
   #[test]
   fn scroll() {
     let tree = parse("fn foo() {}");
@@ -565,6 +1090,30 @@ mod tests {
     assert_eq!(state.scroll_offset, 0);
   }
 
+  #[test]
+  fn scroll_horizontally() {
+    let tree = parse("fn foo() {}");
+    let mut state = State::new(tree.root_node().id());
+
+    state.scroll_right(10);
+    state.scroll_right(10);
+    assert_eq!(state.horizontal_offset, 8);
+
+    state.scroll_right(10);
+    state.scroll_right(10);
+    assert_eq!(state.horizontal_offset, 10);
+
+    state.scroll_right(6);
+    assert_eq!(state.horizontal_offset, 10);
+
+    state.scroll_left();
+    assert_eq!(state.horizontal_offset, 6);
+
+    state.scroll_left();
+    state.scroll_left();
+    assert_eq!(state.horizontal_offset, 0);
+  }
+
   #[test]
   fn search_by_kind() {
     let code = "fn foo() {} fn bar() {}";
@@ -573,7 +1122,7 @@ mod tests {
     let mut state = State::new(tree.root_node().id());
 
     state.search_query = "identifier".to_string();
-    state.search(&tree, code);
+    state.search(&tree, &code.into());
 
     assert_eq!(state.matches.len(), 2);
     assert_eq!(state.node(&tree).unwrap().kind(), "identifier");
@@ -587,7 +1136,7 @@ mod tests {
     let mut state = State::new(tree.root_node().id());
 
     state.search_query = "bar".to_string();
-    state.search(&tree, code);
+    state.search(&tree, &code.into());
 
     assert_eq!(state.matches.len(), 1);
     assert_eq!(
@@ -605,7 +1154,7 @@ mod tests {
     let mut state = State::new(tree.root_node().id());
 
     state.search_query = "foo".to_string();
-    state.search(&tree, code);
+    state.search(&tree, &code.into());
 
     assert_eq!(state.matches.len(), 1);
   }
@@ -618,7 +1167,7 @@ mod tests {
     let mut state = State::new(tree.root_node().id());
     let cursor_before = state.cursor;
 
-    state.search(&tree, code);
+    state.search(&tree, &code.into());
 
     assert!(state.matches.is_empty());
     assert_eq!(state.cursor, cursor_before);
@@ -632,7 +1181,7 @@ mod tests {
     let mut state = State::new(tree.root_node().id());
 
     state.search_query = "identifier".to_string();
-    state.search(&tree, code);
+    state.search(&tree, &code.into());
 
     let first = state.cursor;
 
@@ -653,12 +1202,40 @@ mod tests {
     let cursor_before = state.cursor;
 
     state.search_query = "zzz".to_string();
-    state.search(&tree, code);
+    state.search(&tree, &code.into());
 
     assert!(state.matches.is_empty());
     assert_eq!(state.cursor, cursor_before);
   }
 
+  #[test]
+  fn selected_nodes_in_document_order() {
+    let tree = parse("fn foo() {} fn bar() {}");
+    let root = tree.root_node();
+
+    let mut state = State::new(root.id());
+
+    state.selected = vec![
+      root.child(1).unwrap().id(),
+      root.child(0).unwrap().id(),
+      root.id(),
+    ]
+    .into();
+
+    assert_eq!(
+      state
+        .selected_nodes(&tree)
+        .iter()
+        .map(Node::id)
+        .collect::<Vec<_>>(),
+      vec![
+        root.id(),
+        root.child(0).unwrap().id(),
+        root.child(1).unwrap().id()
+      ]
+    );
+  }
+
   #[test]
   fn toggle_collapse() {
     let tree = parse("fn foo() {}");
@@ -697,10 +1274,17 @@ mod tests {
     let mut state = State::new(root.id());
 
     state.toggle_select();
-    assert_eq!(state.selected, Some(root.id()));
+    assert_eq!(state.selected, vec![root.id()]);
+
+    state.cursor = root.child(0).unwrap().id();
 
     state.toggle_select();
-    assert_eq!(state.selected, None);
+    assert_eq!(state.selected, vec![root.id(), root.child(0).unwrap().id()]);
+
+    state.cursor = root.id();
+
+    state.toggle_select();
+    assert_eq!(state.selected, vec![root.child(0).unwrap().id()]);
   }
 
   #[test]
@@ -712,7 +1296,7 @@ mod tests {
     let mut state = State::new(tree.root_node().id());
 
     state.ts_query = "(identifier) @name".to_string();
-    state.execute_query(&lang, &tree, code);
+    state.execute_query(&lang, &tree, &code.into());
     assert!(!state.ts_query_matches.is_empty());
 
     state.clear_query();
@@ -731,7 +1315,7 @@ mod tests {
     let mut state = State::new(tree.root_node().id());
     let cursor_before = state.cursor;
 
-    state.execute_query(&lang, &tree, code);
+    state.execute_query(&lang, &tree, &code.into());
 
     assert!(state.ts_query_matches.is_empty());
     assert!(state.ts_query_error.is_none());
@@ -747,7 +1331,7 @@ mod tests {
     let mut state = State::new(tree.root_node().id());
 
     state.ts_query = "(not_a_real_node)".to_string();
-    state.execute_query(&lang, &tree, code);
+    state.execute_query(&lang, &tree, &code.into());
 
     assert!(state.ts_query_matches.is_empty());
     assert!(state.ts_query_error.is_some());
@@ -762,7 +1346,7 @@ mod tests {
     let mut state = State::new(tree.root_node().id());
 
     state.ts_query = "(identifier) @name".to_string();
-    state.execute_query(&lang, &tree, code);
+    state.execute_query(&lang, &tree, &code.into());
 
     let first = state.cursor;
 
@@ -774,6 +1358,103 @@ mod tests {
     assert_eq!(state.cursor, first);
   }
 
+  #[test]
+  fn ts_query_limited_to_subtree() {
+    let code = "fn foo() { bar(); }\nfn baz() {}";
+
+    let tree = parse(code);
+
+    let mut state = State::new(tree.root_node().id());
+
+    state.query_scope =
+      QueryScope::Subtree(tree.root_node().child(0).unwrap().id());
+
+    state.ts_query = "(identifier) @id".into();
+
+    state.execute_query(&language(), &tree, &code.into());
+
+    assert_eq!(
+      state
+        .ts_query_matches
+        .iter()
+        .map(|&id| {
+          let node = state.find(&tree, id).unwrap();
+          &code[node.byte_range()]
+        })
+        .collect::<Vec<_>>(),
+      ["foo", "bar"]
+    );
+  }
+
+  #[test]
+  fn ts_query_limited_to_viewport() {
+    let code = "fn foo() {}\nfn bar() {}\nfn baz() {}";
+
+    let tree = parse(code);
+
+    let mut state = State::new(tree.root_node().id());
+
+    state.ensure_cursor_in_view(&tree, 8);
+
+    state.scroll_offset = 10;
+    state.query_scope = QueryScope::Viewport;
+    state.ts_query = "(identifier) @id".into();
+
+    state.execute_query(&language(), &tree, &code.into());
+
+    assert_eq!(
+      state
+        .ts_query_matches
+        .iter()
+        .map(|&id| {
+          let node = state.find(&tree, id).unwrap();
+          &code[node.byte_range()]
+        })
+        .collect::<Vec<_>>(),
+      ["bar"]
+    );
+
+    let cursor = state.cursor;
+
+    state.scroll_offset = 15;
+
+    state.refresh_viewport_query(&language(), &tree, &code.into());
+
+    assert_eq!(
+      state
+        .ts_query_matches
+        .iter()
+        .map(|&id| &code[state.find(&tree, id).unwrap().byte_range()])
+        .collect::<Vec<_>>(),
+      ["baz"]
+    );
+
+    assert_eq!(state.cursor, cursor);
+  }
+
+  #[test]
+  fn ts_query_matches_are_unique() {
+    let code = "fn foo() {} fn bar() {}";
+    let tree = parse(code);
+    let lang = language();
+
+    let mut state = State::new(tree.root_node().id());
+
+    state.ts_query =
+      "(function_item name: (identifier) @name body: (block) @body)\n\
+       (identifier) @id"
+        .to_string();
+    state.execute_query(&lang, &tree, &code.into());
+
+    let kinds = state
+      .ts_query_matches
+      .iter()
+      .map(|&id| state.find(&tree, id).unwrap().kind())
+      .collect::<Vec<_>>();
+
+    assert_eq!(kinds, ["identifier", "block", "identifier", "block"]);
+  }
+
   #[test]
   fn ts_query_matches_captures() {
     let code = "fn foo() {} fn bar() {}";
@@ -783,7 +1464,7 @@ mod tests {
     let mut state = State::new(tree.root_node().id());
 
     state.ts_query = "(identifier) @name".to_string();
-    state.execute_query(&lang, &tree, code);
+    state.execute_query(&lang, &tree, &code.into());
 
     assert_eq!(state.ts_query_matches.len(), 2);
     assert!(state.ts_query_error.is_none());
@@ -799,7 +1480,7 @@ mod tests {
     let mut state = State::new(tree.root_node().id());
 
     state.ts_query = "(identifier) @id (integer_literal) @num".to_string();
-    state.execute_query(&lang, &tree, code);
+    state.execute_query(&lang, &tree, &code.into());
 
     assert!(state.ts_query_matches.len() >= 3);
     assert!(state.ts_query_error.is_none());
@@ -815,7 +1496,7 @@ mod tests {
     let cursor_before = state.cursor;
 
     state.ts_query = "(struct_item) @s".to_string();
-    state.execute_query(&lang, &tree, code);
+    state.execute_query(&lang, &tree, &code.into());
 
     assert!(state.ts_query_matches.is_empty());
     assert!(state.ts_query_error.is_none());
@@ -831,9 +1512,117 @@ mod tests {
     let mut state = State::new(tree.root_node().id());
 
     state.ts_query = "(((".to_string();
-    state.execute_query(&lang, &tree, code);
+    state.execute_query(&lang, &tree, &code.into());
 
     assert!(state.ts_query_matches.is_empty());
     assert!(state.ts_query_error.is_some());
   }
+
+  #[test]
+  fn zoom_clears_when_cursor_leaves_subtree() {
+    let tree = parse("fn foo() {} fn bar() {}");
+    let root = tree.root_node();
+
+    let mut state = State::new(root.child(0).unwrap().id());
+
+    state.zoom_in(&tree).unwrap();
+
+    state.cursor = root.child(1).unwrap().id();
+
+    state.ensure_cursor_in_view(&tree, 50);
+
+    assert_eq!(state.zoom, None);
+  }
+
+  #[test]
+  fn zoom_in_at_root_is_noop() {
+    let tree = parse("fn foo() {}");
+
+    let mut state = State::new(tree.root_node().id());
+
+    state.zoom_in(&tree).unwrap();
+
+    assert_eq!(state.zoom, None);
+  }
+
+  #[test]
+  fn zoom_in_restricts_movement() {
+    let tree = parse("fn foo() {} fn bar() {}");
+    let root = tree.root_node();
+
+    let first = root.child(0).unwrap();
+
+    let mut state = State::new(first.id());
+
+    state.zoom_in(&tree).unwrap();
+
+    assert_eq!(state.root(&tree).id(), first.id());
+
+    state.move_up(&tree).unwrap();
+    assert_eq!(state.cursor, first.id());
+
+    state.move_right(&tree).unwrap();
+    assert_eq!(state.cursor, first.id());
+
+    state.move_left(&tree).unwrap();
+    assert_eq!(state.cursor, first.id());
+  }
+
+  #[test]
+  fn zoom_out_one_level() {
+    let tree = parse("fn foo() {}");
+    let root = tree.root_node();
+
+    let function = root.child(0).unwrap();
+    let block = function.child(3).unwrap();
+
+    let mut state = State::new(block.id());
+
+    state.zoom_in(&tree).unwrap();
+    assert_eq!(state.zoom, Some(block.id()));
+
+    state.zoom_out(&tree);
+    assert_eq!(state.zoom, Some(function.id()));
+
+    state.zoom_out(&tree);
+    assert_eq!(state.zoom, None);
+  }
+
+  #[test]
+  fn zoomed_node_at_row() {
+    let tree = parse("fn foo() {}");
+    let root = tree.root_node();
+
+    let function = root.child(0).unwrap();
+
+    let mut state = State::new(function.id());
+
+    state.zoom_in(&tree).unwrap();
+
+    assert_eq!(state.node_at_row(&tree, 0), Some(function.id()));
+    assert_eq!(
+      state.node_at_row(&tree, 1),
+      Some(function.child(0).unwrap().id())
+    );
+  }
+
+  #[test]
+  fn zoomed_to_bottom_and_top() {
+    let tree = parse("fn foo() {} fn bar() {}");
+    let root = tree.root_node();
+
+    let first = root.child(0).unwrap();
+
+    let mut state = State::new(first.id());
+
+    state.zoom_in(&tree).unwrap();
+
+    state.move_to_bottom(&tree);
+
+    assert_eq!(state.cursor, first.child(3).unwrap().child(1).unwrap().id());
+
+    state.move_to_top(&tree);
+
+    assert_eq!(state.cursor, first.id());
+  }
 }
@@ -0,0 +1,162 @@
+use super::*;
+
+/// Statistics about a parse, printed by `--stats`.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Stats {
+  bytes: usize,
+  depth: usize,
+  errors: usize,
+  kinds: Vec<(String, usize)>,
+  nodes: usize,
+  parse_time: Duration,
+}
+
+impl Display for Stats {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    const BAR_WIDTH: usize = 30;
+
+    writeln!(f, "parse time  {:?}", self.parse_time)?;
+    writeln!(
+      f,
+      "throughput  {}",
+      Self::throughput(self.bytes, self.parse_time)
+    )?;
+    writeln!(f, "bytes       {}", self.bytes)?;
+    writeln!(f, "nodes       {}", self.nodes)?;
+    writeln!(f, "max depth   {}", self.depth)?;
+    writeln!(f, "errors      {}", self.errors)?;
+
+    writeln!(f, "\nkinds")?;
+
+    let kind_width = self
+      .kinds
+      .iter()
+      .map(|(kind, _)| kind.chars().count())
+      .max()
+      .unwrap_or_default();
+
+    let max = self.kinds.first().map_or(1, |(_, count)| *count);
+
+    let count_width = max.to_string().len();
+
+    for (kind, count) in &self.kinds {
+      writeln!(
+        f,
+        "  {kind:<kind_width$}  {count:>count_width$} {}",
+        "#".repeat((count * BAR_WIDTH).div_ceil(max)),
+      )?;
+    }
+
+    Ok(())
+  }
+}
+
+impl Stats {
+  pub(crate) fn new(tree: &Tree, bytes: usize, parse_time: Duration) -> Self {
+    let (mut depth, mut errors, mut nodes) = (0, 0, 0);
+
+    let mut kinds = HashMap::<String, usize>::new();
+
+    tree.root_node().preorder(|node, node_depth| {
+      depth = depth.max(node_depth);
+      nodes += 1;
+
+      if node.is_error() || node.is_missing() {
+        errors += 1;
+      }
+
+      let kind = if node.is_named() {
+        node.kind().to_string()
+      } else {
+        format!("{:?}", node.kind())
+      };
+
+      *kinds.entry(kind).or_default() += 1;
+
+      true
+    });
+
+    let mut kinds = kinds.into_iter().collect::<Vec<_>>();
+
+    kinds.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    Self {
+      bytes,
+      depth,
+      errors,
+      kinds,
+      nodes,
+      parse_time,
+    }
+  }
+
+  /// Formats the rate at which `bytes` were parsed in `time`.
+  #[allow(clippy::cast_precision_loss)]
+  pub(crate) fn throughput(bytes: usize, time: Duration) -> String {
+    if time.is_zero() {
+      return "-".into();
+    }
+
+    format!(
+      "{:.2} MB/s",
+      bytes as f64 / time.as_secs_f64() / 1_000_000.0
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn display() {
+    let code = "fn foo() {}\nfn bar() {}";
+
+    let tree = parse(code);
+
+    assert_eq!(
+      Stats::new(&tree, code.len(), Duration::from_millis(2)).to_string(),
+      [
+        "parse time  2ms",
+        "throughput  0.01 MB/s",
+        "bytes       23",
+        "nodes       19",
+        "max depth   3",
+        "errors      0",
+        "",
+        "kinds",
+        "  \"(\"            2 ##############################",
+        "  \")\"            2 ##############################",
+        "  \"fn\"           2 ##############################",
+        "  \"{\"            2 ##############################",
+        "  \"}\"            2 ##############################",
+        "  block          2 ##############################",
+        "  function_item  2 ##############################",
+        "  identifier     2 ##############################",
+        "  parameters     2 ##############################",
+        "  source_file    1 ###############",
+        "",
+      ]
+      .join("\n")
+    );
+  }
+
+  #[test]
+  fn errors() {
+    let code = "fn foo( {}";
+
+    let tree = parse(code);
+
+    assert!(Stats::new(&tree, code.len(), Duration::ZERO).errors > 0);
+  }
+
+  #[test]
+  fn throughput() {
+    assert_eq!(
+      Stats::throughput(5_000_000, Duration::from_secs(2)),
+      "2.50 MB/s"
+    );
+
+    assert_eq!(Stats::throughput(5, Duration::ZERO), "-");
+  }
+}
@@ -1,6 +1,9 @@
 use super::*;
 
 pub(crate) struct StatusLine<'a> {
+  draft: Option<&'a Draft>,
+  error: Option<&'a (String, Instant)>,
+  file_deleted: bool,
   message: Option<&'a (String, Instant)>,
   mode: &'a Mode,
   state: &'a State,
@@ -15,14 +18,34 @@ impl Widget for StatusLine<'_> {
 }
 
 impl<'a> StatusLine<'a> {
+  pub(crate) const ERROR_DURATION: Duration = Duration::from_secs(5);
+
   pub(crate) const MESSAGE_DURATION: Duration = Duration::from_secs(2);
 
+  pub(crate) fn draft(self, draft: Option<&'a Draft>) -> Self {
+    Self { draft, ..self }
+  }
+
+  pub(crate) fn error(self, error: Option<&'a (String, Instant)>) -> Self {
+    Self { error, ..self }
+  }
+
+  pub(crate) fn file_deleted(self, file_deleted: bool) -> Self {
+    Self {
+      file_deleted,
+      ..self
+    }
+  }
+
   pub(crate) fn new(
     mode: &'a Mode,
     state: &'a State,
     message: Option<&'a (String, Instant)>,
   ) -> Self {
     Self {
+      draft: None,
+      error: None,
+      file_deleted: false,
       message,
       mode,
       state,
@@ -30,7 +53,40 @@ impl<'a> StatusLine<'a> {
   }
 
   fn prompt(&self) -> Option<(String, Style)> {
-    if *self.mode == Mode::Search || !self.state.search_query.is_empty() {
+    if *self.mode == Mode::Edit {
+      Some((
+        self.draft.map_or_else(
+          || "-- EDIT -- Enter to apply, Esc to cancel".into(),
+          |draft| {
+            let (before, after) = draft.line();
+            format!("-- EDIT -- {before}▏{after}")
+          },
+        ),
+        Style::default().fg(Color::Green),
+      ))
+    } else if *self.mode == Mode::Fold {
+      Some(("z".into(), Style::default().fg(Color::Magenta)))
+    } else if *self.mode == Mode::Yank {
+      Some((
+        "y: [y]text [s]sexp [p]osition [b]ytes [k]ind [a]ncestors \
+         [q]uery [j]son"
+          .into(),
+        Style::default().fg(Color::Magenta),
+      ))
+    } else if let Some((error, _)) = self.error.filter(|(_, t)| {
+      *self.mode == Mode::Normal && t.elapsed() < Self::ERROR_DURATION
+    }) {
+      Some((
+        format!("error: {error} (E for history)"),
+        Style::default().fg(Color::Red),
+      ))
+    } else if self.file_deleted && *self.mode == Mode::Normal {
+      Some((
+        "File deleted, showing its last contents until it comes back".into(),
+        Style::default().fg(Color::Red),
+      ))
+    } else if *self.mode == Mode::Search || !self.state.search_query.is_empty()
+    {
       let prompt = if *self.mode == Mode::Search {
         format!("/{}", self.state.search_query)
       } else {
@@ -39,8 +95,7 @@ impl<'a> StatusLine<'a> {
         let position = self
           .state
           .matches
-          .iter()
-          .position(|&id| id == self.state.cursor)
+          .position(self.state.cursor)
           .map(|i| i + 1);
 
         if let Some(pos) = position {
@@ -57,7 +112,7 @@ impl<'a> StatusLine<'a> {
         Style::default().fg(Color::Red),
       ))
     } else if *self.mode == Mode::Query || !self.state.ts_query.is_empty() {
-      let prompt = if *self.mode == Mode::Query {
+      let mut prompt = if *self.mode == Mode::Query {
         format!(":{}", self.state.ts_query)
       } else {
         let match_count = self.state.ts_query_matches.len();
@@ -65,8 +120,7 @@ impl<'a> StatusLine<'a> {
         let position = self
           .state
           .ts_query_matches
-          .iter()
-          .position(|&id| id == self.state.cursor)
+          .position(self.state.cursor)
           .map(|i| i + 1);
 
         if let Some(pos) = position {
@@ -76,6 +130,14 @@ impl<'a> StatusLine<'a> {
         }
       };
 
+      if self.state.query_scope != QueryScope::Tree {
+        prompt = format!("{prompt} [{}]", self.state.query_scope);
+      }
+
+      if let Some(warning) = self.state.ts_query_warning {
+        prompt = format!("{prompt} | {warning}");
+      }
+
       Some((prompt, Style::default().fg(Color::Cyan)))
     } else if self
       .message
@@ -99,6 +161,86 @@ impl<'a> StatusLine<'a> {
 mod tests {
   use super::*;
 
+  #[test]
+  fn edit_mode_shows_cursor_line() {
+    let tree = parse("fn foo() {}");
+
+    let state = State::new(tree.root_node().id());
+
+    let mut draft = Draft::new("fn foo() {\n}".into(), 0);
+
+    draft.left();
+    draft.left();
+    draft.left();
+
+    assert_eq!(
+      StatusLine::new(&Mode::Edit, &state, None)
+        .draft(Some(&draft))
+        .prompt()
+        .map(|(text, _)| text),
+      Some("-- EDIT -- fn foo() ▏{".into()),
+    );
+  }
+
+  #[test]
+  fn edit_mode_shows_hint() {
+    let tree = parse("fn foo() {}");
+
+    let state = State::new(tree.root_node().id());
+
+    assert_eq!(prompt_color(&Mode::Edit, &state, None), Some(Color::Green));
+  }
+
+  #[test]
+  fn error_is_red_in_normal_mode() {
+    let tree = parse("fn foo() {}");
+
+    let mut state = State::new(tree.root_node().id());
+    state.search_query = "bar".into();
+
+    let error = ("failed to reload `foo.rs`".into(), Instant::now());
+
+    assert_eq!(
+      StatusLine::new(&Mode::Normal, &state, None)
+        .error(Some(&error))
+        .file_deleted(true)
+        .prompt(),
+      Some((
+        "error: failed to reload `foo.rs` (E for history)".into(),
+        Style::default().fg(Color::Red),
+      )),
+    );
+
+    assert_eq!(
+      StatusLine::new(&Mode::Search, &state, None)
+        .error(Some(&error))
+        .prompt()
+        .map(|(text, _)| text),
+      Some("/bar".into()),
+    );
+  }
+
+  #[test]
+  fn expired_error_returns_none() {
+    let tree = parse("fn foo() {}");
+
+    let state = State::new(tree.root_node().id());
+
+    let error = (
+      "foo".into(),
+      Instant::now()
+        .checked_sub(StatusLine::ERROR_DURATION)
+        .unwrap(),
+    );
+
+    assert_eq!(
+      StatusLine::new(&Mode::Normal, &state, None)
+        .error(Some(&error))
+        .prompt(),
+      None
+    );
+  }
+
   #[test]
   fn expired_message_returns_none() {
     let tree = parse("fn foo() {}");
@@ -120,8 +262,41 @@ mod tests {
     );
   }
 
-  fn language() -> TreeSitterLanguage {
-    tree_sitter_rust::LANGUAGE.into()
+  #[test]
+  fn file_deleted_is_red_in_normal_mode() {
+    let tree = parse("fn foo() {}");
+
+    let mut state = State::new(tree.root_node().id());
+    state.ts_query = "(identifier)".into();
+
+    let status_line =
+      StatusLine::new(&Mode::Normal, &state, None).file_deleted(true);
+
+    assert_eq!(
+      status_line.prompt(),
+      Some((
+        "File deleted, showing its last contents until it comes back".into(),
+        Style::default().fg(Color::Red),
+      )),
+    );
+
+    assert_eq!(
+      StatusLine::new(&Mode::Query, &state, None)
+        .file_deleted(true)
+        .prompt()
+        .map(|(text, _)| text),
+      Some(":(identifier)".into()),
+    );
+  }
+
+  #[test]
+  fn fold_mode_shows_pending_prefix() {
+    let tree = parse("fn foo() {}");
+
+    let mut state = State::new(tree.root_node().id());
+    state.search_query = "bar".into();
+
+    assert_eq!(prompt(&Mode::Fold, &state, None), Some("z".into()));
   }
 
   #[test]
@@ -170,12 +345,6 @@ mod tests {
     assert!(!StatusLine::new(&Mode::Normal, &state, None).visible());
   }
 
-  fn parse(code: &str) -> Tree {
-    let mut parser = Parser::new();
-    parser.set_language(&language()).unwrap();
-    parser.parse(code, None).unwrap()
-  }
-
   fn prompt(
     mode: &Mode,
     state: &State,
@@ -228,7 +397,7 @@ mod tests {
     let mut state = State::new(tree.root_node().id());
     state.ts_query = "(bad".into();
     state.ts_query_error = Some("syntax error".into());
-    state.ts_query_matches = vec![1, 2];
+    state.ts_query_matches = vec![1, 2].into();
 
     assert_eq!(
       prompt(&Mode::Normal, &state, None),
@@ -265,7 +434,7 @@ mod tests {
 
     let mut state = State::new(tree.root_node().id());
     state.ts_query = "(identifier)".into();
-    state.ts_query_matches = vec![1, 2];
+    state.ts_query_matches = vec![1, 2].into();
 
     assert_eq!(
       prompt(&Mode::Normal, &state, None),
@@ -279,7 +448,7 @@ mod tests {
 
     let mut state = State::new(tree.root_node().id());
     state.ts_query = "(identifier)".into();
-    state.ts_query_matches = vec![10, 20];
+    state.ts_query_matches = vec![10, 20].into();
     state.cursor = 10;
 
     assert_eq!(
@@ -294,7 +463,7 @@ mod tests {
 
     let mut state = State::new(tree.root_node().id());
     state.ts_query = "(identifier)".into();
-    state.ts_query_matches = vec![1];
+    state.ts_query_matches = vec![1].into();
 
     assert_eq!(
       prompt(&Mode::Normal, &state, Some(&("foo".into(), Instant::now()))),
@@ -331,7 +500,7 @@ mod tests {
 
     let mut state = State::new(tree.root_node().id());
     state.search_query = "bar".into();
-    state.matches = vec![1, 2, 3];
+    state.matches = vec![1, 2, 3].into();
 
     assert_eq!(prompt(&Mode::Normal, &state, None), Some("[3] /bar".into()),);
   }
@@ -342,7 +511,7 @@ mod tests {
 
     let mut state = State::new(tree.root_node().id());
     state.search_query = "bar".into();
-    state.matches = vec![10, 20, 30];
+    state.matches = vec![10, 20, 30].into();
     state.cursor = 20;
 
     assert_eq!(
@@ -372,4 +541,15 @@ mod tests {
 
     assert!(StatusLine::new(&Mode::Search, &state, None).visible());
   }
+
+  #[test]
+  fn yank_mode_shows_menu() {
+    let tree = parse("fn foo() {}");
+
+    let state = State::new(tree.root_node().id());
+
+    assert!(prompt(&Mode::Yank, &state, None)
+      .unwrap()
+      .starts_with("y: [y]text"));
+  }
 }
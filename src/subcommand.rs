@@ -0,0 +1,27 @@
+use super::*;
+
+#[derive(clap::Subcommand, Debug)]
+pub(crate) enum Subcommand {
+  /// Time how long grammars take to parse files
+  Bench(Bench),
+  /// Show how two grammars parse the same file
+  Compare(Compare),
+  /// Show a structural diff between two versions of a file
+  Diff(Diff),
+  /// Check comment assertions in annotated sources against a query's captures
+  QueryTest(QueryTest),
+  /// Run a grammar's corpus tests
+  Test(CorpusTest),
+}
+
+impl Subcommand {
+  pub(crate) fn run(self) -> Result {
+    match self {
+      Self::Bench(bench) => bench.run(),
+      Self::Compare(compare) => compare.run(),
+      Self::Diff(diff) => diff.run(),
+      Self::QueryTest(query_test) => query_test.run(),
+      Self::Test(test) => test.run(),
+    }
+  }
+}
@@ -47,4 +47,34 @@ impl Terminal {
 
     Ok(())
   }
+
+  pub(crate) fn set_clipboard(text: &str) -> Result {
+    let mut stdout = io::stdout();
+
+    write!(stdout, "\x1b]52;c;{}\x07", BASE64.encode(text))?;
+
+    stdout.flush()?;
+
+    Ok(())
+  }
+
+  /// Hands the screen back to the shell while `f` runs, then takes it over
+  /// again and forces a full redraw.
+  pub(crate) fn suspend<T>(&mut self, f: impl FnOnce() -> T) -> Result<T> {
+    self.restore()?;
+
+    let value = f();
+
+    crossterm::terminal::enable_raw_mode()?;
+
+    execute!(
+      self.inner.backend_mut(),
+      EnterAlternateScreen,
+      EnableMouseCapture
+    )?;
+
+    self.inner.clear()?;
+
+    Ok(value)
+  }
 }
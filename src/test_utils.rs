@@ -0,0 +1,24 @@
+use super::*;
+
+/// Parses `depth` nested JSON arrays, which is deep enough at 100k to
+/// overflow the stack of anything that recurses over the tree.
+pub(crate) fn deeply_nested(depth: usize) -> (String, Tree) {
+  let code = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+  let tree = parse_with(&tree_sitter_json::LANGUAGE.into(), &code);
+
+  (code, tree)
+}
+
+/// Parses `code` as Rust.
+pub(crate) fn parse(code: &str) -> Tree {
+  parse_with(&tree_sitter_rust::LANGUAGE.into(), code)
+}
+
+pub(crate) fn parse_with(language: &TreeSitterLanguage, code: &str) -> Tree {
+  let mut parser = Parser::new();
+
+  parser.set_language(language).unwrap();
+
+  parser.parse(code, None).unwrap()
+}
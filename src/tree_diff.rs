@@ -0,0 +1,607 @@
+use super::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Change {
+  Changed,
+  Deleted,
+  Inserted,
+  Moved,
+}
+
+impl Change {
+  pub(crate) fn color(self) -> Color {
+    match self {
+      Self::Changed => Color::Yellow,
+      Self::Deleted => Color::Red,
+      Self::Inserted => Color::Green,
+      Self::Moved => Color::Magenta,
+    }
+  }
+
+  pub(crate) fn marker(self) -> char {
+    match self {
+      Self::Changed => '~',
+      Self::Deleted => '-',
+      Self::Inserted => '+',
+      Self::Moved => '>',
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Entry {
+  pub(crate) change: Change,
+  pub(crate) new: Option<usize>,
+  pub(crate) old: Option<usize>,
+}
+
+/// A structural diff between two syntax trees, pairing up nodes of the old
+/// tree with nodes of the new one.
+#[derive(Debug, Default)]
+pub(crate) struct TreeDiff {
+  pub(crate) entries: Vec<Entry>,
+  pub(crate) new: HashMap<usize, Change>,
+  pub(crate) old: HashMap<usize, Change>,
+  partners: HashMap<usize, usize>,
+}
+
+impl TreeDiff {
+  const MAX_ALIGNMENT_CELLS: usize = 1_000_000;
+
+  /// Returns index pairs of the longest common subsequence of `old` and `new`.
+  pub(crate) fn align(old: &[u64], new: &[u64]) -> Vec<(usize, usize)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+
+    let suffix = old[prefix..]
+      .iter()
+      .rev()
+      .zip(new[prefix..].iter().rev())
+      .take_while(|(a, b)| a == b)
+      .count();
+
+    let (a, b) = (
+      &old[prefix..old.len() - suffix],
+      &new[prefix..new.len() - suffix],
+    );
+
+    let mut pairs = (0..prefix).map(|i| (i, i)).collect::<Vec<_>>();
+
+    if (a.len() + 1) * (b.len() + 1) <= Self::MAX_ALIGNMENT_CELLS {
+      let mut table = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+      for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+          table[i][j] = if a[i] == b[j] {
+            table[i + 1][j + 1] + 1
+          } else {
+            table[i + 1][j].max(table[i][j + 1])
+          };
+        }
+      }
+
+      let (mut i, mut j) = (0, 0);
+
+      while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+          pairs.push((prefix + i, prefix + j));
+          i += 1;
+          j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+          i += 1;
+        } else {
+          j += 1;
+        }
+      }
+    }
+
+    pairs.extend(
+      (0..suffix).map(|k| (old.len() - suffix + k, new.len() - suffix + k)),
+    );
+
+    pairs
+  }
+
+  /// Pairs up the nodes of two trees parsed from the same source by byte
+  /// range, marking nodes whose kinds differ as changed and nodes whose range
+  /// has no counterpart as deleted or inserted.
+  pub(crate) fn by_range(old: &Tree, new: &Tree) -> Self {
+    fn ranges(tree: &Tree) -> HashMap<(usize, usize), Vec<Node<'_>>> {
+      let mut ranges = HashMap::<_, Vec<_>>::new();
+
+      tree.root_node().preorder(|node, _| {
+        ranges
+          .entry((node.start_byte(), node.end_byte()))
+          .or_default()
+          .push(node);
+        true
+      });
+
+      ranges
+    }
+
+    let mut diff = Self::default();
+
+    let (mut old_ranges, new_ranges) = (ranges(old), ranges(new));
+
+    for (range, new_nodes) in new_ranges {
+      let old_nodes = old_ranges.remove(&range).unwrap_or_default();
+
+      for (k, &new_node) in new_nodes.iter().enumerate() {
+        let Some(&old_node) = old_nodes.get(k) else {
+          diff.new.insert(new_node.id(), Change::Inserted);
+
+          diff.entries.push(Entry {
+            change: Change::Inserted,
+            new: Some(new_node.id()),
+            old: None,
+          });
+
+          continue;
+        };
+
+        diff.partners.insert(new_node.id(), old_node.id());
+
+        if old_node.kind() != new_node.kind() {
+          diff.old.insert(old_node.id(), Change::Changed);
+          diff.new.insert(new_node.id(), Change::Changed);
+
+          diff.entries.push(Entry {
+            change: Change::Changed,
+            new: Some(new_node.id()),
+            old: Some(old_node.id()),
+          });
+        }
+      }
+
+      for &old_node in old_nodes.iter().skip(new_nodes.len()) {
+        diff.old.insert(old_node.id(), Change::Deleted);
+
+        diff.entries.push(Entry {
+          change: Change::Deleted,
+          new: None,
+          old: Some(old_node.id()),
+        });
+      }
+    }
+
+    for old_node in old_ranges.into_values().flatten() {
+      diff.old.insert(old_node.id(), Change::Deleted);
+
+      diff.entries.push(Entry {
+        change: Change::Deleted,
+        new: None,
+        old: Some(old_node.id()),
+      });
+    }
+
+    let (old_index, new_index) = (NodeIndex::new(old), NodeIndex::new(new));
+
+    diff.entries.sort_by_key(|entry| {
+      entry
+        .new
+        .and_then(|id| new_index.node(new, id))
+        .or_else(|| entry.old.and_then(|id| old_index.node(old, id)))
+        .map(|node| (node.start_byte(), Reverse(node.end_byte())))
+    });
+
+    diff
+  }
+
+  fn children(node: Node<'_>) -> Vec<Node<'_>> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor).collect()
+  }
+
+  fn describe(node: Node, source: &Source) -> String {
+    let text = if node.child_count() == 0 {
+      format!(" \"{}\"", source.text(node.byte_range()))
+    } else {
+      String::new()
+    };
+
+    format!(
+      "{} [{}:{}..{}:{}]{text}",
+      node.kind(),
+      node.start_position().row,
+      node.start_position().column,
+      node.end_position().row,
+      node.end_position().column,
+    )
+  }
+
+  /// Hashes every node of the subtree by kind, leaf text and the hashes of
+  /// its children, so equal hashes mean identical subtrees.
+  fn hashes(root: Node, source: &Source) -> HashMap<usize, u64> {
+    let mut nodes = Vec::new();
+
+    root.preorder(|node, depth| {
+      nodes.push((node, depth));
+      true
+    });
+
+    let mut hashes = HashMap::new();
+    let mut stack = Vec::<(usize, u64)>::new();
+
+    for &(node, depth) in nodes.iter().rev() {
+      let mut hasher = DefaultHasher::new();
+
+      node.kind_id().hash(&mut hasher);
+
+      if node.child_count() == 0 {
+        source.text(node.byte_range()).hash(&mut hasher);
+      }
+
+      while let Some(&(child_depth, hash)) = stack.last() {
+        if child_depth != depth + 1 {
+          break;
+        }
+
+        hash.hash(&mut hasher);
+        stack.pop();
+      }
+
+      let hash = hasher.finish();
+
+      hashes.insert(node.id(), hash);
+      stack.push((depth, hash));
+    }
+
+    hashes
+  }
+
+  fn mark(map: &mut HashMap<usize, Change>, node: Node, change: Change) {
+    node.preorder(|node, _| {
+      map.insert(node.id(), change);
+      true
+    });
+  }
+
+  pub(crate) fn new(old: (&Tree, &Source), new: (&Tree, &Source)) -> Self {
+    let (old_root, new_root) = (old.0.root_node(), new.0.root_node());
+
+    let old_hashes = Self::hashes(old_root, old.1);
+    let new_hashes = Self::hashes(new_root, new.1);
+
+    let mut diff = Self::default();
+
+    let mut deleted = Vec::new();
+    let mut inserted = Vec::new();
+
+    let mut work = Vec::new();
+
+    if old_root.kind_id() == new_root.kind_id() {
+      work.push((old_root, new_root));
+    } else {
+      deleted.push(old_root);
+      inserted.push(new_root);
+    }
+
+    while let Some((old_node, new_node)) = work.pop() {
+      if old_hashes[&old_node.id()] == new_hashes[&new_node.id()] {
+        diff.pair(old_node, new_node, None);
+        continue;
+      }
+
+      diff.partners.insert(new_node.id(), old_node.id());
+
+      if old_node.child_count() == 0 || new_node.child_count() == 0 {
+        diff.old.insert(old_node.id(), Change::Changed);
+        diff.new.insert(new_node.id(), Change::Changed);
+
+        diff.entries.push(Entry {
+          change: Change::Changed,
+          new: Some(new_node.id()),
+          old: Some(old_node.id()),
+        });
+
+        continue;
+      }
+
+      let old_children = Self::children(old_node);
+      let new_children = Self::children(new_node);
+
+      let anchors = Self::align(
+        &old_children
+          .iter()
+          .map(|node| old_hashes[&node.id()])
+          .collect::<Vec<_>>(),
+        &new_children
+          .iter()
+          .map(|node| new_hashes[&node.id()])
+          .collect::<Vec<_>>(),
+      );
+
+      let (mut i, mut j) = (0, 0);
+
+      for (a, b) in anchors
+        .into_iter()
+        .chain(iter::once((old_children.len(), new_children.len())))
+      {
+        let mut k = i;
+
+        for &child in &new_children[j..b] {
+          if let Some(offset) = old_children[k..a]
+            .iter()
+            .position(|old| old.kind_id() == child.kind_id())
+          {
+            deleted.extend(&old_children[k..k + offset]);
+            work.push((old_children[k + offset], child));
+            k += offset + 1;
+          } else {
+            inserted.push(child);
+          }
+        }
+
+        deleted.extend(&old_children[k..a]);
+
+        if a < old_children.len() {
+          work.push((old_children[a], new_children[b]));
+        }
+
+        (i, j) = (a + 1, b + 1);
+      }
+    }
+
+    let mut candidates = HashMap::<u64, VecDeque<Node>>::new();
+
+    for &node in &deleted {
+      if node.is_named() || node.child_count() > 0 {
+        candidates
+          .entry(old_hashes[&node.id()])
+          .or_default()
+          .push_back(node);
+      }
+    }
+
+    let mut moved = HashSet::new();
+
+    for &node in &inserted {
+      let old_node = (node.is_named() || node.child_count() > 0)
+        .then(|| candidates.get_mut(&new_hashes[&node.id()]))
+        .flatten()
+        .and_then(VecDeque::pop_front);
+
+      if let Some(old_node) = old_node {
+        moved.insert(old_node.id());
+        moved.insert(node.id());
+
+        diff.pair(old_node, node, Some(Change::Moved));
+
+        diff.entries.push(Entry {
+          change: Change::Moved,
+          new: Some(node.id()),
+          old: Some(old_node.id()),
+        });
+      }
+    }
+
+    for node in deleted {
+      if !moved.contains(&node.id()) {
+        Self::mark(&mut diff.old, node, Change::Deleted);
+
+        diff.entries.push(Entry {
+          change: Change::Deleted,
+          new: None,
+          old: Some(node.id()),
+        });
+      }
+    }
+
+    for node in inserted {
+      if !moved.contains(&node.id()) {
+        Self::mark(&mut diff.new, node, Change::Inserted);
+
+        diff.entries.push(Entry {
+          change: Change::Inserted,
+          new: Some(node.id()),
+          old: None,
+        });
+      }
+    }
+
+    let (old_index, new_index) = (NodeIndex::new(old.0), NodeIndex::new(new.0));
+
+    diff.entries.sort_by_key(|entry| {
+      entry
+        .new
+        .and_then(|id| new_index.node(new.0, id))
+        .or_else(|| entry.old.and_then(|id| old_index.node(old.0, id)))
+        .map(|node| node.start_byte())
+    });
+
+    diff
+  }
+
+  /// Pairs up every node of two identical subtrees, marking them all with
+  /// `change` if given.
+  fn pair(&mut self, old: Node, new: Node, change: Option<Change>) {
+    let mut old_nodes = Vec::new();
+
+    old.preorder(|node, _| {
+      old_nodes.push(node.id());
+      true
+    });
+
+    let mut old_nodes = old_nodes.into_iter();
+
+    new.preorder(|node, _| {
+      let Some(old) = old_nodes.next() else {
+        return false;
+      };
+
+      self.partners.insert(node.id(), old);
+
+      if let Some(change) = change {
+        self.new.insert(node.id(), change);
+        self.old.insert(old, change);
+      }
+
+      true
+    });
+  }
+
+  /// Returns the node of the old tree paired with `new`, if any.
+  pub(crate) fn partner(&self, new: usize) -> Option<usize> {
+    self.partners.get(&new).copied()
+  }
+
+  pub(crate) fn render(
+    &self,
+    old: (&Tree, &Source),
+    new: (&Tree, &Source),
+  ) -> String {
+    let (old_index, new_index) = (NodeIndex::new(old.0), NodeIndex::new(new.0));
+
+    let mut output = String::new();
+
+    for entry in &self.entries {
+      let old_node = entry
+        .old
+        .and_then(|id| old_index.node(old.0, id))
+        .map(|node| Self::describe(node, old.1));
+
+      let new_node = entry
+        .new
+        .and_then(|id| new_index.node(new.0, id))
+        .map(|node| Self::describe(node, new.1));
+
+      let line = match (old_node, new_node) {
+        (Some(old), Some(new)) => format!("{old} -> {new}"),
+        (Some(node), None) | (None, Some(node)) => node,
+        (None, None) => continue,
+      };
+
+      output.push(entry.change.marker());
+      output.push(' ');
+      output.push_str(&line);
+      output.push('\n');
+    }
+
+    output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn align() {
+    #[track_caller]
+    fn case(old: &[u64], new: &[u64], expected: &[(usize, usize)]) {
+      assert_eq!(TreeDiff::align(old, new), expected);
+    }
+
+    case(&[], &[], &[]);
+    case(&[1, 2, 3], &[1, 2, 3], &[(0, 0), (1, 1), (2, 2)]);
+    case(&[1, 2, 3], &[1, 3], &[(0, 0), (2, 1)]);
+    case(&[1, 3], &[1, 2, 3], &[(0, 0), (1, 2)]);
+    case(&[1, 2, 3, 4], &[1, 3, 2, 4], &[(0, 0), (2, 1), (3, 3)]);
+    case(&[1, 2], &[3, 4], &[]);
+  }
+
+  #[test]
+  fn by_range() {
+    let code = Source::from("f<T>(x);");
+
+    let mut parser = Parser::new();
+
+    let mut parse = |language: Language| {
+      parser.set_language(&language.into()).unwrap();
+      code.parse(&mut parser, None).unwrap()
+    };
+
+    let (old, new) = (parse(Language::JavaScript), parse(Language::TypeScript));
+
+    assert_eq!(
+      TreeDiff::by_range(&old, &old).render((&old, &code), (&old, &code)),
+      ""
+    );
+
+    assert_eq!(
+      TreeDiff::by_range(&old, &new).render((&old, &code), (&new, &code)),
+      [
+        "~ binary_expression [0:0..0:7] -> call_expression [0:0..0:7]",
+        "- binary_expression [0:0..0:3]",
+        "+ type_arguments [0:1..0:4]",
+        "~ identifier [0:2..0:3] \"T\" -> type_identifier [0:2..0:3] \"T\"",
+        "~ parenthesized_expression [0:4..0:7] -> arguments [0:4..0:7]",
+        "",
+      ]
+      .join("\n"),
+    );
+  }
+
+  #[test]
+  fn changed_leaf() {
+    assert_eq!(
+      diff("fn foo() {}", "fn bar() {}"),
+      "~ identifier [0:3..0:6] \"foo\" -> identifier [0:3..0:6] \"bar\"\n",
+    );
+  }
+
+  #[test]
+  fn deleted() {
+    assert_eq!(
+      diff("fn foo() {}\nfn bar() {}", "fn foo() {}"),
+      "- function_item [1:0..1:11]\n",
+    );
+  }
+
+  fn diff(old: &str, new: &str) -> String {
+    let (old_tree, new_tree) = (parse(old), parse(new));
+
+    let (old, new) = (Source::from(old), Source::from(new));
+
+    TreeDiff::new((&old_tree, &old), (&new_tree, &new))
+      .render((&old_tree, &old), (&new_tree, &new))
+  }
+
+  #[test]
+  fn identical() {
+    assert_eq!(diff("fn foo() {}", "fn foo() {}"), "");
+  }
+
+  #[test]
+  fn inserted() {
+    assert_eq!(
+      diff("fn foo() {}", "fn foo() {}\nstruct Bar;"),
+      "+ struct_item [1:0..1:11]\n",
+    );
+  }
+
+  #[test]
+  fn moved() {
+    assert_eq!(
+      diff("fn foo() {}\nfn bar() {}", "fn bar() {}\nfn foo() {}"),
+      "> function_item [0:0..0:11] -> function_item [1:0..1:11]\n",
+    );
+  }
+
+  #[test]
+  fn partners() {
+    let (old, new) = ("fn foo() {}", "fn bar() {}");
+
+    let (old_tree, new_tree) = (parse(old), parse(new));
+
+    let diff =
+      TreeDiff::new((&old_tree, &old.into()), (&new_tree, &new.into()));
+
+    let old_root = old_tree.root_node();
+    let new_root = new_tree.root_node();
+
+    assert_eq!(diff.partner(new_root.id()), Some(old_root.id()));
+
+    assert_eq!(
+      diff.partner(new_root.child(0).unwrap().child(2).unwrap().id()),
+      Some(old_root.child(0).unwrap().child(2).unwrap().id())
+    );
+
+    assert_eq!(
+      diff
+        .new
+        .get(&new_root.child(0).unwrap().child(1).unwrap().id()),
+      Some(&Change::Changed)
+    );
+  }
+}
@@ -1,21 +1,19 @@
 use super::*;
 
 pub(crate) struct TreePanel<'a> {
-  code: &'a str,
+  changes: Option<&'a HashMap<usize, Change>>,
+  max_text_width: usize,
+  source: &'a Source,
   state: &'a State,
   tree: &'a Tree,
 }
 
 impl Widget for TreePanel<'_> {
   fn render(self, area: Rect, buf: &mut Buffer) {
-    let lines = self
-      .collect_lines()
-      .into_iter()
-      .skip(self.state.scroll_offset as usize)
-      .take(area.height as usize)
-      .collect::<Vec<_>>();
+    let lines = self.visible_lines(area.height as usize);
 
     Paragraph::new(lines)
+      .scroll((0, self.state.horizontal_offset))
       .block(
         Block::default()
           .title_style(
@@ -30,6 +28,15 @@ impl Widget for TreePanel<'_> {
 }
 
 impl<'a> TreePanel<'a> {
+  pub(crate) const DEFAULT_MAX_TEXT_WIDTH: usize = 60;
+
+  pub(crate) fn changes(self, changes: &'a HashMap<usize, Change>) -> Self {
+    Self {
+      changes: Some(changes),
+      ..self
+    }
+  }
+
   fn child_count_span(node: &Node) -> Span<'a> {
     Span::styled(
       format!("{} ", node.child_count()),
@@ -37,12 +44,20 @@ impl<'a> TreePanel<'a> {
     )
   }
 
-  fn collect_lines(&self) -> Vec<Line<'a>> {
-    let mut lines = Vec::new();
+  fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
 
-    self.render_node(&self.tree.root_node(), 0, &mut lines);
+    for c in text.chars() {
+      match c {
+        '\n' => escaped.push_str("\\n"),
+        '\r' => escaped.push_str("\\r"),
+        '\t' => escaped.push_str("\\t"),
+        c if c.is_control() => escaped.extend(c.escape_unicode()),
+        c => escaped.push(c),
+      }
+    }
 
-    lines
+    escaped
   }
 
   fn fold_span(&self, node: &Node) -> Span<'a> {
@@ -50,7 +65,7 @@ impl<'a> TreePanel<'a> {
       return Span::styled("    ", Style::default());
     }
 
-    let collapsed = self.state.collapsed_nodes.contains(&node.id());
+    let collapsed = self.state.collapsed_nodes().contains(&node.id());
 
     Span::styled(
       if collapsed { "[+] " } else { "[-] " },
@@ -71,18 +86,28 @@ impl<'a> TreePanel<'a> {
   }
 
   fn indent_span(depth: usize) -> Span<'a> {
-    Span::styled("  ".repeat(depth), Style::default().fg(Color::DarkGray))
+    Span::styled("│ ".repeat(depth), Style::default().fg(Color::DarkGray))
   }
 
   fn kind_span(&self, node: &Node) -> Span<'a> {
     let id = node.id();
 
     let is_cursor = id == self.state.cursor;
-    let is_match = self.state.matches.contains(&id);
-    let is_query_match = self.state.ts_query_matches.contains(&id);
-    let is_selected = self.state.selected.is_some_and(|s| s == id);
+    let is_match = self.state.matches.contains(id);
+    let is_query_match = self.state.ts_query_matches.contains(id);
+    let is_selected = self.state.selected.contains(id);
 
-    let style = if is_match {
+    let change = self.changes.and_then(|changes| changes.get(&id));
+
+    let style = if let Some(change) = change {
+      Style::default()
+        .fg(change.color())
+        .add_modifier(if is_cursor {
+          Modifier::BOLD | Modifier::UNDERLINED
+        } else {
+          Modifier::BOLD
+        })
+    } else if is_match {
       Style::default()
         .fg(Color::Black)
         .bg(Color::Yellow)
@@ -105,8 +130,39 @@ impl<'a> TreePanel<'a> {
     Span::styled(node.kind(), style)
   }
 
-  pub(crate) fn new(tree: &'a Tree, code: &'a str, state: &'a State) -> Self {
-    Self { code, state, tree }
+  /// Returns how far the rows shown in `area` can scroll right before the
+  /// widest of them ends at its right edge.
+  pub(crate) fn max_horizontal_offset(&self, area: Rect) -> u16 {
+    let widest = self
+      .visible_lines(area.height as usize)
+      .iter()
+      .map(Line::width)
+      .max()
+      .unwrap_or_default();
+
+    u16::try_from(widest.saturating_sub(area.width as usize))
+      .unwrap_or(u16::MAX)
+  }
+
+  pub(crate) fn max_text_width(self, max_text_width: usize) -> Self {
+    Self {
+      max_text_width,
+      ..self
+    }
+  }
+
+  pub(crate) fn new(
+    tree: &'a Tree,
+    source: &'a Source,
+    state: &'a State,
+  ) -> Self {
+    Self {
+      changes: None,
+      max_text_width: Self::DEFAULT_MAX_TEXT_WIDTH,
+      source,
+      state,
+      tree,
+    }
   }
 
   fn position_span(node: &Node) -> Span<'a> {
@@ -126,10 +182,17 @@ impl<'a> TreePanel<'a> {
     let id = node.id();
 
     let is_cursor = id == self.state.cursor;
-    let is_selected = self.state.selected.is_some_and(|s| s == id);
+    let is_selected = self.state.selected.contains(id);
 
     if is_cursor {
       Span::styled("> ", Style::default().add_modifier(Modifier::BOLD))
+    } else if let Some(change) =
+      self.changes.and_then(|changes| changes.get(&id))
+    {
+      Span::styled(
+        format!("{} ", change.marker()),
+        Style::default().fg(change.color()),
+      )
     } else if is_selected {
       Span::styled(
         "* ",
@@ -143,28 +206,98 @@ impl<'a> TreePanel<'a> {
     }
   }
 
-  fn render_node(&self, node: &Node, depth: usize, lines: &mut Vec<Line<'a>>) {
-    lines.push(self.format_node(node, depth));
-
-    if self.state.collapsed_nodes.contains(&node.id()) {
-      return;
-    }
-
-    for i in 0..node.child_count_u32() {
-      if let Some(child) = node.child(i) {
-        self.render_node(&child, depth + 1, lines);
-      }
-    }
-  }
-
   fn text_span(&self, node: &Node) -> Span<'a> {
     if node.child_count() > 0 {
       return Span::raw("");
     }
 
     Span::styled(
-      format!("\"{}\"", &self.code[node.start_byte()..node.end_byte()]),
+      format!(
+        "\"{}\"",
+        Self::truncate(
+          &Self::escape(&self.source.text(node.byte_range())),
+          self.max_text_width
+        )
+      ),
       Style::default().fg(Color::Green),
     )
   }
+
+  fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+      return text.to_string();
+    }
+
+    let mut truncated = text
+      .chars()
+      .take(width.saturating_sub(1))
+      .collect::<String>();
+
+    truncated.push('…');
+
+    truncated
+  }
+
+  fn visible_lines(&self, height: usize) -> Vec<Line<'a>> {
+    self
+      .state
+      .rows(self.tree)
+      .iter()
+      .skip(self.state.scroll_offset as usize)
+      .take(height)
+      .filter_map(|row| {
+        self
+          .state
+          .find(self.tree, row.id)
+          .map(|node| self.format_node(&node, row.depth))
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn escape() {
+    #[track_caller]
+    fn case(text: &str, expected: &str) {
+      assert_eq!(TreePanel::escape(text), expected);
+    }
+
+    case("foo", "foo");
+    case("foo\nbar", "foo\\nbar");
+    case("a\tb\r\n", "a\\tb\\r\\n");
+    case("\u{1b}[0m", "\\u{1b}[0m");
+    case("héllo", "héllo");
+  }
+
+  #[test]
+  fn max_horizontal_offset() {
+    let tree = parse("fn foo() {}");
+
+    let state = State::new(tree.root_node().id());
+
+    let source = "fn foo() {}".into();
+
+    let panel = TreePanel::new(&tree, &source, &state);
+
+    assert_eq!(panel.max_horizontal_offset(Rect::new(0, 0, 10, 20)), 29);
+    assert_eq!(panel.max_horizontal_offset(Rect::new(0, 0, 30, 1)), 2);
+    assert_eq!(panel.max_horizontal_offset(Rect::new(0, 0, 80, 20)), 0);
+  }
+
+  #[test]
+  fn truncate() {
+    #[track_caller]
+    fn case(text: &str, width: usize, expected: &str) {
+      assert_eq!(TreePanel::truncate(text, width), expected);
+    }
+
+    case("foo", 3, "foo");
+    case("foobar", 4, "foo…");
+    case("héllo wörld", 6, "héllo…");
+    case("foo", 0, "…");
+  }
 }
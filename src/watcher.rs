@@ -1,29 +1,370 @@
 use super::*;
 
+/// The files to watch in an interactive session.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct WatchPaths {
+  pub(crate) grammar: Option<PathBuf>,
+  /// The source directory of `grammar`, whose hand-written sources are
+  /// watched so the library can be rebuilt from them.
+  pub(crate) grammar_dir: Option<PathBuf>,
+  pub(crate) queries: Vec<PathBuf>,
+  pub(crate) source: Option<PathBuf>,
+}
+
+impl WatchPaths {
+  /// The files a grammar is written in, rather than generated from. Files in
+  /// `src` are only watched if the directory exists.
+  fn grammar_sources(directory: &Path) -> Vec<PathBuf> {
+    let src = directory.join("src");
+
+    iter::once(directory.join("grammar.js"))
+      .chain(
+        ["scanner.c", "scanner.cc"]
+          .into_iter()
+          .filter(|_| src.is_dir())
+          .map(|name| src.join(name)),
+      )
+      .collect()
+  }
+
+  fn targets(&self) -> Vec<(PathBuf, ChannelEvent)> {
+    self
+      .source
+      .iter()
+      .map(|path| (path.clone(), ChannelEvent::FileChanged))
+      .chain(
+        self
+          .queries
+          .iter()
+          .map(|path| (path.clone(), ChannelEvent::QueryChanged)),
+      )
+      .chain(
+        self
+          .grammar
+          .iter()
+          .map(|path| (path.clone(), ChannelEvent::GrammarChanged)),
+      )
+      .chain(
+        self
+          .grammar_dir
+          .iter()
+          .flat_map(|directory| Self::grammar_sources(directory))
+          .map(|path| (path, ChannelEvent::GrammarSourceChanged)),
+      )
+      .collect()
+  }
+}
+
 pub(crate) struct Watcher {
   _inner: notify::RecommendedWatcher,
 }
 
 impl Watcher {
-  pub(crate) fn new(path: &Path, tx: &Sender<ChannelEvent>) -> Result<Self> {
-    let watcher_tx = tx.clone();
+  /// How long a path must go without events before its change is reported.
+  pub(crate) const DEBOUNCE: Duration = Duration::from_millis(50);
+
+  /// Collects changed paths until none have changed for `DEBOUNCE`, then
+  /// reports each one once, so the last write of a burst is never lost.
+  ///
+  /// Whether the source was deleted is decided by checking for it once the
+  /// burst is over, since saving by rename briefly removes it.
+  fn debounce(
+    rx: &Receiver<PathBuf>,
+    targets: &HashMap<PathBuf, ChannelEvent>,
+    tx: &Sender<ChannelEvent>,
+  ) {
+    while let Ok(path) = rx.recv() {
+      let mut changed = BTreeSet::from([path]);
+
+      loop {
+        match rx.recv_timeout(Self::DEBOUNCE) {
+          Ok(path) => {
+            changed.insert(path);
+          }
+          Err(RecvTimeoutError::Timeout) => break,
+          Err(RecvTimeoutError::Disconnected) => return,
+        }
+      }
+
+      for path in changed {
+        let Some(event) = targets.get(&path) else {
+          continue;
+        };
+
+        let event = match event {
+          ChannelEvent::FileChanged if !path.exists() => {
+            ChannelEvent::FileDeleted
+          }
+          event => event.clone(),
+        };
+
+        if tx.send(event).is_err() {
+          return;
+        }
+      }
+    }
+  }
+
+  /// Resolves `path` to an absolute path through its parent directory, which
+  /// is what gets watched and what event paths are reported relative to.
+  fn key(path: &Path) -> Result<(PathBuf, PathBuf)> {
+    let file_name = path
+      .file_name()
+      .ok_or_else(|| anyhow!("cannot watch `{}`", path.display()))?;
+
+    let parent = match path.parent() {
+      Some(parent) if !parent.as_os_str().is_empty() => parent,
+      _ => Path::new("."),
+    };
+
+    let parent = parent
+      .canonicalize()
+      .with_context(|| format!("failed to watch `{}`", path.display()))?;
+
+    Ok((parent.join(file_name), parent))
+  }
+
+  /// Watches every path in `paths`, sending its event when it changes.
+  ///
+  /// Parent directories are watched rather than the files themselves, so the
+  /// watch survives editors that save by writing a new file and renaming it
+  /// over the old one, or by deleting and recreating it.
+  pub(crate) fn new(
+    paths: &WatchPaths,
+    tx: &Sender<ChannelEvent>,
+  ) -> Result<Self> {
+    let mut targets = HashMap::new();
+    let mut directories = HashSet::new();
+
+    for (path, event) in paths.targets() {
+      let (key, parent) = Self::key(&path)?;
+      targets.insert(key, event);
+      directories.insert(parent);
+    }
+
+    let targets = Arc::new(targets);
+
+    let (changed_tx, changed_rx) = channel();
+
+    let mut inner = notify::recommended_watcher({
+      let targets = targets.clone();
 
-    let mut inner = notify::recommended_watcher(
       move |res: Result<notify::Event, notify::Error>| {
-        if let Ok(event) = res {
-          if event.kind.is_modify() {
-            let _ = watcher_tx.send(ChannelEvent::FileChanged);
+        let Ok(event) = res else {
+          return;
+        };
+
+        if event.kind.is_access() {
+          return;
+        }
+
+        for path in event.paths {
+          if targets.contains_key(&path) {
+            let _ = changed_tx.send(path);
           }
         }
-      },
-    )?;
+      }
+    })?;
+
+    for directory in directories {
+      notify::Watcher::watch(
+        &mut inner,
+        &directory,
+        notify::RecursiveMode::NonRecursive,
+      )?;
+    }
+
+    let tx = tx.clone();
 
-    notify::Watcher::watch(
-      &mut inner,
-      path,
-      notify::RecursiveMode::NonRecursive,
-    )?;
+    thread::spawn(move || Self::debounce(&changed_rx, &targets, &tx));
 
     Ok(Self { _inner: inner })
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn atomic_rename_is_reported_as_change() {
+    let (tempdir, source, rx, _watcher) = watch();
+
+    let replacement = tempdir.path().join("foo.rs.swp");
+
+    fs::write(&replacement, "fn bar() {}").unwrap();
+    fs::rename(&replacement, &source).unwrap();
+
+    assert_eq!(events(&rx), [ChannelEvent::FileChanged]);
+
+    fs::write(&source, "fn baz() {}").unwrap();
+
+    assert_eq!(events(&rx), [ChannelEvent::FileChanged]);
+  }
+
+  #[test]
+  fn backup_and_recreate_is_reported_as_change() {
+    let (tempdir, source, rx, _watcher) = watch();
+
+    fs::rename(&source, tempdir.path().join("foo.rs~")).unwrap();
+    fs::write(&source, "fn bar() {}").unwrap();
+
+    assert_eq!(events(&rx), [ChannelEvent::FileChanged]);
+  }
+
+  #[test]
+  fn burst_of_writes_is_reported_once_after_last_write() {
+    let (_tempdir, source, rx, _watcher) = watch();
+
+    for i in 0..10 {
+      fs::write(&source, format!("fn foo{i}() {{}}")).unwrap();
+    }
+
+    assert_eq!(
+      rx.recv_timeout(Duration::from_secs(5)).unwrap(),
+      ChannelEvent::FileChanged
+    );
+
+    assert_eq!(fs::read_to_string(&source).unwrap(), "fn foo9() {}");
+
+    assert_eq!(events(&rx), []);
+  }
+
+  #[test]
+  fn delete_is_reported() {
+    let (_tempdir, source, rx, _watcher) = watch();
+
+    fs::remove_file(&source).unwrap();
+
+    assert_eq!(events(&rx), [ChannelEvent::FileDeleted]);
+
+    fs::write(&source, "fn bar() {}").unwrap();
+
+    assert_eq!(events(&rx), [ChannelEvent::FileChanged]);
+  }
+
+  /// Collects events until none arrive for a while.
+  fn events(rx: &Receiver<ChannelEvent>) -> Vec<ChannelEvent> {
+    iter::from_fn(|| rx.recv_timeout(Watcher::DEBOUNCE * 10).ok()).collect()
+  }
+
+  #[test]
+  fn grammar_source_changes_are_reported() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let src = tempdir.path().join("src");
+
+    fs::create_dir(&src).unwrap();
+
+    let (tx, rx) = channel();
+
+    let _watcher = Watcher::new(
+      &WatchPaths {
+        grammar_dir: Some(tempdir.path().into()),
+        ..WatchPaths::default()
+      },
+      &tx,
+    )
+    .unwrap();
+
+    fs::write(tempdir.path().join("grammar.js"), "module.exports = {}")
+      .unwrap();
+
+    assert_eq!(events(&rx), [ChannelEvent::GrammarSourceChanged]);
+
+    fs::write(src.join("scanner.c"), "").unwrap();
+
+    assert_eq!(events(&rx), [ChannelEvent::GrammarSourceChanged]);
+
+    fs::write(src.join("parser.c"), "").unwrap();
+    fs::write(src.join("grammar.json"), "{}").unwrap();
+
+    assert_eq!(events(&rx), []);
+  }
+
+  #[test]
+  fn in_place_write_is_reported_as_change() {
+    let (_tempdir, source, rx, _watcher) = watch();
+
+    fs::write(&source, "fn bar() {}").unwrap();
+
+    assert_eq!(events(&rx), [ChannelEvent::FileChanged]);
+  }
+
+  #[test]
+  fn key_resolves_relative_paths() {
+    let current = env::current_dir().unwrap().canonicalize().unwrap();
+
+    assert_eq!(
+      Watcher::key(Path::new("foo.rs")).unwrap(),
+      (current.join("foo.rs"), current.clone())
+    );
+
+    assert_eq!(
+      Watcher::key(Path::new("src/foo.rs")).unwrap(),
+      (current.join("src/foo.rs"), current.join("src"))
+    );
+  }
+
+  #[test]
+  fn other_files_are_ignored() {
+    let (tempdir, _source, rx, _watcher) = watch();
+
+    fs::write(tempdir.path().join("bar.rs"), "fn bar() {}").unwrap();
+
+    assert_eq!(events(&rx), []);
+  }
+
+  #[test]
+  fn query_and_grammar_changes_have_their_own_events() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let grammar = tempdir.path().join("foo.so");
+    let query = tempdir.path().join("query.scm");
+
+    fs::write(&grammar, "").unwrap();
+    fs::write(&query, "(identifier) @name").unwrap();
+
+    let (tx, rx) = channel();
+
+    let _watcher = Watcher::new(
+      &WatchPaths {
+        grammar: Some(grammar.clone()),
+        grammar_dir: None,
+        queries: vec![query.clone()],
+        source: None,
+      },
+      &tx,
+    )
+    .unwrap();
+
+    fs::write(&query, "(block) @block").unwrap();
+
+    assert_eq!(events(&rx), [ChannelEvent::QueryChanged]);
+
+    fs::write(&grammar, "rebuilt").unwrap();
+
+    assert_eq!(events(&rx), [ChannelEvent::GrammarChanged]);
+  }
+
+  fn watch() -> (tempfile::TempDir, PathBuf, Receiver<ChannelEvent>, Watcher) {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let source = tempdir.path().join("foo.rs");
+
+    fs::write(&source, "fn foo() {}").unwrap();
+
+    let (tx, rx) = channel();
+
+    let watcher = Watcher::new(
+      &WatchPaths {
+        source: Some(source.clone()),
+        ..WatchPaths::default()
+      },
+      &tx,
+    )
+    .unwrap();
+
+    (tempdir, source, rx, watcher)
+  }
+}
@@ -0,0 +1,189 @@
+use super::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum YankFormat {
+  Ancestors,
+  ByteRange,
+  Json,
+  Kind,
+  Pattern,
+  Position,
+  Sexp,
+  Text,
+}
+
+impl Display for YankFormat {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(
+      f,
+      "{}",
+      match self {
+        Self::Ancestors => "ancestor path",
+        Self::ByteRange => "byte range",
+        Self::Json => "JSON",
+        Self::Kind => "kind",
+        Self::Pattern => "query pattern",
+        Self::Position => "position",
+        Self::Sexp => "S-expression",
+        Self::Text => "text",
+      }
+    )
+  }
+}
+
+impl YankFormat {
+  pub(crate) fn from_key(c: char) -> Option<Self> {
+    match c {
+      'a' => Some(Self::Ancestors),
+      'b' => Some(Self::ByteRange),
+      'j' => Some(Self::Json),
+      'k' => Some(Self::Kind),
+      'p' => Some(Self::Position),
+      'q' => Some(Self::Pattern),
+      's' => Some(Self::Sexp),
+      'y' => Some(Self::Text),
+      _ => None,
+    }
+  }
+
+  pub(crate) fn json(node: Node, source: &Source) -> Result<String> {
+    fn point(point: Point) -> String {
+      format!("{{\"row\":{},\"column\":{}}}", point.row, point.column)
+    }
+
+    fn string(value: &str) -> String {
+      serde_json::Value::from(value).to_string()
+    }
+
+    let mut json = String::new();
+
+    let mut cursor = node.walk();
+
+    'outer: loop {
+      let node = cursor.node();
+
+      write!(
+        json,
+        "{{\"kind\":{},\"named\":{},\"field\":{},\"start_byte\":{},\
+         \"end_byte\":{},\"start\":{},\"end\":{}",
+        string(node.kind()),
+        node.is_named(),
+        cursor.field_name().map_or_else(|| "null".into(), string),
+        node.start_byte(),
+        node.end_byte(),
+        point(node.start_position()),
+        point(node.end_position()),
+      )?;
+
+      if cursor.goto_first_child() {
+        json.push_str(",\"children\":[");
+        continue;
+      }
+
+      write!(
+        json,
+        ",\"text\":{}}}",
+        string(&source.text(node.byte_range()))
+      )?;
+
+      loop {
+        if cursor.goto_next_sibling() {
+          json.push(',');
+          continue 'outer;
+        }
+
+        if !cursor.goto_parent() {
+          break 'outer;
+        }
+
+        json.push_str("]}");
+      }
+    }
+
+    Ok(json)
+  }
+
+  pub(crate) fn position(node: Node, path: Option<&Path>) -> String {
+    let position = node.start_position();
+
+    let (row, column) = (position.row + 1, position.column + 1);
+
+    match path {
+      Some(path) => format!("{}:{row}:{column}", path.display()),
+      None => format!("{row}:{column}"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn from_key() {
+    #[track_caller]
+    fn case(c: char, expected: Option<YankFormat>) {
+      assert_eq!(YankFormat::from_key(c), expected);
+    }
+
+    case('a', Some(YankFormat::Ancestors));
+    case('b', Some(YankFormat::ByteRange));
+    case('j', Some(YankFormat::Json));
+    case('k', Some(YankFormat::Kind));
+    case('p', Some(YankFormat::Position));
+    case('q', Some(YankFormat::Pattern));
+    case('s', Some(YankFormat::Sexp));
+    case('y', Some(YankFormat::Text));
+    case('x', None);
+  }
+
+  #[test]
+  fn json() {
+    let code = "fn foo() {}";
+
+    let tree = parse(code);
+
+    let node = tree.root_node().child(0).unwrap();
+
+    let value = serde_json::from_str::<serde_json::Value>(
+      &YankFormat::json(node, &code.into()).unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(value["kind"], "function_item");
+    assert_eq!(value["field"], serde_json::Value::Null);
+    assert_eq!(value["end_byte"], 11);
+    assert_eq!(value["children"].as_array().unwrap().len(), 4);
+    assert_eq!(value["children"][1]["field"], "name");
+    assert_eq!(value["children"][1]["text"], "foo");
+    assert_eq!(value["children"][2]["children"][0]["kind"], "(");
+    assert_eq!(value["children"][3]["start"]["column"], 9);
+  }
+
+  #[test]
+  fn json_deeply_nested() {
+    let depth = 10_000;
+
+    let (code, tree) = deeply_nested(depth);
+
+    let json =
+      YankFormat::json(tree.root_node(), &code.as_str().into()).unwrap();
+
+    assert!(json.starts_with("{\"kind\":\"document\""));
+    assert!(json.ends_with("]}"));
+  }
+
+  #[test]
+  fn position() {
+    let tree = parse("fn foo() {}\nfn bar() {}");
+
+    let node = tree.root_node().child(1).unwrap().child(1).unwrap();
+
+    assert_eq!(
+      YankFormat::position(node, Some(Path::new("src/foo.rs"))),
+      "src/foo.rs:2:4"
+    );
+
+    assert_eq!(YankFormat::position(node, None), "2:4");
+  }
+}
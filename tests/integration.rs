@@ -12,6 +12,39 @@ use {
   Expected::*,
 };
 
+const CORPUS: &str = "\
+===
+Functions
+===
+
+fn foo() {}
+
+---
+
+(source_file (function_item (identifier) (parameters) (block)))
+
+===
+Lets
+===
+
+let x = 1;
+
+---
+
+(source_file
+  (let_declaration
+    (identifier)))
+
+===
+Broken
+:error
+===
+
+fn (
+
+---
+";
+
 enum Expected {
   Contains(String),
   Exact(String),
@@ -19,10 +52,11 @@ enum Expected {
 
 struct Test<'a> {
   arguments: Vec<String>,
+  env: Vec<(&'a str, &'a str)>,
   expected_status: i32,
   expected_stderr: Expected,
   expected_stdout: String,
-  files: Vec<(&'a str, &'a str)>,
+  files: Vec<(&'a str, &'a [u8])>,
   stdin: Option<&'a str>,
   tempdir: tempfile::TempDir,
 }
@@ -39,6 +73,17 @@ impl<'a> Test<'a> {
     }
   }
 
+  fn binary_file(self, path: &'a str, content: &'a [u8]) -> Self {
+    Self {
+      files: self
+        .files
+        .into_iter()
+        .chain(std::iter::once((path, content)))
+        .collect(),
+      ..self
+    }
+  }
+
   fn command(&self) -> Command {
     let mut command = Command::new(executable_path("axil"));
 
@@ -51,9 +96,20 @@ impl<'a> Test<'a> {
       command.arg(argument);
     }
 
+    for (key, value) in &self.env {
+      command.env(key, value);
+    }
+
     command
   }
 
+  fn env(self, key: &'a str, value: &'a str) -> Self {
+    Self {
+      env: self.env.into_iter().chain(once((key, value))).collect(),
+      ..self
+    }
+  }
+
   fn expected_status(self, expected_status: i32) -> Self {
     Self {
       expected_status,
@@ -76,19 +132,13 @@ impl<'a> Test<'a> {
   }
 
   fn file(self, path: &'a str, content: &'a str) -> Self {
-    Self {
-      files: self
-        .files
-        .into_iter()
-        .chain(std::iter::once((path, content)))
-        .collect(),
-      ..self
-    }
+    self.binary_file(path, content.as_bytes())
   }
 
   fn new() -> Self {
     Self {
       arguments: Vec::new(),
+      env: Vec::new(),
       expected_status: 0,
       expected_stderr: Exact(String::new()),
       expected_stdout: String::new(),
@@ -98,7 +148,7 @@ impl<'a> Test<'a> {
     }
   }
 
-  fn run(self) {
+  fn run(self) -> tempfile::TempDir {
     for (path, content) in &self.files {
       let path = self.tempdir.path().join(path);
 
@@ -142,6 +192,8 @@ impl<'a> Test<'a> {
     }
 
     assert_eq!(stdout, self.expected_stdout, "unexpected stdout");
+
+    self.tempdir
   }
 
   fn stdin(self, stdin: &'a str) -> Self {
@@ -152,6 +204,346 @@ impl<'a> Test<'a> {
   }
 }
 
+#[test]
+fn compare_grammars() {
+  Test::new()
+    .file("foo.ts", "f<T>(x);\n")
+    .argument("compare")
+    .argument("foo.ts")
+    .argument("javascript")
+    .argument("typescript")
+    .expected_stdout(
+      r#"
+      ~ binary_expression [0:0..0:7] -> call_expression [0:0..0:7]
+      - binary_expression [0:0..0:3]
+      + type_arguments [0:1..0:4]
+      ~ identifier [0:2..0:3] "T" -> type_identifier [0:2..0:3] "T"
+      ~ parenthesized_expression [0:4..0:7] -> arguments [0:4..0:7]
+      "#,
+    )
+    .run();
+}
+
+#[test]
+fn compare_missing_grammar_library_is_error() {
+  Test::new()
+    .file("foo.rs", "fn foo() {}\n")
+    .argument("compare")
+    .argument("foo.rs")
+    .argument("rust")
+    .argument("missing.so")
+    .expected_status(1)
+    .expected_stderr(Contains(
+      "failed to load grammar library `missing.so`".into(),
+    ))
+    .run();
+}
+
+#[test]
+fn compare_utf16_file() {
+  let content = "f<T>(x);\n"
+    .encode_utf16()
+    .flat_map(u16::to_be_bytes)
+    .collect::<Vec<_>>();
+
+  Test::new()
+    .binary_file("foo.ts", &content)
+    .argument("compare")
+    .argument("--encoding")
+    .argument("utf-16be")
+    .argument("foo.ts")
+    .argument("javascript")
+    .argument("typescript")
+    .expected_stdout(
+      r#"
+      ~ binary_expression [0:0..0:14] -> call_expression [0:0..0:14]
+      - binary_expression [0:0..0:6]
+      + type_arguments [0:2..0:8]
+      ~ identifier [0:4..0:6] "T" -> type_identifier [0:4..0:6] "T"
+      ~ parenthesized_expression [0:8..0:14] -> arguments [0:8..0:14]
+      "#,
+    )
+    .run();
+}
+
+#[test]
+fn corpus_test_failure() {
+  Test::new()
+    .file("test/corpus/items.txt", CORPUS)
+    .argument("test")
+    .argument("--language")
+    .argument("rust")
+    .expected_status(1)
+    .expected_stderr(Exact("error: 1 of 3 corpus examples failed\n".into()))
+    .expected_stdout(
+      "
+      test/corpus/items.txt:
+        Functions ... ok
+        Lets ... FAILED
+            (source_file
+              (let_declaration
+          -     (identifier)))
+          +     (identifier)
+          +     (integer_literal)))
+        Broken ... ok
+
+      2 passed, 1 failed, 0 skipped
+      ",
+    )
+    .run();
+}
+
+#[test]
+fn corpus_test_update() {
+  Test::new()
+    .file("corpus.txt", CORPUS)
+    .argument("test")
+    .argument("corpus.txt")
+    .argument("--language")
+    .argument("rust")
+    .argument("--update")
+    .expected_stdout(
+      "
+      corpus.txt:
+        Functions ... ok
+        Lets ... updated
+        Broken ... ok
+
+      2 passed, 0 failed, 0 skipped, 1 updated
+      ",
+    )
+    .run();
+}
+
+#[test]
+fn debug_log_writes_parse_log() {
+  let tempdir = Test::new()
+    .file("foo.rs", "fn bar() {}")
+    .argument("foo.rs")
+    .argument("--debug-log")
+    .argument("parse.log")
+    .expected_stdout(
+      "
+      source_file [0:0..0:11]
+        function_item [0:0..0:11]
+          fn [0:0..0:2] \"fn\"
+          identifier [0:3..0:6] \"bar\"
+          parameters [0:6..0:8]
+            ( [0:6..0:7] \"(\"
+            ) [0:7..0:8] \")\"
+          block [0:9..0:11]
+            { [0:9..0:10] \"{\"
+            } [0:10..0:11] \"}\"
+      ",
+    )
+    .run();
+
+  let log = fs::read_to_string(tempdir.path().join("parse.log")).unwrap();
+
+  assert!(log.starts_with("new_parse\n"), "{log}");
+  assert!(log.contains("\n  consume character:'f'\n"), "{log}");
+}
+
+#[test]
+fn diff_files() {
+  Test::new()
+    .file("old.rs", "fn foo() { 1 }\n")
+    .file("new.rs", "fn foo() { 2 }\nstruct Bar;\n")
+    .argument("diff")
+    .argument("old.rs")
+    .argument("new.rs")
+    .expected_stdout(
+      r#"
+      ~ integer_literal [0:11..0:12] "1" -> integer_literal [0:11..0:12] "2"
+      + struct_item [1:0..1:11]
+      "#,
+    )
+    .run();
+}
+
+#[test]
+fn diff_non_utf8_files() {
+  Test::new()
+    .binary_file("old.rs", b"let s = \"caf\xe9\";\n")
+    .file("new.rs", "let s = \"cafe\";\n")
+    .argument("diff")
+    .argument("old.rs")
+    .argument("new.rs")
+    .expected_stdout(
+      r#"
+      ~ string_content [0:9..0:13] "café" -> string_content [0:9..0:13] "cafe"
+      "#,
+    )
+    .run();
+}
+
+#[test]
+fn diff_revision_outside_git_is_error() {
+  Test::new()
+    .file("foo.rs", "fn foo() {}\n")
+    .argument("diff")
+    .argument("foo.rs")
+    .argument("--rev")
+    .argument("HEAD")
+    .expected_status(1)
+    .expected_stderr(Contains("failed to read `HEAD:./foo.rs` from git".into()))
+    .run();
+}
+
+#[test]
+fn dot_without_graphviz_writes_raw_graphs() {
+  let tempdir = Test::new()
+    .file("foo.rs", "fn bar() {}")
+    .argument("foo.rs")
+    .argument("--dot")
+    .argument("parse.html")
+    .env("PATH", "")
+    .expected_stdout(
+      "
+      source_file [0:0..0:11]
+        function_item [0:0..0:11]
+          fn [0:0..0:2] \"fn\"
+          identifier [0:3..0:6] \"bar\"
+          parameters [0:6..0:8]
+            ( [0:6..0:7] \"(\"
+            ) [0:7..0:8] \")\"
+          block [0:9..0:11]
+            { [0:9..0:10] \"{\"
+            } [0:10..0:11] \"}\"
+      ",
+    )
+    .expected_stderr(Contains(
+      "warning: `dot` not found, is Graphviz installed? Writing unrendered \
+       graphs to `parse.dot`"
+        .into(),
+    ))
+    .run();
+
+  let dot = fs::read_to_string(tempdir.path().join("parse.dot")).unwrap();
+
+  assert!(dot.starts_with("graph {\nlabel=\"new_parse\""), "{dot}");
+  assert!(!tempdir.path().join("parse.html").exists());
+}
+
+#[test]
+fn dot_writes_graphs() {
+  let tempdir = Test::new()
+    .file("foo.rs", "fn bar() {}")
+    .argument("foo.rs")
+    .argument("--dot")
+    .argument("parse.dot")
+    .expected_stdout(
+      "
+      source_file [0:0..0:11]
+        function_item [0:0..0:11]
+          fn [0:0..0:2] \"fn\"
+          identifier [0:3..0:6] \"bar\"
+          parameters [0:6..0:8]
+            ( [0:6..0:7] \"(\"
+            ) [0:7..0:8] \")\"
+          block [0:9..0:11]
+            { [0:9..0:10] \"{\"
+            } [0:10..0:11] \"}\"
+      ",
+    )
+    .run();
+
+  let dot = fs::read_to_string(tempdir.path().join("parse.dot")).unwrap();
+
+  assert!(dot.starts_with("graph {\nlabel=\"new_parse\""), "{dot}");
+  assert!(dot.contains("digraph stack {"), "{dot}");
+}
+
+#[test]
+fn encoding_flag_overrides_detection() {
+  Test::new()
+    .file("foo.rs", "let s = \"café\";")
+    .argument("--encoding")
+    .argument("latin-1")
+    .argument("--query")
+    .argument("(string_content) @s")
+    .argument("foo.rs")
+    .expected_stdout(
+      "
+      source_file [0:0..0:16]
+        let_declaration [0:0..0:16]
+          string_literal [0:8..0:15]
+            string_content [0:9..0:14] \"cafÃ©\"
+      ",
+    )
+    .run();
+}
+
+#[test]
+fn file_named_like_subcommand() {
+  for arguments in [&["./test"][..], &["--", "test"]] {
+    let mut test = Test::new()
+      .file("test", "fn a() {}")
+      .argument("--language")
+      .argument("rust");
+
+    for argument in arguments {
+      test = test.argument(argument);
+    }
+
+    test
+      .expected_stdout(
+        "
+        source_file [0:0..0:9]
+          function_item [0:0..0:9]
+            fn [0:0..0:2] \"fn\"
+            identifier [0:3..0:4] \"a\"
+            parameters [0:4..0:6]
+              ( [0:4..0:5] \"(\"
+              ) [0:5..0:6] \")\"
+            block [0:7..0:9]
+              { [0:7..0:8] \"{\"
+              } [0:8..0:9] \"}\"
+        ",
+      )
+      .run();
+  }
+}
+
+#[test]
+fn grammar_dir_requires_grammar_library() {
+  Test::new()
+    .file("foo.rs", "fn foo() {}")
+    .argument("foo.rs")
+    .argument("--interactive")
+    .argument("--watch")
+    .argument("--grammar-dir")
+    .argument(".")
+    .argument("--language")
+    .argument("rust")
+    .expected_status(1)
+    .expected_stderr(Exact(
+      "error: `--grammar-dir` requires `--language` to be a grammar library\n"
+        .into(),
+    ))
+    .run();
+}
+
+#[test]
+fn invalid_utf8_is_escaped() {
+  Test::new()
+    .binary_file("foo.rs", b"let s = \"caf\xe9\";")
+    .argument("--encoding")
+    .argument("utf-8")
+    .argument("--query")
+    .argument("(string_content) @s")
+    .argument("foo.rs")
+    .expected_stdout(
+      "
+      source_file [0:0..0:15]
+        let_declaration [0:0..0:15]
+          string_literal [0:8..0:14]
+            string_content [0:9..0:13] \"caf\\xe9\"
+      ",
+    )
+    .run();
+}
+
 #[test]
 fn language_flag_override() {
   Test::new()
@@ -225,6 +617,59 @@ fn parse_rust_file() {
     .run();
 }
 
+#[test]
+fn profile_query_requires_query() {
+  Test::new()
+    .file("foo.rs", "fn foo() {}")
+    .argument("foo.rs")
+    .argument("--profile-query")
+    .expected_status(1)
+    .expected_stderr(Exact(
+      "error: `--profile-query` requires `--query` or `--query-file`\n".into(),
+    ))
+    .run();
+}
+
+#[test]
+fn query_file_can_be_repeated() {
+  Test::new()
+    .file("foo.rs", "fn bar() {}")
+    .file("names.scm", "(identifier) @name\n")
+    .file("blocks.scm", "(block) @block\n")
+    .argument("foo.rs")
+    .argument("--query-file")
+    .argument("names.scm")
+    .argument("--query-file")
+    .argument("blocks.scm")
+    .expected_stdout(
+      "
+      source_file [0:0..0:11]
+        function_item [0:0..0:11]
+          identifier [0:3..0:6] \"bar\"
+          block [0:9..0:11]
+      ",
+    )
+    .run();
+}
+
+#[test]
+fn query_file_filters_output() {
+  Test::new()
+    .file("foo.rs", "fn bar() {}")
+    .file("query.scm", "; names\n(identifier) @name\n")
+    .argument("foo.rs")
+    .argument("--query-file")
+    .argument("query.scm")
+    .expected_stdout(
+      "
+      source_file [0:0..0:11]
+        function_item [0:0..0:11]
+          identifier [0:3..0:6] \"bar\"
+      ",
+    )
+    .run();
+}
+
 #[test]
 fn query_filters_output() {
   Test::new()
@@ -262,6 +707,86 @@ fn query_filters_output_multiple_patterns() {
     .run();
 }
 
+#[test]
+fn query_range_limits_matches() {
+  Test::new()
+    .file("foo.rs", "fn bar() {}\nfn baz() {}")
+    .argument("foo.rs")
+    .argument("--query")
+    .argument("(identifier) @name")
+    .argument("--query-range")
+    .argument("1:0..1:11")
+    .expected_stdout(
+      "
+      source_file [0:0..1:11]
+        function_item [1:0..1:11]
+          identifier [1:3..1:6] \"baz\"
+      ",
+    )
+    .run();
+}
+
+#[test]
+fn query_range_rejects_backwards_range() {
+  Test::new()
+    .file("foo.rs", "fn bar() {}")
+    .argument("foo.rs")
+    .argument("--query")
+    .argument("(identifier) @name")
+    .argument("--query-range")
+    .argument("5..1")
+    .expected_status(2)
+    .expected_stderr(Contains("invalid value '5..1'".into()))
+    .run();
+}
+
+#[test]
+fn query_test_assertions() {
+  Test::new()
+    .file(
+      "highlights.scm",
+      "\"fn\" @keyword\n(function_item name: (identifier) @function)\n",
+    )
+    .file(
+      "test.rs",
+      "fn foo() {}\n// <- keyword\n// ^ function\n//  ^ !function\n",
+    )
+    .argument("query-test")
+    .argument("--query")
+    .argument("highlights.scm")
+    .argument("test.rs")
+    .expected_status(1)
+    .expected_stderr(Exact("error: 1 of 3 query assertions failed\n".into()))
+    .expected_stdout(
+      "
+      test.rs:1:5: expected `!function`, found `function`
+      2 passed, 1 failed
+      ",
+    )
+    .run();
+}
+
+#[test]
+fn query_test_utf16_file() {
+  let content = "fn foo() {}\n// <- keyword\n// ^ function\n"
+    .encode_utf16()
+    .flat_map(u16::to_le_bytes)
+    .collect::<Vec<_>>();
+
+  Test::new()
+    .file(
+      "highlights.scm",
+      "\"fn\" @keyword\n(function_item name: (identifier) @function)\n",
+    )
+    .binary_file("test.rs", &content)
+    .argument("query-test")
+    .argument("--query")
+    .argument("highlights.scm")
+    .argument("test.rs")
+    .expected_stdout("2 passed, 0 failed\n")
+    .run();
+}
+
 #[test]
 fn stdin_with_language() {
   Test::new()
@@ -316,6 +841,18 @@ fn stdin_without_language_is_error() {
     .run();
 }
 
+#[test]
+fn unknown_encoding_is_error() {
+  Test::new()
+    .file("foo.rs", "fn foo() {}")
+    .argument("--encoding")
+    .argument("ebcdic")
+    .argument("foo.rs")
+    .expected_status(2)
+    .expected_stderr(Contains("unknown encoding `ebcdic`".into()))
+    .run();
+}
+
 #[test]
 fn unknown_extension_is_error() {
   Test::new()
@@ -335,12 +872,34 @@ fn unknown_language_is_error() {
     .argument("foo")
     .expected_status(2)
     .expected_stderr(Contains(
-      "invalid value 'foo' for '--language <LANGUAGE>': unknown language `foo`"
+      "invalid value 'foo' for '--language <LANGUAGE>': unknown language or grammar library `foo`"
         .into(),
     ))
     .run();
 }
 
+#[test]
+fn utf16_file_keeps_byte_offsets() {
+  let content = "\u{feff}fn foo() {}"
+    .encode_utf16()
+    .flat_map(u16::to_le_bytes)
+    .collect::<Vec<_>>();
+
+  Test::new()
+    .binary_file("foo.rs", &content)
+    .argument("--query")
+    .argument("((identifier) @id (#eq? @id \"foo\"))")
+    .argument("foo.rs")
+    .expected_stdout(
+      "
+      source_file [0:2..0:24]
+        function_item [0:2..0:24]
+          identifier [0:8..0:14] \"foo\"
+      ",
+    )
+    .run();
+}
+
 #[test]
 fn watch_requires_file() {
   Test::new()